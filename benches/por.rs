@@ -7,6 +7,8 @@ use rust_aes_proofs::por::aes_ni::AesNi;
 use rust_aes_proofs::por::aes_ni::AesNiKeys;
 use rust_aes_proofs::por::software_bit_slicing::SoftwareBitSlicing;
 use rust_aes_proofs::por::software_bit_slicing::SoftwareBitSlicingKeys;
+use rust_aes_proofs::por::software_fix_slicing::SoftwareFixSlicing;
+use rust_aes_proofs::por::software_fix_slicing::SoftwareFixSlicingKeys;
 use rust_aes_proofs::por::software_lut::SoftwareLuT;
 use rust_aes_proofs::por::software_lut::SoftwareLuTKeys;
 use rust_aes_proofs::por::vaes::VAes;
@@ -20,8 +22,8 @@ use test_data::PIECE;
 pub fn criterion_benchmark(c: &mut Criterion) {
     #[cfg(target_arch = "x86_64")]
     {
-        let keys = AesNiKeys::new(&ID);
-        let por = AesNi::new();
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let por = AesNi::new().unwrap();
 
         let mut group = c.benchmark_group("AES-NI");
         group.sample_size(10);
@@ -39,6 +41,17 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             })
         });
 
+        // Same number of pieces as x4 above
+        group.bench_function("PoR-128-encode-x8", |b| {
+            let mut pieces = [PIECE; 8];
+            let ivs = [IV; 8];
+            b.iter(|| {
+                for _ in 0..5 {
+                    por.encode_x8(&mut pieces, &keys, ivs, aes_iterations, breadth_iterations);
+                }
+            })
+        });
+
         group.bench_function("PoR-128-decode-single", |b| {
             let mut piece = PIECE;
             b.iter(|| {
@@ -48,11 +61,20 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             })
         });
 
+        group.bench_function("PoR-128-decode-single-x8", |b| {
+            let mut piece = PIECE;
+            b.iter(|| {
+                for _ in 0..10 {
+                    por.decode_x8(&mut piece, &keys, &IV, aes_iterations, breadth_iterations);
+                }
+            })
+        });
+
         group.bench_function("PoR-128-decode-parallel", |b| {
             let mut pieces = vec![PIECE; num_cpus::get_physical() * 10];
             b.iter(|| {
-                pieces.par_iter_mut().for_each(|mut piece| {
-                    por.decode(&mut piece, &keys, &IV, aes_iterations, breadth_iterations);
+                pieces.par_iter_mut().for_each(|piece| {
+                    por.decode(piece, &keys, &IV, aes_iterations, breadth_iterations);
                 });
             })
         });
@@ -102,6 +124,52 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
         group.finish();
     }
+    {
+        let keys = SoftwareFixSlicingKeys::new(&ID);
+        let por = SoftwareFixSlicing::new();
+
+        let mut group = c.benchmark_group("Software (fixslicing)");
+        group.sample_size(10);
+
+        let aes_iterations = 256;
+        let breadth_iterations = 16;
+
+        group.bench_function("PoR-128-encode", |b| {
+            let mut piece = PIECE;
+            b.iter(|| {
+                for _ in 0..10 {
+                    por.encode(&mut piece, &keys, IV, aes_iterations, breadth_iterations);
+                }
+            })
+        });
+
+        group.bench_function("PoR-128-encode-x4", |b| {
+            let mut pieces = [PIECE; 4];
+            b.iter(|| {
+                for _ in 0..10 {
+                    por.encode_x4(
+                        &mut pieces,
+                        &keys,
+                        [IV; 4],
+                        aes_iterations,
+                        breadth_iterations,
+                    );
+                }
+            })
+        });
+
+        // Here we use incorrect key, but performance should be identical
+        group.bench_function("PoR-128-decode", |b| {
+            let mut piece = PIECE;
+            b.iter(|| {
+                for _ in 0..10 {
+                    por.decode(&mut piece, &keys, &IV, aes_iterations, breadth_iterations);
+                }
+            })
+        });
+
+        group.finish();
+    }
     {
         let keys = SoftwareLuTKeys::new(&ID);
         let por = SoftwareLuT::new();
@@ -137,6 +205,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     {
         use rust_aes_proofs::por::opencl::OpenCL;
         use rust_aes_proofs::por::opencl::OpenCLKeys;
+        use rust_aes_proofs::por::opencl::OpenCLOptions;
+        use rust_aes_proofs::por::opencl::OpenCLStrategy;
 
         let keys = OpenCLKeys::new(&ID);
         let mut por = OpenCL::new().unwrap();
@@ -149,8 +219,15 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
         group.bench_function("PoR-128-encode-single", |b| {
             b.iter(|| {
-                por.encode(&PIECE, &[IV], &keys, aes_iterations, breadth_iterations)
-                    .unwrap();
+                por.encode(
+                    &PIECE,
+                    &[IV],
+                    &[&keys],
+                    None,
+                    aes_iterations,
+                    breadth_iterations,
+                )
+                .unwrap();
             })
         });
 
@@ -159,15 +236,29 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
         group.bench_function("PoR-128-encode-100", |b| {
             b.iter(|| {
-                por.encode(&pieces, &ivs, &keys, aes_iterations, breadth_iterations)
-                    .unwrap();
+                por.encode(
+                    &pieces,
+                    &ivs,
+                    &[&keys],
+                    None,
+                    aes_iterations,
+                    breadth_iterations,
+                )
+                .unwrap();
             })
         });
 
         group.bench_function("PoR-128-decode-single", |b| {
             b.iter(|| {
-                por.decode(&PIECE, &[IV], &keys, aes_iterations, breadth_iterations)
-                    .unwrap();
+                por.decode(
+                    &PIECE,
+                    &[IV],
+                    &[&keys],
+                    None,
+                    aes_iterations,
+                    breadth_iterations,
+                )
+                .unwrap();
             })
         });
 
@@ -175,11 +266,40 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
         group.bench_function("PoR-128-decode-100", |b| {
             b.iter(|| {
-                por.decode(&encodings, &ivs, &keys, aes_iterations, breadth_iterations)
-                    .unwrap();
+                por.decode(
+                    &encodings,
+                    &ivs,
+                    &[&keys],
+                    None,
+                    aes_iterations,
+                    breadth_iterations,
+                )
+                .unwrap();
             })
         });
 
+        for &strategy in OpenCLStrategy::ALL.iter() {
+            let mut por = OpenCL::with_options(OpenCLOptions {
+                strategy,
+                ..OpenCLOptions::default()
+            })
+            .unwrap();
+
+            group.bench_function(format!("PoR-128-encode-100-{:?}", strategy), |b| {
+                b.iter(|| {
+                    por.encode(
+                        &pieces,
+                        &ivs,
+                        &[&keys],
+                        None,
+                        aes_iterations,
+                        breadth_iterations,
+                    )
+                    .unwrap();
+                })
+            });
+        }
+
         group.finish();
     }
     #[cfg(target_arch = "x86_64")]
@@ -187,8 +307,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         if !utils::aes_implementations_available().contains(&AesImplementation::VAes) {
             println!("VAES support not available, skipping benchmarks");
         } else {
-            let keys = VAesKeys::new(&ID);
-            let por = VAes::new();
+            let keys = VAesKeys::new(&ID).unwrap();
+            let por = VAes::new().unwrap();
 
             let mut group = c.benchmark_group("VAES");
             group.sample_size(10);
@@ -218,8 +338,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             group.bench_function("PoR-128-decode-parallel", |b| {
                 let mut piece = vec![PIECE; num_cpus::get_physical() * 10];
                 b.iter(|| {
-                    piece.par_iter_mut().for_each(|mut piece| {
-                        por.decode(&mut piece, &keys, &IV, aes_iterations, breadth_iterations);
+                    piece.par_iter_mut().for_each(|piece| {
+                        por.decode(piece, &keys, &IV, aes_iterations, breadth_iterations);
                     });
                 })
             });
@@ -6,6 +6,8 @@ use criterion::criterion_main;
 use criterion::Criterion;
 use rust_aes_proofs::pot::aes_ni::AesNi;
 use rust_aes_proofs::pot::aes_ni::AesNiKeys;
+use rust_aes_proofs::pot::software_fix_slicing::SoftwareFixSlicing;
+use rust_aes_proofs::pot::software_fix_slicing::SoftwareFixSlicingKeys;
 use rust_aes_proofs::pot::vaes::VAes;
 use rust_aes_proofs::pot::vaes::VAesKeys;
 use rust_aes_proofs::utils;
@@ -13,8 +15,8 @@ use rust_aes_proofs::utils::AesImplementation;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     {
-        let keys = AesNiKeys::new(&ID);
-        let pot = AesNi::new();
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let pot = AesNi::new().unwrap();
 
         let base_aes_iterations = 3_000_000;
 
@@ -56,6 +58,18 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 },
             );
 
+            group.bench_function(
+                format!(
+                    "Verify-pipelined-x8-{}-iterations-{}-parallelism",
+                    aes_iterations, verifier_parallelism
+                ),
+                |b| {
+                    b.iter(|| {
+                        pot.verify_x8(&proof, &SEED, &keys, aes_iterations);
+                    })
+                },
+            );
+
             group.bench_function(
                 format!(
                     "Verify-pipelined-parallel-{}-iterations-{}-parallelism",
@@ -71,11 +85,50 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
         group.finish();
     }
+    {
+        let keys = SoftwareFixSlicingKeys::new(&ID);
+        let pot = SoftwareFixSlicing::new();
+
+        let aes_iterations = 3_000_000;
+
+        let mut group = c.benchmark_group("Software (fixslicing)");
+        group.sample_size(10);
+
+        for &verifier_parallelism in [4, 8, 12, 16].iter() {
+            group.bench_function(
+                format!(
+                    "Prove-{}-iterations-{}-parallelism",
+                    aes_iterations, verifier_parallelism
+                ),
+                |b| {
+                    b.iter(|| {
+                        pot.prove(&SEED, &keys, aes_iterations, verifier_parallelism);
+                    })
+                },
+            );
+
+            let proof = pot.prove(&SEED, &keys, aes_iterations, verifier_parallelism);
+
+            group.bench_function(
+                format!(
+                    "Verify-{}-iterations-{}-parallelism",
+                    aes_iterations, verifier_parallelism
+                ),
+                |b| {
+                    b.iter(|| {
+                        pot.verify(&proof, &SEED, &keys, aes_iterations);
+                    })
+                },
+            );
+        }
+
+        group.finish();
+    }
     if !utils::aes_implementations_available().contains(&AesImplementation::VAes) {
         println!("VAES support not available, skipping benchmarks");
     } else {
-        let keys = VAesKeys::new(&ID);
-        let pot = VAes::new();
+        let keys = VAesKeys::new(&ID).unwrap();
+        let pot = VAes::new().unwrap();
 
         let base_aes_iterations = 3_000_000;
 
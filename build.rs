@@ -1,10 +1,31 @@
-use cc::Build;
-
 fn main() {
-    #[cfg(target_arch = "x86_64")]
+    #[cfg(feature = "capi")]
     {
-        Build::new()
-            .file("src/aes_low_level/vaes.c")
-            .compile("vaes");
+        let crate_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+        let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+
+        // Only `src/capi.rs` is parsed, so nothing else from the crate ends up in the header
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+            .expect("Failed to read cbindgen config");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(crate_dir.join("src/capi.rs"))
+            .generate()
+            .expect("Failed to generate C header")
+            .write_to_file(out_dir.join("aes_proofs.h"));
+
+        // Only linked into tests of `capi` module rather than into the library itself
+        cc::Build::new()
+            .file("src/capi/test.c")
+            .include(&out_dir)
+            .warnings_into_errors(true)
+            .cargo_metadata(false)
+            .compile("aes_proofs_capi_test");
+        println!("cargo:rustc-link-search=native={}", out_dir.display());
+
+        // Header is generated from `src/capi.rs` alone, so these are all the inputs
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=src/capi/test.c");
     }
 }
@@ -0,0 +1,171 @@
+/* Generated by cbindgen from src/capi.rs with `capi` feature enabled, do not edit */
+
+#ifndef AES_PROOFS_H
+#define AES_PROOFS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Size of IDs, IVs and proof of time blocks in bytes
+ */
+#define AES_PROOFS_BLOCK_SIZE 16
+
+/**
+ * Size of proof of replication pieces in bytes
+ */
+#define AES_PROOFS_PIECE_SIZE 4096
+
+/**
+ * Result of every call
+ */
+typedef enum AesProofsStatus {
+  /**
+   * Success
+   */
+  AES_PROOFS_STATUS_OK = 0,
+  /**
+   * One of the pointers is null
+   */
+  AES_PROOFS_STATUS_NULL_POINTER = 1,
+  /**
+   * Buffer length is not a multiple of piece or block size or buffers don't match each other
+   */
+  AES_PROOFS_STATUS_INVALID_LENGTH = 2,
+  /**
+   * Unsupported number of iterations or verifier parallelism
+   */
+  AES_PROOFS_STATUS_INVALID_PARAMETER = 3,
+  /**
+   * Unexpected internal error, should never happen
+   */
+  AES_PROOFS_STATUS_INTERNAL_ERROR = 4,
+  /**
+   * None of the backends passed known-answer self-test, results can't be trusted
+   */
+  AES_PROOFS_STATUS_SELF_TEST_FAILED = 5,
+} AesProofsStatus;
+
+/**
+ * Opaque handle with keys derived from 16-byte ID, created with `aes_proofs_keys_new()` and
+ * freed with `aes_proofs_keys_free()`
+ */
+typedef struct AesProofsKeys AesProofsKeys;
+
+/**
+ * Opaque proof of replication handle that uses the fastest backend available on this machine,
+ * created with `aes_proofs_por_new()` and freed with `aes_proofs_por_free()`, must not be used
+ * from multiple threads at the same time
+ */
+typedef struct AesProofsPor AesProofsPor;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Derives keys from 16-byte `id` and writes the handle into `keys`.
+ *
+ * # Safety
+ * `id` must point to 16 readable bytes and `keys` must be valid for writes.
+ */
+enum AesProofsStatus aes_proofs_keys_new(const uint8_t *id, struct AesProofsKeys **keys);
+
+/**
+ * Frees keys (zeroizing them in the process), null is ignored.
+ *
+ * # Safety
+ * `keys` must be null or a handle returned by `aes_proofs_keys_new()` that wasn't freed yet.
+ */
+void aes_proofs_keys_free(struct AesProofsKeys *keys);
+
+/**
+ * Creates proof of replication handle and writes it into `por`.
+ *
+ * # Safety
+ * `por` must be valid for writes.
+ */
+enum AesProofsStatus aes_proofs_por_new(struct AesProofsPor **por);
+
+/**
+ * Frees proof of replication handle, null is ignored.
+ *
+ * # Safety
+ * `por` must be null or a handle returned by `aes_proofs_por_new()` that wasn't freed yet.
+ */
+void aes_proofs_por_free(struct AesProofsPor *por);
+
+/**
+ * Proof of replication encoding of `pieces_len / 4096` pieces in-place, each with its own 16-byte
+ * IV from `ivs`.
+ *
+ * # Safety
+ * `por` and `keys` must be live handles, `pieces` must point to `pieces_len` readable and writable
+ * bytes and `ivs` to `ivs_len` readable bytes.
+ */
+enum AesProofsStatus aes_proofs_por_encode(struct AesProofsPor *por,
+                                           const struct AesProofsKeys *keys,
+                                           uint8_t *pieces,
+                                           size_t pieces_len,
+                                           const uint8_t *ivs,
+                                           size_t ivs_len,
+                                           size_t aes_iterations,
+                                           size_t breadth_iterations);
+
+/**
+ * Proof of replication decoding of `pieces_len / 4096` pieces in-place, each with its own 16-byte
+ * IV from `ivs`.
+ *
+ * # Safety
+ * `por` and `keys` must be live handles, `pieces` must point to `pieces_len` readable and writable
+ * bytes and `ivs` to `ivs_len` readable bytes.
+ */
+enum AesProofsStatus aes_proofs_por_decode(struct AesProofsPor *por,
+                                           const struct AesProofsKeys *keys,
+                                           uint8_t *pieces,
+                                           size_t pieces_len,
+                                           const uint8_t *ivs,
+                                           size_t ivs_len,
+                                           size_t aes_iterations,
+                                           size_t breadth_iterations);
+
+/**
+ * Proof of time for 16-byte `seed`, writes `verifier_parallelism` blocks into `proof`
+ * (`proof_len` must be `verifier_parallelism * 16`). `aes_iterations` must be a multiple of 48
+ * and `verifier_parallelism` one of 4, 8, 12 or 16.
+ *
+ * # Safety
+ * `keys` must be a live handle, `seed` must point to 16 readable bytes and `proof` to `proof_len`
+ * writable bytes.
+ */
+enum AesProofsStatus aes_proofs_pot_prove(const struct AesProofsKeys *keys,
+                                          const uint8_t *seed,
+                                          size_t aes_iterations,
+                                          size_t verifier_parallelism,
+                                          uint8_t *proof,
+                                          size_t proof_len);
+
+/**
+ * Proof of time verification for 16-byte `seed`, writes whether `proof` is valid into `valid`.
+ * Same constraints as for `aes_proofs_pot_prove()` apply, with verifier parallelism being
+ * `proof_len / 16`.
+ *
+ * # Safety
+ * `keys` must be a live handle, `seed` must point to 16 readable bytes, `proof` to `proof_len`
+ * readable bytes and `valid` must be valid for writes.
+ */
+enum AesProofsStatus aes_proofs_pot_verify(const struct AesProofsKeys *keys,
+                                           const uint8_t *seed,
+                                           size_t aes_iterations,
+                                           const uint8_t *proof,
+                                           size_t proof_len,
+                                           bool *valid);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* AES_PROOFS_H */
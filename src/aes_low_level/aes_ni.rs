@@ -120,132 +120,326 @@ macro_rules! compare_eq4 {
     ($what:expr, $with:expr) => {{
         use core::arch::x86_64::*;
 
-        let mut value = [0u128];
-        _mm_storeu_si128(
-            value.as_mut_ptr() as *mut __m128i,
+        let mask = _mm_movemask_epi8(_mm_and_si128(
             _mm_and_si128(
-                _mm_and_si128(
-                    _mm_cmpeq_epi64($what[0], $with[0]),
-                    _mm_cmpeq_epi64($what[1], $with[1]),
-                ),
-                _mm_and_si128(
-                    _mm_cmpeq_epi64($what[2], $with[2]),
-                    _mm_cmpeq_epi64($what[3], $with[3]),
-                ),
+                _mm_cmpeq_epi8($what[0], $with[0]),
+                _mm_cmpeq_epi8($what[1], $with[1]),
             ),
-        );
-        value == [u128::max_value()]
+            _mm_and_si128(
+                _mm_cmpeq_epi8($what[2], $with[2]),
+                _mm_cmpeq_epi8($what[3], $with[3]),
+            ),
+        ));
+        mask == 0xFFFF
+    }};
+}
+
+// x8 macros process two halves of 4 blocks each, such that 8 independent blocks are in flight
+
+macro_rules! aes128_xor8 {
+    ($what:expr, $with:expr) => {{
+        aes128_xor4!($what[..4], $with);
+        aes128_xor4!($what[4..], $with);
+    }};
+}
+
+macro_rules! aes128_xor8x8 {
+    ($what:expr, $with:expr) => {{
+        aes128_xor4x4!($what[..4], $with[..4]);
+        aes128_xor4x4!($what[4..], $with[4..]);
+    }};
+}
+
+macro_rules! aes128_encode8 {
+    ($target:expr, $key:expr) => {{
+        aes128_encode4!($target[..4], $key);
+        aes128_encode4!($target[4..], $key);
+    }};
+}
+
+macro_rules! aes128_encode8_last {
+    ($target:expr, $key:expr) => {{
+        aes128_encode4_last!($target[..4], $key);
+        aes128_encode4_last!($target[4..], $key);
+    }};
+}
+
+macro_rules! aes128_decode8 {
+    ($target:expr, $key:expr) => {{
+        aes128_decode4!($target[..4], $key);
+        aes128_decode4!($target[4..], $key);
+    }};
+}
+
+macro_rules! aes128_decode8_last {
+    ($target:expr, $key:expr) => {{
+        aes128_decode4_last!($target[..4], $key);
+        aes128_decode4_last!($target[4..], $key);
+    }};
+}
+
+macro_rules! compare_eq8 {
+    ($what:expr, $with:expr) => {{
+        compare_eq4!($what[..4], $with[..4]) & compare_eq4!($what[4..], $with[4..])
     }};
 }
 
-pub fn por_encode_pipelined_x4_low_level(
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn por_encode_pipelined_x4_low_level(
     keys_reg: [__m128i; 11],
     blocks_reg: &mut [__m128i; 4],
     feedbacks_reg: [__m128i; 4],
     aes_iterations: usize,
 ) {
-    unsafe {
-        aes128_xor4x4!(blocks_reg, feedbacks_reg);
-
-        for _ in 0..aes_iterations {
-            aes128_xor4!(blocks_reg, keys_reg[0]);
-
-            aes128_encode4!(blocks_reg, keys_reg[1]);
-            aes128_encode4!(blocks_reg, keys_reg[2]);
-            aes128_encode4!(blocks_reg, keys_reg[3]);
-            aes128_encode4!(blocks_reg, keys_reg[4]);
-            aes128_encode4!(blocks_reg, keys_reg[5]);
-            aes128_encode4!(blocks_reg, keys_reg[6]);
-            aes128_encode4!(blocks_reg, keys_reg[7]);
-            aes128_encode4!(blocks_reg, keys_reg[8]);
-            aes128_encode4!(blocks_reg, keys_reg[9]);
-
-            aes128_encode4_last!(blocks_reg, keys_reg[10]);
-        }
+    aes128_xor4x4!(blocks_reg, feedbacks_reg);
+
+    for _ in 0..aes_iterations {
+        aes128_xor4!(blocks_reg, keys_reg[0]);
+
+        aes128_encode4!(blocks_reg, keys_reg[1]);
+        aes128_encode4!(blocks_reg, keys_reg[2]);
+        aes128_encode4!(blocks_reg, keys_reg[3]);
+        aes128_encode4!(blocks_reg, keys_reg[4]);
+        aes128_encode4!(blocks_reg, keys_reg[5]);
+        aes128_encode4!(blocks_reg, keys_reg[6]);
+        aes128_encode4!(blocks_reg, keys_reg[7]);
+        aes128_encode4!(blocks_reg, keys_reg[8]);
+        aes128_encode4!(blocks_reg, keys_reg[9]);
+
+        aes128_encode4_last!(blocks_reg, keys_reg[10]);
     }
 }
 
-pub fn por_decode_pipelined_x4_low_level(
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn por_decode_pipelined_x4_low_level(
     keys_reg: [__m128i; 11],
     blocks_reg: &mut [__m128i; 4],
     feedbacks_reg: [__m128i; 4],
     aes_iterations: usize,
 ) {
-    unsafe {
-        for _ in 0..aes_iterations {
-            aes128_xor4!(blocks_reg, keys_reg[10]);
-
-            aes128_decode4!(blocks_reg, keys_reg[9]);
-            aes128_decode4!(blocks_reg, keys_reg[8]);
-            aes128_decode4!(blocks_reg, keys_reg[7]);
-            aes128_decode4!(blocks_reg, keys_reg[6]);
-            aes128_decode4!(blocks_reg, keys_reg[5]);
-            aes128_decode4!(blocks_reg, keys_reg[4]);
-            aes128_decode4!(blocks_reg, keys_reg[3]);
-            aes128_decode4!(blocks_reg, keys_reg[2]);
-            aes128_decode4!(blocks_reg, keys_reg[1]);
-
-            aes128_decode4_last!(blocks_reg, keys_reg[0]);
-        }
-
-        aes128_xor4x4!(blocks_reg, feedbacks_reg);
+    for _ in 0..aes_iterations {
+        aes128_xor4!(blocks_reg, keys_reg[10]);
+
+        aes128_decode4!(blocks_reg, keys_reg[9]);
+        aes128_decode4!(blocks_reg, keys_reg[8]);
+        aes128_decode4!(blocks_reg, keys_reg[7]);
+        aes128_decode4!(blocks_reg, keys_reg[6]);
+        aes128_decode4!(blocks_reg, keys_reg[5]);
+        aes128_decode4!(blocks_reg, keys_reg[4]);
+        aes128_decode4!(blocks_reg, keys_reg[3]);
+        aes128_decode4!(blocks_reg, keys_reg[2]);
+        aes128_decode4!(blocks_reg, keys_reg[1]);
+
+        aes128_decode4_last!(blocks_reg, keys_reg[0]);
     }
+
+    aes128_xor4x4!(blocks_reg, feedbacks_reg);
 }
 
-pub fn pot_prove_low_level(
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn pot_prove_low_level(
     keys_reg: [__m128i; 11],
     mut block_reg: __m128i,
     inner_iterations: usize,
 ) -> __m128i {
-    unsafe {
-        for _ in 0..inner_iterations {
-            block_reg = _mm_xor_si128(block_reg, keys_reg[0]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[1]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[2]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[3]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[4]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[5]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[6]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[7]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[8]);
-            block_reg = _mm_aesenc_si128(block_reg, keys_reg[9]);
-
-            block_reg = _mm_aesenclast_si128(block_reg, keys_reg[10]);
-        }
+    for _ in 0..inner_iterations {
+        block_reg = _mm_xor_si128(block_reg, keys_reg[0]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[1]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[2]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[3]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[4]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[5]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[6]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[7]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[8]);
+        block_reg = _mm_aesenc_si128(block_reg, keys_reg[9]);
+
+        block_reg = _mm_aesenclast_si128(block_reg, keys_reg[10]);
     }
 
     block_reg
 }
 
-pub fn pot_verify_pipelined_x4_low_level(
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn pot_verify_pipelined_x4_low_level(
     keys_reg: [__m128i; 11],
     expected_reg: [__m128i; 4],
     mut blocks_reg: [__m128i; 4],
     aes_iterations: usize,
 ) -> bool {
-    unsafe {
-        for _ in 0..aes_iterations {
-            aes128_xor4!(blocks_reg, keys_reg[10]);
-
-            aes128_decode4!(blocks_reg, keys_reg[9]);
-            aes128_decode4!(blocks_reg, keys_reg[8]);
-            aes128_decode4!(blocks_reg, keys_reg[7]);
-            aes128_decode4!(blocks_reg, keys_reg[6]);
-            aes128_decode4!(blocks_reg, keys_reg[5]);
-            aes128_decode4!(blocks_reg, keys_reg[4]);
-            aes128_decode4!(blocks_reg, keys_reg[3]);
-            aes128_decode4!(blocks_reg, keys_reg[2]);
-            aes128_decode4!(blocks_reg, keys_reg[1]);
-
-            aes128_decode4_last!(blocks_reg, keys_reg[0]);
-        }
-
-        compare_eq4!(expected_reg, blocks_reg)
+    for _ in 0..aes_iterations {
+        aes128_xor4!(blocks_reg, keys_reg[10]);
+
+        aes128_decode4!(blocks_reg, keys_reg[9]);
+        aes128_decode4!(blocks_reg, keys_reg[8]);
+        aes128_decode4!(blocks_reg, keys_reg[7]);
+        aes128_decode4!(blocks_reg, keys_reg[6]);
+        aes128_decode4!(blocks_reg, keys_reg[5]);
+        aes128_decode4!(blocks_reg, keys_reg[4]);
+        aes128_decode4!(blocks_reg, keys_reg[3]);
+        aes128_decode4!(blocks_reg, keys_reg[2]);
+        aes128_decode4!(blocks_reg, keys_reg[1]);
+
+        aes128_decode4_last!(blocks_reg, keys_reg[0]);
+    }
+
+    compare_eq4!(expected_reg, blocks_reg)
+}
+
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn por_encode_pipelined_x8_low_level(
+    keys_reg: [__m128i; 11],
+    blocks_reg: &mut [__m128i; 8],
+    feedbacks_reg: [__m128i; 8],
+    aes_iterations: usize,
+) {
+    aes128_xor8x8!(blocks_reg, feedbacks_reg);
+
+    for _ in 0..aes_iterations {
+        aes128_xor8!(blocks_reg, keys_reg[0]);
+
+        aes128_encode8!(blocks_reg, keys_reg[1]);
+        aes128_encode8!(blocks_reg, keys_reg[2]);
+        aes128_encode8!(blocks_reg, keys_reg[3]);
+        aes128_encode8!(blocks_reg, keys_reg[4]);
+        aes128_encode8!(blocks_reg, keys_reg[5]);
+        aes128_encode8!(blocks_reg, keys_reg[6]);
+        aes128_encode8!(blocks_reg, keys_reg[7]);
+        aes128_encode8!(blocks_reg, keys_reg[8]);
+        aes128_encode8!(blocks_reg, keys_reg[9]);
+
+        aes128_encode8_last!(blocks_reg, keys_reg[10]);
+    }
+}
+
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn por_decode_pipelined_x8_low_level(
+    keys_reg: [__m128i; 11],
+    blocks_reg: &mut [__m128i; 8],
+    feedbacks_reg: [__m128i; 8],
+    aes_iterations: usize,
+) {
+    for _ in 0..aes_iterations {
+        aes128_xor8!(blocks_reg, keys_reg[10]);
+
+        aes128_decode8!(blocks_reg, keys_reg[9]);
+        aes128_decode8!(blocks_reg, keys_reg[8]);
+        aes128_decode8!(blocks_reg, keys_reg[7]);
+        aes128_decode8!(blocks_reg, keys_reg[6]);
+        aes128_decode8!(blocks_reg, keys_reg[5]);
+        aes128_decode8!(blocks_reg, keys_reg[4]);
+        aes128_decode8!(blocks_reg, keys_reg[3]);
+        aes128_decode8!(blocks_reg, keys_reg[2]);
+        aes128_decode8!(blocks_reg, keys_reg[1]);
+
+        aes128_decode8_last!(blocks_reg, keys_reg[0]);
+    }
+
+    aes128_xor8x8!(blocks_reg, feedbacks_reg);
+}
+
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn pot_verify_pipelined_x8_low_level(
+    keys_reg: [__m128i; 11],
+    expected_reg: [__m128i; 8],
+    mut blocks_reg: [__m128i; 8],
+    aes_iterations: usize,
+) -> bool {
+    for _ in 0..aes_iterations {
+        aes128_xor8!(blocks_reg, keys_reg[10]);
+
+        aes128_decode8!(blocks_reg, keys_reg[9]);
+        aes128_decode8!(blocks_reg, keys_reg[8]);
+        aes128_decode8!(blocks_reg, keys_reg[7]);
+        aes128_decode8!(blocks_reg, keys_reg[6]);
+        aes128_decode8!(blocks_reg, keys_reg[5]);
+        aes128_decode8!(blocks_reg, keys_reg[4]);
+        aes128_decode8!(blocks_reg, keys_reg[3]);
+        aes128_decode8!(blocks_reg, keys_reg[2]);
+        aes128_decode8!(blocks_reg, keys_reg[1]);
+
+        aes128_decode8_last!(blocks_reg, keys_reg[0]);
     }
+
+    compare_eq8!(expected_reg, blocks_reg)
+}
+
+/// Matyas–Meyer–Oseas compression of `data` (whole blocks) into `state`, every block is encrypted
+/// with the current state as a key and XORed with itself: `state = AES(state, block) ^ block`
+///
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn mmo_low_level(mut state: Block, data: &[u8]) -> Block {
+    for block in data.chunks_exact(16) {
+        let (keys_reg, _) = expand::expand(&state);
+        let block_reg = aes128_load!(block);
+
+        let mut state_reg = _mm_xor_si128(block_reg, keys_reg[0]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[1]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[2]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[3]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[4]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[5]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[6]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[7]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[8]);
+        state_reg = _mm_aesenc_si128(state_reg, keys_reg[9]);
+        state_reg = _mm_aesenclast_si128(state_reg, keys_reg[10]);
+
+        state_reg = _mm_xor_si128(state_reg, block_reg);
+        aes128_store!(state, state_reg);
+    }
+
+    state
 }
 
 pub type ExpandedKeys = [__m128i; 11];
 
-pub fn expand(key: &Block) -> (ExpandedKeys, ExpandedKeys) {
+/// Loads portable round keys into registers (only needs SSE2, which is always present on x86-64)
+pub fn expanded_keys_from_round_keys(round_keys: &[Block; 11]) -> ExpandedKeys {
+    let mut keys_reg = unsafe { [_mm_setzero_si128(); 11] };
+    keys_reg
+        .iter_mut()
+        .zip(round_keys.iter())
+        .for_each(|(key_reg, round_key)| {
+            *key_reg = unsafe { aes128_load!(round_key) };
+        });
+
+    keys_reg
+}
+
+/// Stores registers back into portable round keys
+#[cfg(test)]
+pub fn round_keys_from_expanded_keys(keys_reg: &ExpandedKeys) -> [Block; 11] {
+    let mut round_keys = [Block::default(); 11];
+    round_keys
+        .iter_mut()
+        .zip(keys_reg.iter())
+        .for_each(|(round_key, key_reg)| unsafe {
+            aes128_store!(round_key, *key_reg);
+        });
+
+    round_keys
+}
+
+/// # Safety
+/// CPU must support AES-NI, see [`crate::utils::ensure_available`]
+#[target_feature(enable = "aes,sse2")]
+pub unsafe fn expand(key: &Block) -> (ExpandedKeys, ExpandedKeys) {
     expand::expand(key)
 }
@@ -0,0 +1,605 @@
+//! Constant-time fixsliced AES-128 and AES-256 processing 4 blocks (lanes) at a time
+//!
+//! State of 4 blocks is bitsliced into 8 64-bit words, word `i` holds bit `i` of every byte and
+//! bit `4 * position + lane` of a word belongs to byte `position` (in the column-major order AES
+//! uses) of block `lane`. Only bitwise operations are used, so there are no memory accesses or
+//! branches that depend on keys or data.
+//!
+//! ShiftRows is never applied during rounds (fixslicing). Instead MixColumns takes bytes of each
+//! column from the positions they would have been moved to and round keys are stored shifted to
+//! match, so the state is only corrected once after the last round.
+
+use crate::Block;
+use crate::BLOCK_SIZE;
+
+/// Number of blocks processed at once
+pub const LANES: usize = 4;
+
+/// Bitsliced state of `LANES` blocks
+pub type State = [u64; 8];
+
+/// Lowest nibble of every 16-bit column, which is the first row of the column
+const ROW_0: u64 = 0x000F_000F_000F_000F;
+
+/// Bitslices `LANES` blocks
+pub fn bitslice(blocks: &[Block; LANES]) -> State {
+    let mut state = [0u64; 8];
+    for (lane, block) in blocks.iter().enumerate() {
+        for (position, &byte) in block.iter().enumerate() {
+            for (bit, word) in state.iter_mut().enumerate() {
+                *word |= u64::from((byte >> bit) & 1) << (position * LANES + lane);
+            }
+        }
+    }
+
+    state
+}
+
+/// Reverse of `bitslice()`
+pub fn un_bitslice(state: &State) -> [Block; LANES] {
+    let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+    for (lane, block) in blocks.iter_mut().enumerate() {
+        for (position, byte) in block.iter_mut().enumerate() {
+            for (bit, word) in state.iter().enumerate() {
+                *byte |= (((word >> (position * LANES + lane)) & 1) as u8) << bit;
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Constant-time AES-128 key expansion, returns round keys for encryption and for equivalent
+/// inverse cipher (in the same order as encryption keys)
+pub fn expand_keys_128(key: &Block) -> ([Block; 11], [Block; 11]) {
+    let keys_enc = expand_keys::<4, 11>(key);
+    let keys_dec = dec_round_keys(&keys_enc);
+
+    (keys_enc, keys_dec)
+}
+
+/// Constant-time AES-256 key expansion, same as `expand_keys_128()` otherwise
+pub fn expand_keys_256(key: &[u8; 32]) -> ([Block; 15], [Block; 15]) {
+    let keys_enc = expand_keys::<8, 15>(key);
+    let keys_dec = dec_round_keys(&keys_enc);
+
+    (keys_enc, keys_dec)
+}
+
+/// Converts round keys for encryption (as returned by `expand_keys_*()`) into the form
+/// `encrypt()` expects
+pub fn enc_keys<const N: usize>(round_keys: &[Block; N]) -> [State; N] {
+    let mut keys = [[0u64; 8]; N];
+    for (round, (key, round_key)) in keys.iter_mut().zip(round_keys.iter()).enumerate() {
+        *key = shift_rows(&bitslice(&[*round_key; LANES]), (4 - round % 4) % 4);
+    }
+
+    keys
+}
+
+/// Converts round keys for equivalent inverse cipher (as returned by `expand_keys_*()`) into the
+/// form `decrypt()` expects
+pub fn dec_keys<const N: usize>(round_keys: &[Block; N]) -> [State; N] {
+    let rounds = N - 1;
+    let mut keys = [[0u64; 8]; N];
+    for (round, (key, round_key)) in keys.iter_mut().zip(round_keys.iter()).enumerate() {
+        *key = shift_rows(&bitslice(&[*round_key; LANES]), (rounds - round) % 4);
+    }
+
+    keys
+}
+
+/// Encrypts all lanes of the state in place with keys from `enc_keys()`
+#[inline(always)]
+pub fn encrypt(keys: &[State], state: &mut State) {
+    let rounds = keys.len() - 1;
+
+    xor_in_place(state, &keys[0]);
+    for (round, key) in keys.iter().enumerate().take(rounds).skip(1) {
+        sub_bytes(state);
+        mix_columns(state, round % 4);
+        xor_in_place(state, key);
+    }
+    sub_bytes(state);
+    xor_in_place(state, &keys[rounds]);
+
+    *state = shift_rows(state, rounds % 4);
+}
+
+/// Decrypts all lanes of the state in place with keys from `dec_keys()`
+#[inline(always)]
+pub fn decrypt(keys: &[State], state: &mut State) {
+    let rounds = keys.len() - 1;
+
+    xor_in_place(state, &keys[rounds]);
+    for (round, key) in keys.iter().enumerate().take(rounds).skip(1).rev() {
+        inv_sub_bytes(state);
+        inv_mix_columns(state, (4 - (rounds - round) % 4) % 4);
+        xor_in_place(state, key);
+    }
+    inv_sub_bytes(state);
+    xor_in_place(state, &keys[0]);
+
+    *state = shift_rows(state, (4 - rounds % 4) % 4);
+}
+
+fn expand_keys<const NK: usize, const N: usize>(key: &[u8]) -> [Block; N] {
+    // Enough words for AES-256
+    let mut words = [[0u8; 4]; 60];
+    let words_count = N * 4;
+
+    for (word, chunk) in words.iter_mut().zip(key.chunks_exact(4)).take(NK) {
+        word.copy_from_slice(chunk);
+    }
+
+    let mut rcon = 1u8;
+    for i in NK..words_count {
+        let mut temp = words[i - 1];
+        if i % NK == 0 {
+            temp.rotate_left(1);
+            temp = sub_word(temp);
+            temp[0] ^= rcon;
+            rcon = (rcon << 1) ^ (0x1b * (rcon >> 7));
+        } else if NK > 6 && i % NK == 4 {
+            temp = sub_word(temp);
+        }
+        let previous = words[i - NK];
+        for (byte, (previous, temp)) in words[i].iter_mut().zip(previous.iter().zip(&temp)) {
+            *byte = previous ^ temp;
+        }
+    }
+
+    let mut round_keys = [[0u8; BLOCK_SIZE]; N];
+    for (round_key, words) in round_keys.iter_mut().zip(words.chunks_exact(4)) {
+        for (bytes, word) in round_key.chunks_exact_mut(4).zip(words) {
+            bytes.copy_from_slice(word);
+        }
+    }
+
+    words.iter_mut().for_each(|word| *word = [0u8; 4]);
+
+    round_keys
+}
+
+fn sub_word(word: [u8; 4]) -> [u8; 4] {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..4].copy_from_slice(&word);
+
+    let mut state = bitslice(&[
+        block,
+        [0u8; BLOCK_SIZE],
+        [0u8; BLOCK_SIZE],
+        [0u8; BLOCK_SIZE],
+    ]);
+    sub_bytes(&mut state);
+    let [block, ..] = un_bitslice(&state);
+
+    [block[0], block[1], block[2], block[3]]
+}
+
+/// Applies InvMixColumns to all round keys except the first and the last one
+fn dec_round_keys<const N: usize>(keys_enc: &[Block; N]) -> [Block; N] {
+    let mut keys_dec = *keys_enc;
+    for round_key in keys_dec.iter_mut().take(N - 1).skip(1) {
+        let mut state = bitslice(&[*round_key; LANES]);
+        inv_mix_columns(&mut state, 0);
+        let [key, ..] = un_bitslice(&state);
+        *round_key = key;
+    }
+
+    keys_dec
+}
+
+#[inline(always)]
+fn xor_in_place(state: &mut State, other: &State) {
+    for (word, other) in state.iter_mut().zip(other.iter()) {
+        *word ^= other;
+    }
+}
+
+/// Every byte of the result comes from the byte `rows` rows below in the same column (wrapping
+/// around)
+#[inline(always)]
+fn rotate_rows(word: u64, rows: usize) -> u64 {
+    let low = 0x0001_0001_0001_0001 * (0xFFFF >> (4 * rows));
+    ((word >> (4 * rows)) & low) | ((word << (16 - 4 * rows)) & !low)
+}
+
+/// Every byte of the result comes from the byte `rows` rows below and `rows * shift` columns to
+/// the right (wrapping around), which is the byte that is `rows` rows below in the same column
+/// after `shift` ShiftRows that were skipped
+#[inline(always)]
+fn rotate_rows_and_columns(word: u64, rows: usize, shift: usize) -> u64 {
+    rotate_rows(word, rows).rotate_right((16 * (rows * shift % 4)) as u32)
+}
+
+/// ShiftRows applied `shift` times
+fn shift_rows(state: &State, shift: usize) -> State {
+    let mut result = [0u64; 8];
+    for (result, &word) in result.iter_mut().zip(state.iter()) {
+        *result = (0..4)
+            .map(|row| (word & (ROW_0 << (4 * row))).rotate_right((16 * (row * shift % 4)) as u32))
+            .fold(0, |result, word| result | word);
+    }
+
+    result
+}
+
+/// Multiplication by `x` in AES field
+#[inline(always)]
+fn xtime(state: &State) -> State {
+    [
+        state[7],
+        state[0] ^ state[7],
+        state[1],
+        state[2] ^ state[7],
+        state[3] ^ state[7],
+        state[4],
+        state[5],
+        state[6],
+    ]
+}
+
+/// MixColumns on the state that is `shift` ShiftRows behind
+#[inline(always)]
+fn mix_columns(state: &mut State, shift: usize) {
+    let mut rotated_1 = [0u64; 8];
+    let mut t = [0u64; 8];
+    for ((rotated_1, t), &word) in rotated_1.iter_mut().zip(t.iter_mut()).zip(state.iter()) {
+        *rotated_1 = rotate_rows_and_columns(word, 1, shift);
+        *t = word ^ *rotated_1;
+    }
+
+    // out[r] = 2 * (a[r] ^ a[r + 1]) ^ a[r + 1] ^ (a[r + 2] ^ a[r + 3])
+    let t2 = xtime(&t);
+    for (((word, t2), rotated_1), t) in state.iter_mut().zip(&t2).zip(&rotated_1).zip(&t) {
+        *word = t2 ^ rotated_1 ^ rotate_rows_and_columns(*t, 2, shift);
+    }
+}
+
+/// InvMixColumns on the state that is `shift` ShiftRows behind
+#[inline(always)]
+fn inv_mix_columns(state: &mut State, shift: usize) {
+    // InvMixColumns is MixColumns after adding 4 * (a[r] ^ a[r + 2]) to each byte
+    let mut t = [0u64; 8];
+    for (t, &word) in t.iter_mut().zip(state.iter()) {
+        *t = word ^ rotate_rows_and_columns(word, 2, shift);
+    }
+    let t4 = xtime(&xtime(&t));
+    xor_in_place(state, &t4);
+
+    mix_columns(state, shift);
+}
+
+/// Inverse of S-box affine transformation, including the constant
+#[inline(always)]
+fn inv_affine(state: &mut State) {
+    let s = *state;
+    for (bit, word) in state.iter_mut().enumerate() {
+        *word = s[(bit + 2) % 8] ^ s[(bit + 5) % 8] ^ s[(bit + 7) % 8];
+    }
+    state[0] = !state[0];
+    state[2] = !state[2];
+}
+
+/// InvSubBytes expressed through SubBytes: S-box is an inversion followed by an affine
+/// transformation, so inverse S-box is an inverse affine transformation, inversion and inverse
+/// affine transformation again (inversion itself being S-box followed by inverse affine
+/// transformation)
+#[inline(always)]
+fn inv_sub_bytes(state: &mut State) {
+    inv_affine(state);
+    sub_bytes(state);
+    inv_affine(state);
+}
+
+/// Bitsliced SubBytes using circuit by Boyar and Peralta
+#[inline(always)]
+fn sub_bytes(state: &mut State) {
+    let u0 = state[7];
+    let u1 = state[6];
+    let u2 = state[5];
+    let u3 = state[4];
+    let u4 = state[3];
+    let u5 = state[2];
+    let u6 = state[1];
+    let u7 = state[0];
+
+    // Top linear transformation
+    let t1 = u0 ^ u3;
+    let t2 = u0 ^ u5;
+    let t3 = u0 ^ u6;
+    let t4 = u3 ^ u5;
+    let t5 = u4 ^ u6;
+    let t6 = t1 ^ t5;
+    let t7 = u1 ^ u2;
+    let t8 = u7 ^ t6;
+    let t9 = u7 ^ t7;
+    let t10 = t6 ^ t7;
+    let t11 = u1 ^ u5;
+    let t12 = u2 ^ u5;
+    let t13 = t3 ^ t4;
+    let t14 = t6 ^ t11;
+    let t15 = t5 ^ t11;
+    let t16 = t5 ^ t12;
+    let t17 = t9 ^ t16;
+    let t18 = u3 ^ u7;
+    let t19 = t7 ^ t18;
+    let t20 = t1 ^ t19;
+    let t21 = u6 ^ u7;
+    let t22 = t7 ^ t21;
+    let t23 = t2 ^ t22;
+    let t24 = t2 ^ t10;
+    let t25 = t20 ^ t17;
+    let t26 = t3 ^ t16;
+    let t27 = t1 ^ t12;
+
+    // Non-linear middle part
+    let m1 = t13 & t6;
+    let m2 = t23 & t8;
+    let m3 = t14 ^ m1;
+    let m4 = t19 & u7;
+    let m5 = m4 ^ m1;
+    let m6 = t3 & t16;
+    let m7 = t22 & t9;
+    let m8 = t26 ^ m6;
+    let m9 = t20 & t17;
+    let m10 = m9 ^ m6;
+    let m11 = t1 & t15;
+    let m12 = t4 & t27;
+    let m13 = m12 ^ m11;
+    let m14 = t2 & t10;
+    let m15 = m14 ^ m11;
+    let m16 = m3 ^ m2;
+    let m17 = m5 ^ t24;
+    let m18 = m8 ^ m7;
+    let m19 = m10 ^ m15;
+    let m20 = m16 ^ m13;
+    let m21 = m17 ^ m15;
+    let m22 = m18 ^ m13;
+    let m23 = m19 ^ t25;
+    let m24 = m22 ^ m23;
+    let m25 = m22 & m20;
+    let m26 = m21 ^ m25;
+    let m27 = m20 ^ m21;
+    let m28 = m23 ^ m25;
+    let m29 = m28 & m27;
+    let m30 = m26 & m24;
+    let m31 = m20 & m23;
+    let m32 = m27 & m31;
+    let m33 = m27 ^ m25;
+    let m34 = m21 & m22;
+    let m35 = m24 & m34;
+    let m36 = m24 ^ m25;
+    let m37 = m21 ^ m29;
+    let m38 = m32 ^ m33;
+    let m39 = m23 ^ m30;
+    let m40 = m35 ^ m36;
+    let m41 = m38 ^ m40;
+    let m42 = m37 ^ m39;
+    let m43 = m37 ^ m38;
+    let m44 = m39 ^ m40;
+    let m45 = m42 ^ m41;
+    let m46 = m44 & t6;
+    let m47 = m40 & t8;
+    let m48 = m39 & u7;
+    let m49 = m43 & t16;
+    let m50 = m38 & t9;
+    let m51 = m37 & t17;
+    let m52 = m42 & t15;
+    let m53 = m45 & t27;
+    let m54 = m41 & t10;
+    let m55 = m44 & t13;
+    let m56 = m40 & t23;
+    let m57 = m39 & t19;
+    let m58 = m43 & t3;
+    let m59 = m38 & t22;
+    let m60 = m37 & t20;
+    let m61 = m42 & t1;
+    let m62 = m45 & t4;
+    let m63 = m41 & t2;
+
+    // Bottom linear transformation
+    let l0 = m61 ^ m62;
+    let l1 = m50 ^ m56;
+    let l2 = m46 ^ m48;
+    let l3 = m47 ^ m55;
+    let l4 = m54 ^ m58;
+    let l5 = m49 ^ m61;
+    let l6 = m62 ^ l5;
+    let l7 = m46 ^ l3;
+    let l8 = m51 ^ m59;
+    let l9 = m52 ^ m53;
+    let l10 = m53 ^ l4;
+    let l11 = m60 ^ l2;
+    let l12 = m48 ^ m51;
+    let l13 = m50 ^ l0;
+    let l14 = m52 ^ m61;
+    let l15 = m55 ^ l1;
+    let l16 = m56 ^ l0;
+    let l17 = m57 ^ l1;
+    let l18 = m58 ^ l8;
+    let l19 = m63 ^ l4;
+    let l20 = l0 ^ l1;
+    let l21 = l1 ^ l7;
+    let l22 = l3 ^ l12;
+    let l23 = l18 ^ l2;
+    let l24 = l15 ^ l9;
+    let l25 = l6 ^ l10;
+    let l26 = l7 ^ l9;
+    let l27 = l8 ^ l10;
+    let l28 = l11 ^ l14;
+    let l29 = l11 ^ l17;
+
+    state[7] = l6 ^ l24;
+    state[6] = !(l16 ^ l26);
+    state[5] = !(l19 ^ l28);
+    state[4] = l6 ^ l21;
+    state[3] = l20 ^ l22;
+    state[2] = l25 ^ l29;
+    state[1] = !(l13 ^ l27);
+    state[0] = !(l6 ^ l23);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_low_level::software;
+    use rand::Rng;
+
+    // FIPS-197 Appendix C.1
+    const PLAINTEXT: Block = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ];
+    const KEY_128: Block = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    const CIPHERTEXT_128: Block = [
+        0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5,
+        0x5a,
+    ];
+    // FIPS-197 Appendix C.3
+    const KEY_256: [u8; 32] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+    const CIPHERTEXT_256: Block = [
+        0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49, 0x60,
+        0x89,
+    ];
+
+    #[test]
+    fn test_sub_bytes() {
+        let mut inputs = [[0u8; BLOCK_SIZE]; LANES];
+        for (byte, input) in inputs.iter_mut().flatten().enumerate() {
+            *input = byte as u8;
+        }
+
+        for offset in (0..=255).step_by(BLOCK_SIZE * LANES) {
+            let mut blocks = inputs;
+            blocks
+                .iter_mut()
+                .flatten()
+                .for_each(|byte| *byte = byte.wrapping_add(offset as u8));
+
+            let mut state = bitslice(&blocks);
+            sub_bytes(&mut state);
+            let substituted = un_bitslice(&state);
+            for (input, output) in blocks.iter().flatten().zip(substituted.iter().flatten()) {
+                assert_eq!(software::SBOX[*input as usize], *output);
+            }
+
+            inv_sub_bytes(&mut state);
+            assert_eq!(un_bitslice(&state), blocks);
+        }
+    }
+
+    #[test]
+    fn test_bitslice() {
+        let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+        blocks
+            .iter_mut()
+            .for_each(|block| rand::thread_rng().fill(&mut block[..]));
+
+        assert_eq!(un_bitslice(&bitslice(&blocks)), blocks);
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand_keys_128(&KEY_128),
+            (
+                software::expand_keys_aes_128_enc(&KEY_128),
+                software::expand_keys_aes_128_dec(&KEY_128)
+            )
+        );
+
+        for _ in 0..100 {
+            let mut key = [0u8; 16];
+            rand::thread_rng().fill(&mut key[..]);
+
+            assert_eq!(
+                expand_keys_128(&key),
+                (
+                    software::expand_keys_aes_128_enc(&key),
+                    software::expand_keys_aes_128_dec(&key)
+                )
+            );
+        }
+
+        // FIPS-197 Appendix A.3
+        let (keys_enc, _) = expand_keys_256(&KEY_256);
+        assert_eq!(
+            keys_enc[14],
+            [
+                0x24, 0xfc, 0x79, 0xcc, 0xbf, 0x09, 0x79, 0xe9, 0x37, 0x1a, 0xc2, 0x3c, 0x6d, 0x68,
+                0xde, 0x36
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aes_128() {
+        let (keys_enc, keys_dec) = expand_keys_128(&KEY_128);
+        let keys_enc = enc_keys(&keys_enc);
+        let keys_dec = dec_keys(&keys_dec);
+
+        let mut state = bitslice(&[PLAINTEXT, [0u8; BLOCK_SIZE], PLAINTEXT, [0xff; BLOCK_SIZE]]);
+        encrypt(&keys_enc, &mut state);
+        let blocks = un_bitslice(&state);
+        assert_eq!(blocks[0], CIPHERTEXT_128);
+        assert_eq!(blocks[2], CIPHERTEXT_128);
+
+        decrypt(&keys_dec, &mut state);
+        assert_eq!(
+            un_bitslice(&state),
+            [PLAINTEXT, [0u8; BLOCK_SIZE], PLAINTEXT, [0xff; BLOCK_SIZE]]
+        );
+    }
+
+    #[test]
+    fn test_aes_256() {
+        let (keys_enc, keys_dec) = expand_keys_256(&KEY_256);
+        let keys_enc = enc_keys(&keys_enc);
+        let keys_dec = dec_keys(&keys_dec);
+
+        let mut state = bitslice(&[PLAINTEXT; LANES]);
+        encrypt(&keys_enc, &mut state);
+        assert_eq!(un_bitslice(&state), [CIPHERTEXT_256; LANES]);
+
+        decrypt(&keys_dec, &mut state);
+        assert_eq!(un_bitslice(&state), [PLAINTEXT; LANES]);
+    }
+
+    #[test]
+    fn test_random() {
+        for _ in 0..10 {
+            let mut key = [0u8; 16];
+            rand::thread_rng().fill(&mut key[..]);
+
+            let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+            blocks
+                .iter_mut()
+                .for_each(|block| rand::thread_rng().fill(&mut block[..]));
+
+            let (round_keys_enc, round_keys_dec) = expand_keys_128(&key);
+            let mut keys_enc = [0u32; 44];
+            aes_frast::aes_core::setkey_enc_k128(&key, &mut keys_enc);
+
+            let mut state = bitslice(&blocks);
+            encrypt(&enc_keys(&round_keys_enc), &mut state);
+
+            for (block, encrypted) in blocks.iter().zip(un_bitslice(&state).iter()) {
+                let mut expected = [0u8; BLOCK_SIZE];
+                aes_frast::aes_core::block_enc_k128(block, &mut expected, &keys_enc);
+                assert_eq!(*encrypted, expected);
+            }
+
+            decrypt(&dec_keys(&round_keys_dec), &mut state);
+            assert_eq!(un_bitslice(&state), blocks);
+        }
+    }
+}
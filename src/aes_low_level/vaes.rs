@@ -1,7 +1,11 @@
 use crate::aes_low_level::aes_ni::ExpandedKeys;
+use crate::utils::AesImplementation;
 use crate::Block;
 use crate::BLOCK_SIZE;
-use std::io::Write;
+use core::arch::x86_64::*;
+
+/// Round keys broadcast to all 4 lanes of 512-bit register
+type BroadcastKeys = [__m512i; 11];
 
 pub fn por_encode_pipelined_x12_low_level(
     keys: &ExpandedKeys,
@@ -9,6 +13,7 @@ pub fn por_encode_pipelined_x12_low_level(
     feedbacks: &[Block; 12],
     aes_iterations: usize,
 ) {
+    assert_vaes_available();
     for block in blocks.iter() {
         assert!(
             block.len() == BLOCK_SIZE,
@@ -17,80 +22,31 @@ pub fn por_encode_pipelined_x12_low_level(
         );
     }
 
-    let mut blocks_0 = [0u8; BLOCK_SIZE * 4];
-    blocks_0
-        .chunks_exact_mut(BLOCK_SIZE)
-        .zip(blocks[..4].iter())
-        .for_each(|(chunk, block)| {
-            chunk.as_mut().write_all(block.as_ref()).unwrap();
-        });
-    let mut blocks_1 = [0u8; BLOCK_SIZE * 4];
-    blocks_1
-        .chunks_exact_mut(BLOCK_SIZE)
-        .zip(blocks[4..8].iter())
-        .for_each(|(chunk, block)| {
-            chunk.as_mut().write_all(block.as_ref()).unwrap();
-        });
-    let mut blocks_2 = [0u8; BLOCK_SIZE * 4];
-    blocks_2
+    let mut blocks_flat = [0u8; BLOCK_SIZE * 12];
+    blocks_flat
         .chunks_exact_mut(BLOCK_SIZE)
-        .zip(blocks[8..].iter())
+        .zip(blocks.iter())
         .for_each(|(chunk, block)| {
-            chunk.as_mut().write_all(block.as_ref()).unwrap();
+            chunk.copy_from_slice(block);
         });
 
-    let mut feedbacks_0 = [0u8; BLOCK_SIZE * 4];
-    feedbacks_0
+    let mut feedbacks_flat = [0u8; BLOCK_SIZE * 12];
+    feedbacks_flat
         .chunks_exact_mut(BLOCK_SIZE)
-        .zip(feedbacks[..4].iter())
+        .zip(feedbacks.iter())
         .for_each(|(chunk, feedback)| {
-            chunk.as_mut().write_all(feedback.as_ref()).unwrap();
-        });
-    let mut feedbacks_1 = [0u8; BLOCK_SIZE * 4];
-    feedbacks_1
-        .chunks_exact_mut(BLOCK_SIZE)
-        .zip(feedbacks[4..8].iter())
-        .for_each(|(chunk, feedback)| {
-            chunk.as_mut().write_all(feedback.as_ref()).unwrap();
-        });
-    let mut feedbacks_2 = [0u8; BLOCK_SIZE * 4];
-    feedbacks_2
-        .chunks_exact_mut(BLOCK_SIZE)
-        .zip(feedbacks[8..].iter())
-        .for_each(|(chunk, feedback)| {
-            chunk.as_mut().write_all(feedback.as_ref()).unwrap();
+            chunk.copy_from_slice(feedback);
         });
 
     unsafe {
-        c_exports::por_encode_pipelined_x12_low_level(
-            blocks_0.as_mut_ptr(),
-            blocks_1.as_mut_ptr(),
-            blocks_2.as_mut_ptr(),
-            feedbacks_0.as_ptr(),
-            feedbacks_1.as_ptr(),
-            feedbacks_2.as_ptr(),
-            keys.as_ptr(),
-            aes_iterations,
-        );
+        por_encode_x4n::<3>(keys, &mut blocks_flat, &feedbacks_flat, aes_iterations);
     }
 
-    blocks[..4]
+    blocks
         .iter_mut()
-        .zip(blocks_0.chunks_exact(BLOCK_SIZE))
+        .zip(blocks_flat.chunks_exact(BLOCK_SIZE))
         .for_each(|(block, chunk)| {
-            block.as_mut().write_all(&chunk).unwrap();
-        });
-    blocks[4..8]
-        .iter_mut()
-        .zip(blocks_1.chunks_exact(BLOCK_SIZE))
-        .for_each(|(block, chunk)| {
-            block.as_mut().write_all(&chunk).unwrap();
-        });
-    blocks[8..]
-        .iter_mut()
-        .zip(blocks_2.chunks_exact(BLOCK_SIZE))
-        .for_each(|(block, chunk)| {
-            block.as_mut().write_all(&chunk).unwrap();
+            block.copy_from_slice(chunk);
         });
 }
 
@@ -100,6 +56,7 @@ pub fn por_decode_pipelined_x12_low_level(
     feedbacks: &[u8],
     aes_iterations: usize,
 ) {
+    assert_vaes_available();
     assert!(
         blocks.len() == BLOCK_SIZE * 12,
         "Blocks length must be exactly 12 blocks",
@@ -110,12 +67,7 @@ pub fn por_decode_pipelined_x12_low_level(
     );
 
     unsafe {
-        c_exports::por_decode_pipelined_x12_low_level(
-            blocks.as_mut_ptr(),
-            feedbacks.as_ptr(),
-            keys.as_ptr(),
-            aes_iterations,
-        );
+        por_decode_x4n::<3>(keys, blocks, feedbacks, aes_iterations);
     }
 }
 
@@ -125,6 +77,7 @@ pub fn por_decode_x4_low_level(
     feedbacks: &[u8],
     aes_iterations: usize,
 ) {
+    assert_vaes_available();
     assert!(
         blocks.len() == BLOCK_SIZE * 4,
         "Blocks length must be exactly 4 blocks",
@@ -135,12 +88,7 @@ pub fn por_decode_x4_low_level(
     );
 
     unsafe {
-        c_exports::por_decode_x4_low_level(
-            blocks.as_mut_ptr(),
-            feedbacks.as_ptr(),
-            keys.as_ptr(),
-            aes_iterations,
-        );
+        por_decode_x4n::<1>(keys, blocks, feedbacks, aes_iterations);
     }
 }
 
@@ -150,6 +98,7 @@ pub fn pot_verify_pipelined_x12_low_level(
     blocks: &[u8],
     aes_iterations: usize,
 ) -> bool {
+    assert_vaes_available();
     assert!(
         blocks.len() == BLOCK_SIZE * 12,
         "Blocks length must be exactly 12 blocks",
@@ -159,24 +108,7 @@ pub fn pot_verify_pipelined_x12_low_level(
         "Expected first block length is incorrect",
     );
 
-    let mut expected_first_4_blocks = [0u8; BLOCK_SIZE * 4];
-    expected_first_4_blocks
-        .as_mut()
-        .write_all(expected_first_block)
-        .unwrap();
-    expected_first_4_blocks[BLOCK_SIZE..]
-        .as_mut()
-        .write_all(&blocks[..(blocks.len() - BLOCK_SIZE)])
-        .unwrap();
-
-    unsafe {
-        c_exports::pot_verify_pipelined_x12_low_level(
-            blocks.as_ptr(),
-            expected_first_4_blocks.as_ptr(),
-            keys.as_ptr(),
-            aes_iterations,
-        ) == u8::max_value()
-    }
+    unsafe { pot_verify_x4n::<3>(keys, expected_first_block, blocks, aes_iterations) }
 }
 
 pub fn pot_verify_pipelined_x8_low_level(
@@ -185,6 +117,7 @@ pub fn pot_verify_pipelined_x8_low_level(
     blocks: &[u8],
     aes_iterations: usize,
 ) -> bool {
+    assert_vaes_available();
     assert!(
         blocks.len() == BLOCK_SIZE * 8,
         "Blocks length must be exactly 8 blocks",
@@ -194,24 +127,7 @@ pub fn pot_verify_pipelined_x8_low_level(
         "Expected first block length is incorrect",
     );
 
-    let mut expected_first_4_blocks = [0u8; BLOCK_SIZE * 4];
-    expected_first_4_blocks
-        .as_mut()
-        .write_all(expected_first_block)
-        .unwrap();
-    expected_first_4_blocks[BLOCK_SIZE..]
-        .as_mut()
-        .write_all(&blocks[..(blocks.len() - BLOCK_SIZE)])
-        .unwrap();
-
-    unsafe {
-        c_exports::pot_verify_pipelined_x8_low_level(
-            blocks.as_ptr(),
-            expected_first_4_blocks.as_ptr(),
-            keys.as_ptr(),
-            aes_iterations,
-        ) == u8::max_value()
-    }
+    unsafe { pot_verify_x4n::<2>(keys, expected_first_block, blocks, aes_iterations) }
 }
 
 pub fn pot_verify_x4_low_level(
@@ -220,6 +136,7 @@ pub fn pot_verify_x4_low_level(
     blocks: &[u8],
     aes_iterations: usize,
 ) -> bool {
+    assert_vaes_available();
     assert!(
         blocks.len() == BLOCK_SIZE * 4,
         "Blocks length must be exactly 4 blocks",
@@ -229,76 +146,302 @@ pub fn pot_verify_x4_low_level(
         "Expected first block length is incorrect",
     );
 
-    let mut expected_blocks = [0u8; BLOCK_SIZE * 4];
-    expected_blocks
-        .as_mut()
-        .write_all(expected_first_block)
-        .unwrap();
-    expected_blocks[BLOCK_SIZE..]
-        .as_mut()
-        .write_all(&blocks[..(blocks.len() - BLOCK_SIZE)])
-        .unwrap();
+    unsafe { pot_verify_x4n::<1>(keys, expected_first_block, blocks, aes_iterations) }
+}
 
-    unsafe {
-        c_exports::pot_verify_x4_low_level(
-            blocks.as_ptr(),
-            expected_blocks.as_ptr(),
-            keys.as_ptr(),
-            aes_iterations,
-        ) == u8::max_value()
+/// Functions below are compiled with VAES enabled, calling them on a CPU without it is undefined
+/// behavior, hence this check in every public function
+fn assert_vaes_available() {
+    assert!(
+        crate::utils::ensure_available(AesImplementation::VAes).is_ok(),
+        "VAES is not available on this CPU",
+    );
+}
+
+/// Loads `N` registers of 4 blocks each, `bytes` must be exactly `N * 4` blocks long
+#[target_feature(enable = "avx512f")]
+unsafe fn load<const N: usize>(bytes: &[u8]) -> [__m512i; N] {
+    debug_assert_eq!(bytes.len(), BLOCK_SIZE * 4 * N);
+
+    let mut registers = [_mm512_setzero_si512(); N];
+    registers
+        .iter_mut()
+        .zip(bytes.chunks_exact(BLOCK_SIZE * 4))
+        .for_each(|(register, chunk)| {
+            *register = _mm512_loadu_si512(chunk.as_ptr() as *const __m512i);
+        });
+
+    registers
+}
+
+/// Stores `N` registers of 4 blocks each, `bytes` must be exactly `N * 4` blocks long
+#[target_feature(enable = "avx512f")]
+unsafe fn store<const N: usize>(bytes: &mut [u8], registers: &[__m512i; N]) {
+    debug_assert_eq!(bytes.len(), BLOCK_SIZE * 4 * N);
+
+    registers
+        .iter()
+        .zip(bytes.chunks_exact_mut(BLOCK_SIZE * 4))
+        .for_each(|(register, chunk)| {
+            _mm512_storeu_si512(chunk.as_mut_ptr() as *mut __m512i, *register);
+        });
+}
+
+#[target_feature(enable = "avx512f")]
+unsafe fn broadcast_keys(keys: &ExpandedKeys) -> BroadcastKeys {
+    let mut broadcast_keys = [_mm512_setzero_si512(); 11];
+    broadcast_keys
+        .iter_mut()
+        .zip(keys.iter())
+        .for_each(|(broadcast_key, key)| {
+            *broadcast_key = _mm512_broadcast_i32x4(*key);
+        });
+
+    broadcast_keys
+}
+
+/// AES-128 encryption of `N` registers `aes_iterations` times, rounds of different registers are
+/// interleaved to keep pipeline busy
+#[target_feature(enable = "avx512f,vaes")]
+unsafe fn encode_x4n<const N: usize>(
+    registers: &mut [__m512i; N],
+    keys: &BroadcastKeys,
+    aes_iterations: usize,
+) {
+    for _ in 0..aes_iterations {
+        for register in registers.iter_mut() {
+            *register = _mm512_xor_si512(*register, keys[0]);
+        }
+        for key in keys[1..10].iter() {
+            for register in registers.iter_mut() {
+                *register = _mm512_aesenc_epi128(*register, *key);
+            }
+        }
+        for register in registers.iter_mut() {
+            *register = _mm512_aesenclast_epi128(*register, keys[10]);
+        }
     }
 }
 
-mod c_exports {
-    use core::arch::x86_64::*;
-
-    #[link(name = "vaes")]
-    extern "C" {
-        // TODO: Compiler complains about `__m128i` in below functions and probably rightfully so
-        pub fn por_encode_pipelined_x12_low_level(
-            blocks_0: *mut u8,
-            blocks_1: *mut u8,
-            blocks_2: *mut u8,
-            feedbacks_0: *const u8,
-            feedbacks_1: *const u8,
-            feedbacks_2: *const u8,
-            keys: *const __m128i,
-            aes_iterations: usize,
-        );
+/// AES-128 decryption of `N` registers `aes_iterations` times, rounds of different registers are
+/// interleaved to keep pipeline busy
+#[target_feature(enable = "avx512f,vaes")]
+unsafe fn decode_x4n<const N: usize>(
+    registers: &mut [__m512i; N],
+    keys: &BroadcastKeys,
+    aes_iterations: usize,
+) {
+    for _ in 0..aes_iterations {
+        for register in registers.iter_mut() {
+            *register = _mm512_xor_si512(*register, keys[10]);
+        }
+        for key in keys[1..10].iter().rev() {
+            for register in registers.iter_mut() {
+                *register = _mm512_aesdec_epi128(*register, *key);
+            }
+        }
+        for register in registers.iter_mut() {
+            *register = _mm512_aesdeclast_epi128(*register, keys[0]);
+        }
+    }
+}
 
-        pub fn por_decode_pipelined_x12_low_level(
-            blocks: *mut u8,
-            feedbacks: *const u8,
-            keys: *const __m128i,
-            aes_iterations: usize,
-        );
+#[target_feature(enable = "avx512f,vaes")]
+unsafe fn por_encode_x4n<const N: usize>(
+    keys: &ExpandedKeys,
+    blocks: &mut [u8],
+    feedbacks: &[u8],
+    aes_iterations: usize,
+) {
+    let keys = broadcast_keys(keys);
+    let mut registers = load::<N>(blocks);
+    let feedbacks = load::<N>(feedbacks);
 
-        pub fn por_decode_x4_low_level(
-            blocks: *mut u8,
-            feedbacks: *const u8,
-            keys: *const __m128i,
-            aes_iterations: usize,
-        );
+    for (register, feedback) in registers.iter_mut().zip(feedbacks.iter()) {
+        *register = _mm512_xor_si512(*register, *feedback);
+    }
+
+    encode_x4n(&mut registers, &keys, aes_iterations);
+
+    store(blocks, &registers);
+}
+
+#[target_feature(enable = "avx512f,vaes")]
+unsafe fn por_decode_x4n<const N: usize>(
+    keys: &ExpandedKeys,
+    blocks: &mut [u8],
+    feedbacks: &[u8],
+    aes_iterations: usize,
+) {
+    let keys = broadcast_keys(keys);
+    let mut registers = load::<N>(blocks);
+
+    decode_x4n(&mut registers, &keys, aes_iterations);
+
+    let feedbacks = load::<N>(feedbacks);
+    for (register, feedback) in registers.iter_mut().zip(feedbacks.iter()) {
+        *register = _mm512_xor_si512(*register, *feedback);
+    }
+
+    store(blocks, &registers);
+}
+
+/// Each block decoded `aes_iterations` times must be equal to the previous block, the first block
+/// must be equal to `expected_first_block`
+#[target_feature(enable = "avx512f,vaes")]
+unsafe fn pot_verify_x4n<const N: usize>(
+    keys: &ExpandedKeys,
+    expected_first_block: &[u8],
+    blocks: &[u8],
+    aes_iterations: usize,
+) -> bool {
+    let keys = broadcast_keys(keys);
+
+    let mut expected_blocks = [[0u8; BLOCK_SIZE * 4]; N];
+    for (index, expected_block) in expected_blocks
+        .iter_mut()
+        .flat_map(|chunk| chunk.chunks_exact_mut(BLOCK_SIZE))
+        .enumerate()
+    {
+        expected_block.copy_from_slice(match index {
+            0 => expected_first_block,
+            index => &blocks[(index - 1) * BLOCK_SIZE..][..BLOCK_SIZE],
+        });
+    }
+
+    let mut registers = load::<N>(blocks);
+
+    decode_x4n(&mut registers, &keys, aes_iterations);
+
+    registers
+        .iter()
+        .zip(expected_blocks.iter())
+        .all(|(register, expected_blocks)| {
+            let expected_blocks = _mm512_loadu_si512(expected_blocks.as_ptr() as *const __m512i);
+            _mm512_cmpeq_epi64_mask(*register, expected_blocks) == u8::MAX
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_low_level::aes_ni;
+    use crate::utils;
+    use crate::utils::AesImplementation;
+    use rand::Rng;
+
+    fn aes_ni_encode(block: &mut [u8], keys: &ExpandedKeys, aes_iterations: usize) {
+        unsafe {
+            let block_reg = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+            let block_reg = aes_ni::pot_prove_low_level(*keys, block_reg, aes_iterations);
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, block_reg);
+        }
+    }
+
+    #[test]
+    fn test_against_aes_ni() {
+        if !utils::aes_implementations_available().contains(&AesImplementation::VAes) {
+            println!("VAES support not available, skipping test");
+            return;
+        }
+
+        let mut id = [0u8; BLOCK_SIZE];
+        rand::thread_rng().fill(&mut id[..]);
+        let (keys_enc, keys_dec) = unsafe { aes_ni::expand(&id) };
+
+        let aes_iterations = 3;
+
+        for _ in 0..10 {
+            let mut blocks = [0u8; BLOCK_SIZE * 12];
+            rand::thread_rng().fill(&mut blocks[..]);
+            let mut feedbacks = [[0u8; BLOCK_SIZE]; 12];
+            for feedback in feedbacks.iter_mut() {
+                rand::thread_rng().fill(&mut feedback[..]);
+            }
+
+            // Encoding
+            let mut encodings_aes_ni = blocks;
+            for (block, feedback) in encodings_aes_ni
+                .chunks_exact_mut(BLOCK_SIZE)
+                .zip(feedbacks.iter())
+            {
+                block
+                    .iter_mut()
+                    .zip(feedback.iter())
+                    .for_each(|(byte, feedback_byte)| *byte ^= feedback_byte);
+                aes_ni_encode(block, &keys_enc, aes_iterations);
+            }
+
+            let mut encodings_vaes = blocks;
+            {
+                let mut chunks = encodings_vaes.chunks_exact_mut(BLOCK_SIZE);
+                let mut blocks_refs: [&mut [u8]; 12] = [(); 12].map(|_| chunks.next().unwrap());
+                por_encode_pipelined_x12_low_level(
+                    &keys_enc,
+                    &mut blocks_refs,
+                    &feedbacks,
+                    aes_iterations,
+                );
+            }
+            assert_eq!(encodings_vaes.to_vec(), encodings_aes_ni.to_vec());
+
+            // Decoding
+            let feedbacks_flat = feedbacks.concat();
+            let mut decodings_vaes = encodings_vaes;
+            por_decode_pipelined_x12_low_level(
+                &keys_dec,
+                &mut decodings_vaes,
+                &feedbacks_flat,
+                aes_iterations,
+            );
+            assert_eq!(decodings_vaes.to_vec(), blocks.to_vec());
+
+            let mut decodings_vaes = encodings_vaes;
+            for (blocks, feedbacks) in decodings_vaes
+                .chunks_exact_mut(BLOCK_SIZE * 4)
+                .zip(feedbacks_flat.chunks_exact(BLOCK_SIZE * 4))
+            {
+                por_decode_x4_low_level(&keys_dec, blocks, feedbacks, aes_iterations);
+            }
+            assert_eq!(decodings_vaes.to_vec(), blocks.to_vec());
+
+            // Verification of a chain where each block encodes the previous one
+            let mut chain = [0u8; BLOCK_SIZE * 13];
+            rand::thread_rng().fill(&mut chain[..BLOCK_SIZE]);
+            for index in 1..13 {
+                let (previous, next) = chain.split_at_mut(index * BLOCK_SIZE);
+                next[..BLOCK_SIZE].copy_from_slice(&previous[previous.len() - BLOCK_SIZE..]);
+                aes_ni_encode(&mut next[..BLOCK_SIZE], &keys_enc, aes_iterations);
+            }
+            let (seed, proof) = chain.split_at(BLOCK_SIZE);
+
+            assert!(pot_verify_pipelined_x12_low_level(
+                &keys_dec,
+                seed,
+                proof,
+                aes_iterations,
+            ));
+            assert!(pot_verify_pipelined_x8_low_level(
+                &keys_dec,
+                seed,
+                &proof[..BLOCK_SIZE * 8],
+                aes_iterations,
+            ));
+            assert!(pot_verify_x4_low_level(
+                &keys_dec,
+                seed,
+                &proof[..BLOCK_SIZE * 4],
+                aes_iterations,
+            ));
 
-        pub fn pot_verify_pipelined_x12_low_level(
-            blocks: *const u8,
-            expected_first_4_blocks: *const u8,
-            keys: *const __m128i,
-            aes_iterations: usize,
-        ) -> u8;
-
-        pub fn pot_verify_pipelined_x8_low_level(
-            blocks: *const u8,
-            expected_first_4_blocks: *const u8,
-            keys: *const __m128i,
-            aes_iterations: usize,
-        ) -> u8;
-
-        pub fn pot_verify_x4_low_level(
-            blocks: *const u8,
-            expected_blocks: *const u8,
-            keys: *const __m128i,
-            aes_iterations: usize,
-        ) -> u8;
+            let mut corrupted_proof = proof.to_vec();
+            corrupted_proof[BLOCK_SIZE * 7] ^= 1;
+            assert!(!pot_verify_pipelined_x12_low_level(
+                &keys_dec,
+                seed,
+                &corrupted_proof,
+                aes_iterations,
+            ));
+        }
     }
 }
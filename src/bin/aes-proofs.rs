@@ -0,0 +1,823 @@
+//! `aes-proofs` command-line tool.
+//!
+//! Every command prints a single line with JSON object to stdout on success, errors are printed as
+//! `{"error":"..."}` to stderr. Exit code is 0 on success, 1 on failure (including invalid proof)
+//! and 2 on invalid usage.
+
+use rust_aes_proofs::por::dispatcher::Backend;
+use rust_aes_proofs::por::dispatcher::BackendError;
+use rust_aes_proofs::por::dispatcher::Dispatcher;
+use rust_aes_proofs::por::stream::StreamDecoder;
+use rust_aes_proofs::por::stream::StreamEncoder;
+#[cfg(target_arch = "x86_64")]
+use rust_aes_proofs::pot::aes_ni::AesNi;
+#[cfg(target_arch = "x86_64")]
+use rust_aes_proofs::pot::aes_ni::AesNiKeys;
+use rust_aes_proofs::pot::software_fix_slicing::SoftwareFixSlicing;
+use rust_aes_proofs::pot::software_fix_slicing::SoftwareFixSlicingKeys;
+#[cfg(target_arch = "x86_64")]
+use rust_aes_proofs::pot::vaes::VAes;
+#[cfg(target_arch = "x86_64")]
+use rust_aes_proofs::pot::vaes::VAesKeys;
+use rust_aes_proofs::round_keys::RoundKeys;
+use rust_aes_proofs::utils;
+use rust_aes_proofs::utils::SelfTestFailed;
+use rust_aes_proofs::Block;
+use rust_aes_proofs::BLOCK_SIZE;
+use rust_aes_proofs::PIECE_SIZE;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::process;
+use std::str::FromStr;
+use std::time::Instant;
+
+const USAGE: &str = "\
+Usage: aes-proofs <command> [options]
+
+Commands:
+    encode <input> <output>  Encode file into proof of replication replica (padded to a multiple
+                             of 4096 bytes, padding is removed on decoding), IV of piece `i` is
+                             `iv + i`
+        --id <hex> --iv <hex> [--aes-iterations 256] [--breadth-iterations 16] [--backend auto]
+    decode <input> <output>  Decode proof of replication replica into original file
+        --id <hex> --iv <hex> [--aes-iterations 256] [--breadth-iterations 16] [--backend auto]
+    prove                    Create proof of time
+        --id <hex> --seed <hex> [--aes-iterations 3000000] [--parallelism 16] [--backend auto]
+    verify                   Verify proof of time, exit code is 1 if proof is invalid
+        --id <hex> --seed <hex> --proof <hex> [--aes-iterations 3000000] [--backend auto]
+    backends                 List backends available on this machine (backends are only used after
+                             passing known-answer self-test)
+    calibrate                Measure speed of available backends
+        [--pieces 64] [--aes-iterations 256] [--breadth-iterations 16]
+        [--pot-aes-iterations 480000] [--parallelism 16]
+
+Proof of replication backends: auto, opencl, vaes, aes-ni, software-fix-slicing, software-lut
+Proof of time backends: auto, vaes, aes-ni, software-fix-slicing";
+
+const POR_BACKENDS: [(&str, Backend); 5] = [
+    ("opencl", Backend::OpenCL),
+    ("vaes", Backend::VAes),
+    ("aes-ni", Backend::AesNi),
+    ("software-fix-slicing", Backend::SoftwareFixSlicing),
+    ("software-lut", Backend::SoftwareLuT),
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum PotBackend {
+    VAes,
+    AesNi,
+    SoftwareFixSlicing,
+}
+
+const POT_BACKENDS: [(&str, PotBackend); 3] = [
+    ("vaes", PotBackend::VAes),
+    ("aes-ni", PotBackend::AesNi),
+    ("software-fix-slicing", PotBackend::SoftwareFixSlicing),
+];
+
+#[derive(Debug, Eq, PartialEq)]
+enum Error {
+    /// Invalid command line
+    Usage(String),
+    /// Command failed
+    Failed(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(message) | Error::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Arguments after the command, options are `--name value` pairs
+#[derive(Debug)]
+struct Args {
+    positional: Vec<String>,
+    options: BTreeMap<String, String>,
+}
+
+impl Args {
+    fn parse<I>(args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut positional = Vec::new();
+        let mut options = BTreeMap::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| Error::Usage(format!("Missing value for --{}", name)))?;
+                    if options.insert(name.to_string(), value).is_some() {
+                        return Err(Error::Usage(format!("Duplicated option --{}", name)));
+                    }
+                }
+                None => {
+                    positional.push(arg);
+                }
+            }
+        }
+
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn positional(&mut self, count: usize) -> Result<Vec<String>, Error> {
+        if self.positional.len() != count {
+            return Err(Error::Usage(format!(
+                "Expected {} positional arguments, got {}",
+                count,
+                self.positional.len(),
+            )));
+        }
+
+        Ok(self.positional.drain(..).collect())
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, Error> {
+        self.options
+            .remove(name)
+            .ok_or_else(|| Error::Usage(format!("Missing required option --{}", name)))
+    }
+
+    fn optional<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, Error> {
+        self.options
+            .remove(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| Error::Usage(format!("Invalid value for --{}: {}", name, value)))
+            })
+            .transpose()
+    }
+
+    fn number(&mut self, name: &str, default: usize) -> Result<usize, Error> {
+        Ok(self.optional(name)?.unwrap_or(default))
+    }
+
+    /// Same as `number()`, but 0 is rejected
+    fn positive(&mut self, name: &str, default: usize) -> Result<usize, Error> {
+        match self.number(name, default)? {
+            0 => Err(Error::Usage(format!("--{} must be positive", name))),
+            value => Ok(value),
+        }
+    }
+
+    fn block(&mut self, name: &str) -> Result<Block, Error> {
+        let value = self.required(name)?;
+        let mut block = Block::default();
+        match decode_hex(&value) {
+            Some(bytes) if bytes.len() == BLOCK_SIZE => {
+                block.copy_from_slice(&bytes);
+                Ok(block)
+            }
+            _ => Err(Error::Usage(format!(
+                "--{} must be {} bytes in hex",
+                name, BLOCK_SIZE,
+            ))),
+        }
+    }
+
+    /// Must be called after all arguments were consumed
+    fn finish(self) -> Result<(), Error> {
+        if !self.positional.is_empty() {
+            return Err(Error::Usage(format!(
+                "Unexpected argument {}",
+                self.positional[0],
+            )));
+        }
+        match self.options.keys().next() {
+            Some(name) => Err(Error::Usage(format!("Unknown option --{}", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Minimal JSON object writer
+#[derive(Default)]
+struct JsonObject {
+    fields: Vec<(&'static str, String)>,
+}
+
+impl JsonObject {
+    fn string(mut self, name: &'static str, value: &str) -> Self {
+        self.fields.push((name, quote(value)));
+        self
+    }
+
+    fn number<T: fmt::Display>(mut self, name: &'static str, value: T) -> Self {
+        self.fields.push((name, value.to_string()));
+        self
+    }
+
+    fn bool(mut self, name: &'static str, value: bool) -> Self {
+        self.fields.push((name, value.to_string()));
+        self
+    }
+
+    /// Value that is already JSON
+    fn raw(mut self, name: &'static str, value: String) -> Self {
+        self.fields.push((name, value));
+        self
+    }
+}
+
+impl fmt::Display for JsonObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (index, (name, value)) in self.fields.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", quote(name), value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+fn json_array<I, T>(items: I) -> String
+where
+    I: IntoIterator<Item = T>,
+    T: fmt::Display,
+{
+    let items = items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>();
+    format!("[{}]", items.join(","))
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..(i + 2)], 16).ok())
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn por_backend_name(backend: Backend) -> &'static str {
+    POR_BACKENDS
+        .iter()
+        .find(|(_, known)| *known == backend)
+        .map(|(name, _)| *name)
+        .unwrap()
+}
+
+fn pot_backend_name(backend: PotBackend) -> &'static str {
+    POT_BACKENDS
+        .iter()
+        .find(|(_, known)| *known == backend)
+        .map(|(name, _)| *name)
+        .unwrap()
+}
+
+fn por_dispatcher(args: &mut Args) -> Result<Dispatcher, Error> {
+    let name = args.optional::<String>("backend")?;
+    match name.as_deref() {
+        None | Some("auto") => Ok(Dispatcher::new()),
+        Some(name) => {
+            let &(_, backend) = POR_BACKENDS
+                .iter()
+                .find(|(known, _)| *known == name)
+                .ok_or_else(|| Error::Usage(format!("Unknown backend {}", name)))?;
+            Dispatcher::with_backend(backend).map_err(|error| Error::Failed(error.to_string()))
+        }
+    }
+}
+
+/// Runs known-answer self-test of proof of time backend, `None` if backend is not available
+fn pot_self_test(backend: PotBackend) -> Option<Result<(), SelfTestFailed>> {
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        PotBackend::VAes => VAes::new().ok().map(|vaes| vaes.self_test()),
+        #[cfg(target_arch = "x86_64")]
+        PotBackend::AesNi => AesNi::new().ok().map(|aes_ni| aes_ni.self_test()),
+        PotBackend::SoftwareFixSlicing => Some(SoftwareFixSlicing::new().self_test()),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Available proof of time backends that passed known-answer self-test in order of preference
+fn pot_backends_available() -> Vec<PotBackend> {
+    POT_BACKENDS
+        .iter()
+        .map(|&(_, backend)| backend)
+        .filter(|&backend| pot_self_test(backend) == Some(Ok(())))
+        .collect()
+}
+
+fn pot_backend(args: &mut Args) -> Result<PotBackend, Error> {
+    let name = args.optional::<String>("backend")?;
+    match name.as_deref() {
+        None | Some("auto") => pot_backends_available()
+            .first()
+            .copied()
+            .ok_or_else(|| Error::Failed("All backends failed self-test".to_string())),
+        Some(name) => {
+            let &(_, backend) = POT_BACKENDS
+                .iter()
+                .find(|(known, _)| *known == name)
+                .ok_or_else(|| Error::Usage(format!("Unknown backend {}", name)))?;
+            match pot_self_test(backend) {
+                Some(Ok(())) => Ok(backend),
+                Some(Err(error)) => Err(Error::Failed(format!("Backend {}: {}", name, error))),
+                None => Err(Error::Failed(format!("Backend {} is not available", name))),
+            }
+        }
+    }
+}
+
+fn check_pot_parameters(aes_iterations: usize, parallelism: usize) -> Result<(), Error> {
+    if !aes_iterations.is_multiple_of(48) {
+        return Err(Error::Usage(
+            "--aes-iterations must be a multiple of 48".to_string(),
+        ));
+    }
+    if ![4, 8, 12, 16].contains(&parallelism) {
+        return Err(Error::Usage(
+            "Verifier parallelism must be 4, 8, 12 or 16".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn pot_prove(
+    backend: PotBackend,
+    round_keys: &RoundKeys,
+    seed: &Block,
+    aes_iterations: usize,
+    parallelism: usize,
+) -> Vec<u8> {
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        PotBackend::VAes => VAes::new().unwrap().prove(
+            seed,
+            &VAesKeys::from_round_keys(round_keys),
+            aes_iterations,
+            parallelism,
+        ),
+        #[cfg(target_arch = "x86_64")]
+        PotBackend::AesNi => AesNi::new().unwrap().prove(
+            seed,
+            &AesNiKeys::from_round_keys(round_keys),
+            aes_iterations,
+            parallelism,
+        ),
+        PotBackend::SoftwareFixSlicing => SoftwareFixSlicing::new().prove(
+            seed,
+            &SoftwareFixSlicingKeys::from_round_keys(round_keys),
+            aes_iterations,
+            parallelism,
+        ),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("Only available backends are selected"),
+    }
+}
+
+fn pot_verify(
+    backend: PotBackend,
+    round_keys: &RoundKeys,
+    seed: &Block,
+    proof: &[u8],
+    aes_iterations: usize,
+) -> bool {
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        PotBackend::VAes => VAes::new().unwrap().verify(
+            proof,
+            seed,
+            &VAesKeys::from_round_keys(round_keys),
+            aes_iterations,
+        ),
+        #[cfg(target_arch = "x86_64")]
+        PotBackend::AesNi => AesNi::new().unwrap().verify(
+            proof,
+            seed,
+            &AesNiKeys::from_round_keys(round_keys),
+            aes_iterations,
+        ),
+        PotBackend::SoftwareFixSlicing => SoftwareFixSlicing::new().verify(
+            proof,
+            seed,
+            &SoftwareFixSlicingKeys::from_round_keys(round_keys),
+            aes_iterations,
+        ),
+        #[allow(unreachable_patterns)]
+        _ => unreachable!("Only available backends are selected"),
+    }
+}
+
+fn open_file(path: &str) -> Result<BufReader<File>, Error> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|error| Error::Failed(format!("Failed to read {}: {}", path, error)))
+}
+
+fn create_file(path: &str) -> Result<BufWriter<File>, Error> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|error| Error::Failed(format!("Failed to write {}: {}", path, error)))
+}
+
+fn encode(mut args: Args) -> Result<JsonObject, Error> {
+    let paths = args.positional(2)?;
+    let round_keys = RoundKeys::new(&args.block("id")?);
+    let iv = args.block("iv")?;
+    let aes_iterations = args.positive("aes-iterations", 256)?;
+    let breadth_iterations = args.positive("breadth-iterations", 16)?;
+    let dispatcher = por_dispatcher(&mut args)?;
+    args.finish()?;
+
+    let input = open_file(&paths[0])?;
+    let output = create_file(&paths[1])?;
+    let mut encoder = StreamEncoder::new(
+        dispatcher,
+        round_keys,
+        iv,
+        aes_iterations,
+        breadth_iterations,
+    );
+
+    let start = Instant::now();
+    let summary = encoder
+        .encode(input, output)
+        .map_err(|error| Error::Failed(format!("Failed to encode: {}", error)))?;
+    let seconds = start.elapsed().as_secs_f64();
+
+    Ok(JsonObject::default()
+        .string("backend", por_backend_name(encoder.dispatcher().backend()))
+        .number("size", summary.size)
+        .number("pieces", summary.pieces)
+        .number("aes_iterations", aes_iterations)
+        .number("breadth_iterations", breadth_iterations)
+        .number("seconds", seconds))
+}
+
+fn decode(mut args: Args) -> Result<JsonObject, Error> {
+    let paths = args.positional(2)?;
+    let round_keys = RoundKeys::new(&args.block("id")?);
+    let iv = args.block("iv")?;
+    let aes_iterations = args.positive("aes-iterations", 256)?;
+    let breadth_iterations = args.positive("breadth-iterations", 16)?;
+    let dispatcher = por_dispatcher(&mut args)?;
+    args.finish()?;
+
+    let input = open_file(&paths[0])?;
+    let output = create_file(&paths[1])?;
+    let mut decoder = StreamDecoder::new(
+        dispatcher,
+        round_keys,
+        iv,
+        aes_iterations,
+        breadth_iterations,
+    );
+
+    let start = Instant::now();
+    let summary = decoder
+        .decode(input, output)
+        .map_err(|error| Error::Failed(format!("Failed to decode: {}", error)))?;
+    let seconds = start.elapsed().as_secs_f64();
+
+    Ok(JsonObject::default()
+        .string("backend", por_backend_name(decoder.dispatcher().backend()))
+        .number("size", summary.size)
+        .number("pieces", summary.pieces)
+        .number("aes_iterations", aes_iterations)
+        .number("breadth_iterations", breadth_iterations)
+        .number("seconds", seconds))
+}
+
+fn prove(mut args: Args) -> Result<JsonObject, Error> {
+    let round_keys = RoundKeys::new(&args.block("id")?);
+    let seed = args.block("seed")?;
+    let aes_iterations = args.positive("aes-iterations", 3_000_000)?;
+    let parallelism = args.number("parallelism", 16)?;
+    let backend = pot_backend(&mut args)?;
+    args.finish()?;
+    check_pot_parameters(aes_iterations, parallelism)?;
+
+    let start = Instant::now();
+    let proof = pot_prove(backend, &round_keys, &seed, aes_iterations, parallelism);
+    let seconds = start.elapsed().as_secs_f64();
+
+    Ok(JsonObject::default()
+        .string("backend", pot_backend_name(backend))
+        .string("proof", &encode_hex(&proof))
+        .number("aes_iterations", aes_iterations)
+        .number("parallelism", parallelism)
+        .number("seconds", seconds))
+}
+
+fn verify(mut args: Args) -> Result<(JsonObject, bool), Error> {
+    let round_keys = RoundKeys::new(&args.block("id")?);
+    let seed = args.block("seed")?;
+    let proof = args.required("proof")?;
+    let proof = decode_hex(&proof)
+        .filter(|proof| proof.len().is_multiple_of(BLOCK_SIZE))
+        .ok_or_else(|| Error::Usage("--proof must be whole blocks in hex".to_string()))?;
+    let aes_iterations = args.positive("aes-iterations", 3_000_000)?;
+    let backend = pot_backend(&mut args)?;
+    args.finish()?;
+    check_pot_parameters(aes_iterations, proof.len() / BLOCK_SIZE)?;
+
+    let start = Instant::now();
+    let valid = pot_verify(backend, &round_keys, &seed, &proof, aes_iterations);
+    let seconds = start.elapsed().as_secs_f64();
+
+    let output = JsonObject::default()
+        .string("backend", pot_backend_name(backend))
+        .bool("valid", valid)
+        .number("aes_iterations", aes_iterations)
+        .number("parallelism", proof.len() / BLOCK_SIZE)
+        .number("seconds", seconds);
+
+    Ok((output, valid))
+}
+
+fn backends(args: Args) -> Result<JsonObject, Error> {
+    args.finish()?;
+
+    let mut por = Vec::new();
+    let mut por_failed = Vec::new();
+    for &(name, backend) in POR_BACKENDS.iter() {
+        match Dispatcher::with_backend(backend) {
+            Ok(_) => por.push(quote(name)),
+            Err(BackendError::SelfTestFailed(_)) => por_failed.push(quote(name)),
+            Err(BackendError::Unavailable(_)) => {}
+        }
+    }
+    let mut pot = Vec::new();
+    let mut pot_failed = Vec::new();
+    for &(name, backend) in POT_BACKENDS.iter() {
+        match pot_self_test(backend) {
+            Some(Ok(())) => pot.push(quote(name)),
+            Some(Err(_)) => pot_failed.push(quote(name)),
+            None => {}
+        }
+    }
+    let instruction_sets = utils::aes_implementations_available()
+        .into_iter()
+        .map(|implementation| quote(&format!("{:?}", implementation)));
+
+    Ok(JsonObject::default()
+        .raw("por", json_array(por))
+        .raw("por_self_test_failed", json_array(por_failed))
+        .string("por_default", por_backend_name(Dispatcher::new().backend()))
+        .raw("pot", json_array(pot))
+        .raw("pot_self_test_failed", json_array(pot_failed))
+        .raw("aes_implementations", json_array(instruction_sets)))
+}
+
+/// Rate that stays finite (and thus valid JSON) even if operation was too fast to be measured
+fn per_second(count: usize, seconds: f64) -> f64 {
+    // `Instant` has nanosecond resolution at best
+    count as f64 / seconds.max(1e-9)
+}
+
+fn calibrate(mut args: Args) -> Result<JsonObject, Error> {
+    let pieces_count = args.positive("pieces", 64)?;
+    let aes_iterations = args.positive("aes-iterations", 256)?;
+    let breadth_iterations = args.positive("breadth-iterations", 16)?;
+    let pot_aes_iterations = args.positive("pot-aes-iterations", 480_000)?;
+    let parallelism = args.number("parallelism", 16)?;
+    args.finish()?;
+    check_pot_parameters(pot_aes_iterations, parallelism)?;
+
+    let round_keys = RoundKeys::new(&[0u8; BLOCK_SIZE]);
+
+    let mut por = Vec::new();
+    for &(name, backend) in POR_BACKENDS.iter() {
+        let mut dispatcher = match Dispatcher::with_backend(backend) {
+            Ok(dispatcher) => dispatcher,
+            Err(_) => {
+                continue;
+            }
+        };
+        let mut pieces = vec![[0u8; PIECE_SIZE]; pieces_count];
+        let ivs = vec![Block::default(); pieces_count];
+
+        let start = Instant::now();
+        dispatcher.encode(
+            &mut pieces,
+            &round_keys,
+            &ivs,
+            aes_iterations,
+            breadth_iterations,
+        );
+        let encode_seconds = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        dispatcher.decode(
+            &mut pieces,
+            &round_keys,
+            &ivs,
+            aes_iterations,
+            breadth_iterations,
+        );
+        let decode_seconds = start.elapsed().as_secs_f64();
+
+        por.push(
+            JsonObject::default()
+                .string("backend", name)
+                // Dispatcher may have fallen back to CPU if OpenCL failed
+                .bool("fallback", dispatcher.backend() != backend)
+                .number(
+                    "encode_pieces_per_second",
+                    per_second(pieces_count, encode_seconds),
+                )
+                .number(
+                    "decode_pieces_per_second",
+                    per_second(pieces_count, decode_seconds),
+                ),
+        );
+    }
+
+    let mut pot = Vec::new();
+    for backend in pot_backends_available() {
+        let seed = Block::default();
+
+        let start = Instant::now();
+        let proof = pot_prove(backend, &round_keys, &seed, pot_aes_iterations, parallelism);
+        let prove_seconds = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        let valid = pot_verify(backend, &round_keys, &seed, &proof, pot_aes_iterations);
+        let verify_seconds = start.elapsed().as_secs_f64();
+        if !valid {
+            return Err(Error::Failed(format!(
+                "Backend {} failed to verify its own proof",
+                pot_backend_name(backend),
+            )));
+        }
+
+        pot.push(
+            JsonObject::default()
+                .string("backend", pot_backend_name(backend))
+                .number(
+                    "prove_aes_iterations_per_second",
+                    per_second(pot_aes_iterations, prove_seconds),
+                )
+                .number(
+                    "verify_aes_iterations_per_second",
+                    per_second(pot_aes_iterations, verify_seconds),
+                ),
+        );
+    }
+
+    Ok(JsonObject::default()
+        .number("pieces", pieces_count)
+        .number("aes_iterations", aes_iterations)
+        .number("breadth_iterations", breadth_iterations)
+        .raw("por", json_array(por))
+        .number("pot_aes_iterations", pot_aes_iterations)
+        .number("parallelism", parallelism)
+        .raw("pot", json_array(pot)))
+}
+
+/// Returns output and whether command succeeded
+fn run(mut args: env::Args) -> Result<(JsonObject, bool), Error> {
+    let command = args.nth(1).ok_or_else(|| Error::Usage(USAGE.to_string()))?;
+    let args = Args::parse(args)?;
+
+    match command.as_str() {
+        "encode" => encode(args).map(|output| (output, true)),
+        "decode" => decode(args).map(|output| (output, true)),
+        "prove" => prove(args).map(|output| (output, true)),
+        "verify" => verify(args),
+        "backends" => backends(args).map(|output| (output, true)),
+        "calibrate" => calibrate(args).map(|output| (output, true)),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        command => Err(Error::Usage(format!(
+            "Unknown command {}\n\n{}",
+            command, USAGE,
+        ))),
+    }
+}
+
+fn main() {
+    match run(env::args()) {
+        Ok((output, success)) => {
+            println!("{}", output);
+            if !success {
+                process::exit(1);
+            }
+        }
+        Err(error) => {
+            eprintln!(
+                "{}",
+                JsonObject::default().string("error", &error.to_string())
+            );
+            process::exit(match error {
+                Error::Usage(_) => 2,
+                Error::Failed(_) => 1,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, Error> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_args() {
+        let mut parsed =
+            args(&["input", "--id", "00", "output", "--aes-iterations", "12"]).unwrap();
+        assert_eq!(parsed.positional(2).unwrap(), vec!["input", "output"]);
+        assert_eq!(parsed.number("aes-iterations", 256), Ok(12));
+        assert_eq!(parsed.number("breadth-iterations", 16), Ok(16));
+        assert!(matches!(parsed.block("id"), Err(Error::Usage(_))));
+        assert!(parsed.finish().is_ok());
+
+        let parsed = args(&["--unknown", "1"]).unwrap();
+        assert_eq!(
+            parsed.finish(),
+            Err(Error::Usage("Unknown option --unknown".to_string())),
+        );
+
+        assert!(args(&["--id"]).is_err());
+        assert!(args(&["--id", "1", "--id", "2"]).is_err());
+
+        let mut parsed = args(&["--aes-iterations", "many"]).unwrap();
+        assert!(parsed.number("aes-iterations", 256).is_err());
+
+        let mut parsed = args(&["--aes-iterations", "0"]).unwrap();
+        assert_eq!(
+            parsed.positive("aes-iterations", 256),
+            Err(Error::Usage(
+                "--aes-iterations must be positive".to_string()
+            )),
+        );
+        assert_eq!(parsed.positive("breadth-iterations", 16), Ok(16));
+    }
+
+    #[test]
+    fn test_per_second() {
+        assert_eq!(per_second(10, 2.0), 5.0);
+        assert!(per_second(64, 0.0).is_finite());
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(decode_hex("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(decode_hex("0"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(decode_hex("ü0"), None);
+        assert_eq!(encode_hex(&[0x00, 0xff, 0x7a]), "00ff7a");
+    }
+
+    #[test]
+    fn test_json() {
+        let object = JsonObject::default()
+            .string("string", "a\"b\\c\n\u{1}")
+            .number("number", 1.5)
+            .bool("bool", true)
+            .raw("array", json_array(vec![quote("x"), quote("y")]));
+        assert_eq!(
+            object.to_string(),
+            r#"{"string":"a\"b\\c\n\u0001","number":1.5,"bool":true,"array":["x","y"]}"#,
+        );
+    }
+}
@@ -0,0 +1,553 @@
+//! Stable C ABI (enabled with `capi` feature).
+//!
+//! Functions never panic across the boundary, invalid input is reported with `AesProofsStatus`
+//! instead. C header is generated by `build.rs` from this module only into `OUT_DIR` and its
+//! copy for consumers is committed as `include/aes_proofs.h` (kept up to date by tests), while
+//! `src/capi/test.c` runs test vectors through the header and is driven by tests in this module.
+
+use crate::por::dispatcher::Dispatcher;
+#[cfg(target_arch = "x86_64")]
+use crate::pot::aes_ni::AesNi;
+#[cfg(target_arch = "x86_64")]
+use crate::pot::aes_ni::AesNiKeys;
+use crate::pot::software_fix_slicing::SoftwareFixSlicing;
+use crate::pot::software_fix_slicing::SoftwareFixSlicingKeys;
+use crate::pot::MAX_VERIFIER_PARALLELISM;
+use crate::pot::MIN_VERIFIER_PARALLELISM;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::Piece;
+use crate::BLOCK_SIZE;
+use crate::PIECE_SIZE;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::panic;
+use std::slice;
+
+/// Size of IDs, IVs and proof of time blocks in bytes
+pub const AES_PROOFS_BLOCK_SIZE: usize = 16;
+/// Size of proof of replication pieces in bytes
+pub const AES_PROOFS_PIECE_SIZE: usize = 4096;
+
+// Literals above are exported as is, since cbindgen doesn't evaluate constant expressions
+const _: () = assert!(AES_PROOFS_BLOCK_SIZE == BLOCK_SIZE && AES_PROOFS_PIECE_SIZE == PIECE_SIZE);
+
+/// Result of every call
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AesProofsStatus {
+    /// Success
+    Ok = 0,
+    /// One of the pointers is null
+    NullPointer = 1,
+    /// Buffer length is not a multiple of piece or block size or buffers don't match each other
+    InvalidLength = 2,
+    /// Unsupported number of iterations or verifier parallelism
+    InvalidParameter = 3,
+    /// Unexpected internal error, should never happen
+    InternalError = 4,
+    /// None of the backends passed known-answer self-test, results can't be trusted
+    SelfTestFailed = 5,
+}
+
+/// Opaque handle with keys derived from 16-byte ID, created with `aes_proofs_keys_new()` and
+/// freed with `aes_proofs_keys_free()`
+pub struct AesProofsKeys {
+    round_keys: RoundKeys,
+}
+
+/// Opaque proof of replication handle that uses the fastest backend available on this machine,
+/// created with `aes_proofs_por_new()` and freed with `aes_proofs_por_free()`, must not be used
+/// from multiple threads at the same time
+pub struct AesProofsPor {
+    dispatcher: Dispatcher,
+}
+
+/// Derives keys from 16-byte `id` and writes the handle into `keys`.
+///
+/// # Safety
+/// `id` must point to 16 readable bytes and `keys` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aes_proofs_keys_new(
+    id: *const u8,
+    keys: *mut *mut AesProofsKeys,
+) -> AesProofsStatus {
+    if id.is_null() || keys.is_null() {
+        return AesProofsStatus::NullPointer;
+    }
+
+    catch_panic(|| {
+        let id = &*(id as *const Block);
+        let round_keys = RoundKeys::new(id);
+        *keys = Box::into_raw(Box::new(AesProofsKeys { round_keys }));
+
+        AesProofsStatus::Ok
+    })
+}
+
+/// Frees keys (zeroizing them in the process), null is ignored.
+///
+/// # Safety
+/// `keys` must be null or a handle returned by `aes_proofs_keys_new()` that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn aes_proofs_keys_free(keys: *mut AesProofsKeys) {
+    if !keys.is_null() {
+        drop(Box::from_raw(keys));
+    }
+}
+
+/// Creates proof of replication handle and writes it into `por`.
+///
+/// # Safety
+/// `por` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aes_proofs_por_new(por: *mut *mut AesProofsPor) -> AesProofsStatus {
+    if por.is_null() {
+        return AesProofsStatus::NullPointer;
+    }
+
+    // Dispatcher only panics if none of the backends passed self-test
+    match panic::catch_unwind(Dispatcher::new) {
+        Ok(dispatcher) => {
+            *por = Box::into_raw(Box::new(AesProofsPor { dispatcher }));
+            AesProofsStatus::Ok
+        }
+        Err(_) => AesProofsStatus::SelfTestFailed,
+    }
+}
+
+/// Frees proof of replication handle, null is ignored.
+///
+/// # Safety
+/// `por` must be null or a handle returned by `aes_proofs_por_new()` that wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn aes_proofs_por_free(por: *mut AesProofsPor) {
+    if !por.is_null() {
+        drop(Box::from_raw(por));
+    }
+}
+
+/// Proof of replication encoding of `pieces_len / 4096` pieces in-place, each with its own 16-byte
+/// IV from `ivs`.
+///
+/// # Safety
+/// `por` and `keys` must be live handles, `pieces` must point to `pieces_len` readable and writable
+/// bytes and `ivs` to `ivs_len` readable bytes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn aes_proofs_por_encode(
+    por: *mut AesProofsPor,
+    keys: *const AesProofsKeys,
+    pieces: *mut u8,
+    pieces_len: usize,
+    ivs: *const u8,
+    ivs_len: usize,
+    aes_iterations: usize,
+    breadth_iterations: usize,
+) -> AesProofsStatus {
+    por_codec(
+        por,
+        keys,
+        pieces,
+        pieces_len,
+        ivs,
+        ivs_len,
+        aes_iterations,
+        breadth_iterations,
+        Dispatcher::encode,
+    )
+}
+
+/// Proof of replication decoding of `pieces_len / 4096` pieces in-place, each with its own 16-byte
+/// IV from `ivs`.
+///
+/// # Safety
+/// `por` and `keys` must be live handles, `pieces` must point to `pieces_len` readable and writable
+/// bytes and `ivs` to `ivs_len` readable bytes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn aes_proofs_por_decode(
+    por: *mut AesProofsPor,
+    keys: *const AesProofsKeys,
+    pieces: *mut u8,
+    pieces_len: usize,
+    ivs: *const u8,
+    ivs_len: usize,
+    aes_iterations: usize,
+    breadth_iterations: usize,
+) -> AesProofsStatus {
+    por_codec(
+        por,
+        keys,
+        pieces,
+        pieces_len,
+        ivs,
+        ivs_len,
+        aes_iterations,
+        breadth_iterations,
+        Dispatcher::decode,
+    )
+}
+
+/// Proof of time for 16-byte `seed`, writes `verifier_parallelism` blocks into `proof`
+/// (`proof_len` must be `verifier_parallelism * 16`). `aes_iterations` must be a multiple of 48
+/// and `verifier_parallelism` one of 4, 8, 12 or 16.
+///
+/// # Safety
+/// `keys` must be a live handle, `seed` must point to 16 readable bytes and `proof` to `proof_len`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aes_proofs_pot_prove(
+    keys: *const AesProofsKeys,
+    seed: *const u8,
+    aes_iterations: usize,
+    verifier_parallelism: usize,
+    proof: *mut u8,
+    proof_len: usize,
+) -> AesProofsStatus {
+    if keys.is_null() || seed.is_null() || proof.is_null() {
+        return AesProofsStatus::NullPointer;
+    }
+    if !pot_parameters_valid(aes_iterations, verifier_parallelism) {
+        return AesProofsStatus::InvalidParameter;
+    }
+    if proof_len != verifier_parallelism * BLOCK_SIZE {
+        return AesProofsStatus::InvalidLength;
+    }
+
+    catch_panic(|| {
+        let round_keys = &(*keys).round_keys;
+        let seed = &*(seed as *const Block);
+        let proof = slice::from_raw_parts_mut(proof, proof_len);
+
+        match pot_backend() {
+            #[cfg(target_arch = "x86_64")]
+            Some(PotBackend::AesNi(aes_ni)) => {
+                let keys = AesNiKeys::from_round_keys(round_keys);
+                proof.copy_from_slice(&aes_ni.prove(
+                    seed,
+                    &keys,
+                    aes_iterations,
+                    verifier_parallelism,
+                ));
+            }
+            Some(PotBackend::SoftwareFixSlicing(software_fix_slicing)) => {
+                let keys = SoftwareFixSlicingKeys::from_round_keys(round_keys);
+                proof.copy_from_slice(&software_fix_slicing.prove(
+                    seed,
+                    &keys,
+                    aes_iterations,
+                    verifier_parallelism,
+                ));
+            }
+            None => {
+                return AesProofsStatus::SelfTestFailed;
+            }
+        }
+
+        AesProofsStatus::Ok
+    })
+}
+
+/// Proof of time verification for 16-byte `seed`, writes whether `proof` is valid into `valid`.
+/// Same constraints as for `aes_proofs_pot_prove()` apply, with verifier parallelism being
+/// `proof_len / 16`.
+///
+/// # Safety
+/// `keys` must be a live handle, `seed` must point to 16 readable bytes, `proof` to `proof_len`
+/// readable bytes and `valid` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aes_proofs_pot_verify(
+    keys: *const AesProofsKeys,
+    seed: *const u8,
+    aes_iterations: usize,
+    proof: *const u8,
+    proof_len: usize,
+    valid: *mut bool,
+) -> AesProofsStatus {
+    if keys.is_null() || seed.is_null() || proof.is_null() || valid.is_null() {
+        return AesProofsStatus::NullPointer;
+    }
+    if !proof_len.is_multiple_of(BLOCK_SIZE) {
+        return AesProofsStatus::InvalidLength;
+    }
+    if !pot_parameters_valid(aes_iterations, proof_len / BLOCK_SIZE) {
+        return AesProofsStatus::InvalidParameter;
+    }
+
+    catch_panic(|| {
+        let round_keys = &(*keys).round_keys;
+        let seed = &*(seed as *const Block);
+        let proof = slice::from_raw_parts(proof, proof_len);
+
+        match pot_backend() {
+            #[cfg(target_arch = "x86_64")]
+            Some(PotBackend::AesNi(aes_ni)) => {
+                let keys = AesNiKeys::from_round_keys(round_keys);
+                *valid = aes_ni.verify(proof, seed, &keys, aes_iterations);
+            }
+            Some(PotBackend::SoftwareFixSlicing(software_fix_slicing)) => {
+                let keys = SoftwareFixSlicingKeys::from_round_keys(round_keys);
+                *valid = software_fix_slicing.verify(proof, seed, &keys, aes_iterations);
+            }
+            None => {
+                return AesProofsStatus::SelfTestFailed;
+            }
+        }
+
+        AesProofsStatus::Ok
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn por_codec(
+    por: *mut AesProofsPor,
+    keys: *const AesProofsKeys,
+    pieces: *mut u8,
+    pieces_len: usize,
+    ivs: *const u8,
+    ivs_len: usize,
+    aes_iterations: usize,
+    breadth_iterations: usize,
+    codec: fn(&mut Dispatcher, &mut [Piece], &RoundKeys, &[Block], usize, usize),
+) -> AesProofsStatus {
+    if por.is_null() || keys.is_null() || pieces.is_null() || ivs.is_null() {
+        return AesProofsStatus::NullPointer;
+    }
+    if !pieces_len.is_multiple_of(PIECE_SIZE) || ivs_len != pieces_len / PIECE_SIZE * BLOCK_SIZE {
+        return AesProofsStatus::InvalidLength;
+    }
+    // OpenCL backend takes iterations as `u32`
+    if breadth_iterations == 0
+        || u32::try_from(aes_iterations).is_err()
+        || u32::try_from(breadth_iterations).is_err()
+    {
+        return AesProofsStatus::InvalidParameter;
+    }
+
+    catch_panic(|| {
+        let pieces = slice::from_raw_parts_mut(pieces as *mut Piece, pieces_len / PIECE_SIZE);
+        let ivs = slice::from_raw_parts(ivs, ivs_len)
+            .chunks_exact(BLOCK_SIZE)
+            .map(|iv| iv.try_into().unwrap())
+            .collect::<Vec<Block>>();
+
+        codec(
+            &mut (*por).dispatcher,
+            pieces,
+            &(*keys).round_keys,
+            &ivs,
+            aes_iterations,
+            breadth_iterations,
+        );
+
+        AesProofsStatus::Ok
+    })
+}
+
+enum PotBackend {
+    #[cfg(target_arch = "x86_64")]
+    AesNi(AesNi),
+    SoftwareFixSlicing(SoftwareFixSlicing),
+}
+
+/// The fastest proof of time backend that passes known-answer self-test
+fn pot_backend() -> Option<PotBackend> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if let Ok(aes_ni) = AesNi::new() {
+            if aes_ni.self_test().is_ok() {
+                return Some(PotBackend::AesNi(aes_ni));
+            }
+        }
+    }
+
+    let software_fix_slicing = SoftwareFixSlicing::new();
+    software_fix_slicing
+        .self_test()
+        .ok()
+        .map(|_| PotBackend::SoftwareFixSlicing(software_fix_slicing))
+}
+
+fn pot_parameters_valid(aes_iterations: usize, verifier_parallelism: usize) -> bool {
+    aes_iterations.is_multiple_of(12)
+        && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        && (verifier_parallelism == MIN_VERIFIER_PARALLELISM
+            || verifier_parallelism == 8
+            || verifier_parallelism == 12
+            || verifier_parallelism == MAX_VERIFIER_PARALLELISM)
+}
+
+/// Panics must not unwind into C
+fn catch_panic<F>(f: F) -> AesProofsStatus
+where
+    F: FnOnce() -> AesProofsStatus,
+{
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or(AesProofsStatus::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por;
+    use crate::pot;
+    use std::os::raw::c_int;
+    use std::ptr;
+
+    // Compiled by `build.rs`
+    #[link(name = "aes_proofs_capi_test", kind = "static")]
+    extern "C" {
+        fn aes_proofs_capi_test_por(
+            id: *const u8,
+            iv: *const u8,
+            input: *const u8,
+            correct_encoding: *const u8,
+            correct_encoding_breadth_10: *const u8,
+        ) -> c_int;
+
+        fn aes_proofs_capi_test_pot(
+            id: *const u8,
+            seed: *const u8,
+            correct_proof_16: *const u8,
+            correct_proof_12: *const u8,
+        ) -> c_int;
+    }
+
+    #[test]
+    fn test_c_por() {
+        let failed_line = unsafe {
+            aes_proofs_capi_test_por(
+                por::test_data::ID.as_ptr(),
+                por::test_data::IV.as_ptr(),
+                por::test_data::INPUT.as_ptr(),
+                por::test_data::CORRECT_ENCODING.as_ptr(),
+                por::test_data::CORRECT_ENCODING_BREADTH_10.as_ptr(),
+            )
+        };
+        assert_eq!(failed_line, 0, "src/capi/test.c:{}", failed_line);
+    }
+
+    #[test]
+    fn test_c_pot() {
+        let failed_line = unsafe {
+            aes_proofs_capi_test_pot(
+                pot::test_data::ID.as_ptr(),
+                pot::test_data::SEED.as_ptr(),
+                pot::test_data::CORRECT_PROOF_16.as_ptr(),
+                pot::test_data::CORRECT_PROOF_12.as_ptr(),
+            )
+        };
+        assert_eq!(failed_line, 0, "src/capi/test.c:{}", failed_line);
+    }
+
+    #[test]
+    fn test_header() {
+        assert!(
+            include_str!("../include/aes_proofs.h")
+                == include_str!(concat!(env!("OUT_DIR"), "/aes_proofs.h")),
+            "include/aes_proofs.h is outdated, copy generated header from {}",
+            env!("OUT_DIR"),
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let mut keys = ptr::null_mut();
+            assert_eq!(
+                aes_proofs_keys_new(ptr::null(), &mut keys),
+                AesProofsStatus::NullPointer,
+            );
+            assert_eq!(
+                aes_proofs_keys_new(pot::test_data::ID.as_ptr(), &mut keys),
+                AesProofsStatus::Ok,
+            );
+
+            let mut proof = [0u8; 16 * BLOCK_SIZE];
+            assert_eq!(
+                aes_proofs_pot_prove(
+                    keys,
+                    pot::test_data::SEED.as_ptr(),
+                    100,
+                    16,
+                    proof.as_mut_ptr(),
+                    proof.len(),
+                ),
+                AesProofsStatus::InvalidParameter,
+            );
+            assert_eq!(
+                aes_proofs_pot_prove(
+                    keys,
+                    pot::test_data::SEED.as_ptr(),
+                    288,
+                    16,
+                    proof.as_mut_ptr(),
+                    proof.len() - 1,
+                ),
+                AesProofsStatus::InvalidLength,
+            );
+
+            let mut valid = true;
+            assert_eq!(
+                aes_proofs_pot_verify(
+                    keys,
+                    pot::test_data::SEED.as_ptr(),
+                    288,
+                    proof.as_ptr(),
+                    5 * BLOCK_SIZE,
+                    &mut valid,
+                ),
+                AesProofsStatus::InvalidParameter,
+            );
+
+            let mut por = ptr::null_mut();
+            assert_eq!(aes_proofs_por_new(&mut por), AesProofsStatus::Ok);
+
+            let mut pieces = [0u8; 2 * PIECE_SIZE];
+            let ivs = [0u8; 2 * BLOCK_SIZE];
+            assert_eq!(
+                aes_proofs_por_encode(
+                    por,
+                    keys,
+                    pieces.as_mut_ptr(),
+                    pieces.len(),
+                    ivs.as_ptr(),
+                    BLOCK_SIZE,
+                    256,
+                    1,
+                ),
+                AesProofsStatus::InvalidLength,
+            );
+            assert_eq!(
+                aes_proofs_por_decode(
+                    por,
+                    keys,
+                    pieces.as_mut_ptr(),
+                    pieces.len(),
+                    ivs.as_ptr(),
+                    ivs.len(),
+                    256,
+                    0,
+                ),
+                AesProofsStatus::InvalidParameter,
+            );
+            assert_eq!(
+                aes_proofs_por_encode(
+                    por,
+                    ptr::null(),
+                    pieces.as_mut_ptr(),
+                    pieces.len(),
+                    ivs.as_ptr(),
+                    ivs.len(),
+                    256,
+                    1,
+                ),
+                AesProofsStatus::NullPointer,
+            );
+
+            aes_proofs_por_free(por);
+            aes_proofs_keys_free(keys);
+            aes_proofs_keys_free(ptr::null_mut());
+        }
+    }
+}
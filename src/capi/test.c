@@ -0,0 +1,115 @@
+/* Runs test vectors through the C API, each function returns 0 on success or the line of the first
+ * failed check, test vectors are passed from Rust tests in `src/capi.rs` */
+
+#include <string.h>
+
+#include "aes_proofs.h"
+
+#define CHECK(condition) \
+    do { \
+        if (!(condition)) { \
+            return __LINE__; \
+        } \
+    } while (0)
+
+#define POR_AES_ITERATIONS 256
+#define POT_AES_ITERATIONS 288
+#define PIECES 5
+
+int aes_proofs_capi_test_por(const uint8_t *id,
+                             const uint8_t *iv,
+                             const uint8_t *input,
+                             const uint8_t *correct_encoding,
+                             const uint8_t *correct_encoding_breadth_10) {
+    static uint8_t pieces[PIECES * AES_PROOFS_PIECE_SIZE];
+    uint8_t ivs[PIECES * AES_PROOFS_BLOCK_SIZE];
+    AesProofsKeys *keys = NULL;
+    AesProofsPor *por = NULL;
+    size_t i;
+
+    CHECK(aes_proofs_keys_new(id, &keys) == AES_PROOFS_STATUS_OK);
+    CHECK(aes_proofs_por_new(&por) == AES_PROOFS_STATUS_OK);
+
+    /* Not a multiple of any batch size to check padding */
+    for (i = 0; i < PIECES; i++) {
+        memcpy(pieces + i * AES_PROOFS_PIECE_SIZE, input, AES_PROOFS_PIECE_SIZE);
+        memcpy(ivs + i * AES_PROOFS_BLOCK_SIZE, iv, AES_PROOFS_BLOCK_SIZE);
+    }
+
+    CHECK(aes_proofs_por_encode(por, keys, pieces, sizeof(pieces), ivs, sizeof(ivs),
+                                POR_AES_ITERATIONS, 1) == AES_PROOFS_STATUS_OK);
+    for (i = 0; i < PIECES; i++) {
+        CHECK(memcmp(pieces + i * AES_PROOFS_PIECE_SIZE, correct_encoding,
+                     AES_PROOFS_PIECE_SIZE) == 0);
+    }
+
+    CHECK(aes_proofs_por_decode(por, keys, pieces, sizeof(pieces), ivs, sizeof(ivs),
+                                POR_AES_ITERATIONS, 1) == AES_PROOFS_STATUS_OK);
+    for (i = 0; i < PIECES; i++) {
+        CHECK(memcmp(pieces + i * AES_PROOFS_PIECE_SIZE, input, AES_PROOFS_PIECE_SIZE) == 0);
+    }
+
+    CHECK(aes_proofs_por_encode(por, keys, pieces, AES_PROOFS_PIECE_SIZE, ivs,
+                                AES_PROOFS_BLOCK_SIZE, POR_AES_ITERATIONS,
+                                10) == AES_PROOFS_STATUS_OK);
+    CHECK(memcmp(pieces, correct_encoding_breadth_10, AES_PROOFS_PIECE_SIZE) == 0);
+
+    CHECK(aes_proofs_por_decode(por, keys, pieces, AES_PROOFS_PIECE_SIZE, ivs,
+                                AES_PROOFS_BLOCK_SIZE, POR_AES_ITERATIONS,
+                                10) == AES_PROOFS_STATUS_OK);
+    CHECK(memcmp(pieces, input, AES_PROOFS_PIECE_SIZE) == 0);
+
+    CHECK(aes_proofs_por_encode(por, keys, pieces, AES_PROOFS_PIECE_SIZE - 1, ivs,
+                                AES_PROOFS_BLOCK_SIZE, POR_AES_ITERATIONS,
+                                1) == AES_PROOFS_STATUS_INVALID_LENGTH);
+    CHECK(aes_proofs_por_encode(NULL, keys, pieces, AES_PROOFS_PIECE_SIZE, ivs,
+                                AES_PROOFS_BLOCK_SIZE, POR_AES_ITERATIONS,
+                                1) == AES_PROOFS_STATUS_NULL_POINTER);
+
+    aes_proofs_por_free(por);
+    aes_proofs_keys_free(keys);
+
+    return 0;
+}
+
+int aes_proofs_capi_test_pot(const uint8_t *id,
+                             const uint8_t *seed,
+                             const uint8_t *correct_proof_16,
+                             const uint8_t *correct_proof_12) {
+    uint8_t proof[16 * AES_PROOFS_BLOCK_SIZE];
+    AesProofsKeys *keys = NULL;
+    bool valid = false;
+
+    CHECK(aes_proofs_keys_new(id, &keys) == AES_PROOFS_STATUS_OK);
+
+    CHECK(aes_proofs_pot_prove(keys, seed, POT_AES_ITERATIONS, 16, proof,
+                               16 * AES_PROOFS_BLOCK_SIZE) == AES_PROOFS_STATUS_OK);
+    CHECK(memcmp(proof, correct_proof_16, 16 * AES_PROOFS_BLOCK_SIZE) == 0);
+
+    CHECK(aes_proofs_pot_prove(keys, seed, POT_AES_ITERATIONS, 12, proof,
+                               12 * AES_PROOFS_BLOCK_SIZE) == AES_PROOFS_STATUS_OK);
+    CHECK(memcmp(proof, correct_proof_12, 12 * AES_PROOFS_BLOCK_SIZE) == 0);
+
+    CHECK(aes_proofs_pot_verify(keys, seed, POT_AES_ITERATIONS, correct_proof_16,
+                                16 * AES_PROOFS_BLOCK_SIZE, &valid) == AES_PROOFS_STATUS_OK);
+    CHECK(valid);
+
+    CHECK(aes_proofs_pot_verify(keys, seed, POT_AES_ITERATIONS, correct_proof_12,
+                                12 * AES_PROOFS_BLOCK_SIZE, &valid) == AES_PROOFS_STATUS_OK);
+    CHECK(valid);
+
+    memset(proof, 42, sizeof(proof));
+    CHECK(aes_proofs_pot_verify(keys, seed, POT_AES_ITERATIONS, proof,
+                                16 * AES_PROOFS_BLOCK_SIZE, &valid) == AES_PROOFS_STATUS_OK);
+    CHECK(!valid);
+
+    CHECK(aes_proofs_pot_prove(keys, seed, 100, 16, proof, 16 * AES_PROOFS_BLOCK_SIZE) ==
+          AES_PROOFS_STATUS_INVALID_PARAMETER);
+    CHECK(aes_proofs_pot_verify(keys, NULL, POT_AES_ITERATIONS, proof,
+                                16 * AES_PROOFS_BLOCK_SIZE, &valid) ==
+          AES_PROOFS_STATUS_NULL_POINTER);
+
+    aes_proofs_keys_free(keys);
+
+    return 0;
+}
@@ -0,0 +1,167 @@
+//! AES-128 based Matyas–Meyer–Oseas hash with 128-bit output.
+//!
+//! Message is padded with `0x80` byte, zeroes and its length in bits as 64-bit little-endian
+//! integer to a multiple of block size, then every block is compressed into the state:
+//! `state = AES(state, block) ^ block`. Initial state is the domain, so the same message hashed in
+//! different domains produces unrelated hashes.
+//!
+//! Output is a single AES block, so collision resistance is limited to 2^64 work, which is fine
+//! for committing to replicas, but not for general-purpose use.
+
+#[cfg(target_arch = "x86_64")]
+use crate::aes_low_level::aes_ni;
+use crate::aes_low_level::software;
+use crate::utils::AesImplementation;
+use crate::Block;
+use crate::BLOCK_SIZE;
+
+pub type Hash = Block;
+
+/// Domain of regular hashes, other domains are used by Merkle trees
+pub const DOMAIN_DEFAULT: u8 = 0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Implementation {
+    #[cfg(target_arch = "x86_64")]
+    AesNi,
+    Software,
+}
+
+/// AES-128 Matyas–Meyer–Oseas hash, uses AES-NI when available
+#[derive(Debug, Copy, Clone)]
+pub struct AesHash {
+    implementation: Implementation,
+}
+
+impl Default for AesHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AesHash {
+    /// The fastest implementation available on this machine
+    pub fn new() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if crate::utils::ensure_available(AesImplementation::AesNi).is_ok() {
+                return Self {
+                    implementation: Implementation::AesNi,
+                };
+            }
+        }
+
+        Self::software()
+    }
+
+    /// Software implementation, always available, produces the same results
+    pub fn software() -> Self {
+        Self {
+            implementation: Implementation::Software,
+        }
+    }
+
+    /// Which instruction set is used, `None` for software implementation
+    pub fn aes_implementation(&self) -> Option<AesImplementation> {
+        match self.implementation {
+            #[cfg(target_arch = "x86_64")]
+            Implementation::AesNi => Some(AesImplementation::AesNi),
+            Implementation::Software => None,
+        }
+    }
+
+    pub fn hash(&self, data: &[u8]) -> Hash {
+        self.hash_in_domain(DOMAIN_DEFAULT, data)
+    }
+
+    /// Hash in specific domain, used for domain separation
+    pub fn hash_in_domain(&self, domain: u8, data: &[u8]) -> Hash {
+        let mut state = Hash::default();
+        state[0] = domain;
+
+        let whole_blocks = data.len() / BLOCK_SIZE * BLOCK_SIZE;
+        let (data, remainder) = data.split_at(whole_blocks);
+        state = self.compress(state, data);
+
+        // Padding and length take one or two blocks
+        let mut last_blocks = [0u8; BLOCK_SIZE * 2];
+        last_blocks[..remainder.len()].copy_from_slice(remainder);
+        last_blocks[remainder.len()] = 0x80;
+        let last_blocks_len = if remainder.len() < BLOCK_SIZE - 8 {
+            BLOCK_SIZE
+        } else {
+            BLOCK_SIZE * 2
+        };
+        let length_bits = ((whole_blocks + remainder.len()) as u64).wrapping_mul(8);
+        last_blocks[(last_blocks_len - 8)..last_blocks_len]
+            .copy_from_slice(&length_bits.to_le_bytes());
+
+        self.compress(state, &last_blocks[..last_blocks_len])
+    }
+
+    fn compress(&self, state: Hash, data: &[u8]) -> Hash {
+        match self.implementation {
+            // Safety: only selected when AES-NI is available
+            #[cfg(target_arch = "x86_64")]
+            Implementation::AesNi => unsafe { aes_ni::mmo_low_level(state, data) },
+            Implementation::Software => software::mmo(state, data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_frast::aes_core::block_enc_k128;
+    use aes_frast::aes_core::setkey_enc_k128;
+    use rand::Rng;
+
+    /// Straightforward implementation of the same construction with independent AES
+    fn reference(domain: u8, data: &[u8]) -> Hash {
+        let mut message = data.to_vec();
+        message.push(0x80);
+        while message.len() % BLOCK_SIZE != BLOCK_SIZE - 8 {
+            message.push(0);
+        }
+        message.extend_from_slice(&(data.len() as u64 * 8).to_le_bytes());
+
+        let mut state = [0u8; BLOCK_SIZE];
+        state[0] = domain;
+        for block in message.chunks_exact(BLOCK_SIZE) {
+            let mut keys = [0u32; 44];
+            setkey_enc_k128(&state, &mut keys);
+            block_enc_k128(block, &mut state, &keys);
+            for (state, byte) in state.iter_mut().zip(block.iter()) {
+                *state ^= byte;
+            }
+        }
+
+        state
+    }
+
+    #[test]
+    fn test_reference() {
+        let mut data = [0u8; 100];
+        rand::thread_rng().fill(&mut data[..]);
+
+        for &hasher in [AesHash::new(), AesHash::software()].iter() {
+            for size in 0..data.len() {
+                assert_eq!(
+                    hasher.hash(&data[..size]),
+                    reference(DOMAIN_DEFAULT, &data[..size]),
+                    "{:?} size {}",
+                    hasher.aes_implementation(),
+                    size,
+                );
+            }
+            assert_eq!(hasher.hash_in_domain(3, &data), reference(3, &data));
+        }
+    }
+
+    #[test]
+    fn test_domains() {
+        let hasher = AesHash::new();
+        assert_ne!(hasher.hash(b"abc"), hasher.hash_in_domain(1, b"abc"));
+        assert_ne!(hasher.hash(b""), hasher.hash(&[0]));
+    }
+}
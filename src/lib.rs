@@ -1,6 +1,19 @@
+//! Without `std` feature (enabled by default) the crate is `no_std` and only needs `alloc`, in
+//! which case parallel (rayon-based) APIs, dispatcher and OpenCL are not available and available
+//! instruction sets are detected at compile time
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 mod aes_low_level;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod hash;
+pub mod merkle;
 pub mod por;
 pub mod pot;
+pub mod round_keys;
 pub mod utils;
 
 pub const BLOCK_SIZE: usize = 16;
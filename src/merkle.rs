@@ -0,0 +1,312 @@
+//! Merkle tree over encoded pieces (or over blocks of a piece) built with `AesHash`.
+//!
+//! Leaves are hashed in `DOMAIN_LEAF` and inner nodes are hashes of concatenated children in
+//! `DOMAIN_NODE`, so leaves can't be confused with inner nodes. When a level has odd number of
+//! nodes, the last one is promoted to the next level as is. Root doesn't commit to the number of
+//! leaves, verifiers must know it upfront.
+
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::Piece;
+use crate::BLOCK_SIZE;
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub const DOMAIN_LEAF: u8 = 1;
+pub const DOMAIN_NODE: u8 = 2;
+
+fn hash_leaf(hasher: &AesHash, leaf: &[u8]) -> Hash {
+    hasher.hash_in_domain(DOMAIN_LEAF, leaf)
+}
+
+fn hash_node(hasher: &AesHash, left: &Hash, right: &Hash) -> Hash {
+    let mut children = [0u8; BLOCK_SIZE * 2];
+    children[..BLOCK_SIZE].copy_from_slice(left);
+    children[BLOCK_SIZE..].copy_from_slice(right);
+    hasher.hash_in_domain(DOMAIN_NODE, &children)
+}
+
+/// Merkle tree with all levels kept in memory for generating inclusion proofs
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    hasher: AesHash,
+    /// Leaf hashes first, root last
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// Builds tree from arbitrary leaves, panics if there are no leaves
+    pub fn new<'a, I>(hasher: AesHash, leaves: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let leaf_hashes = leaves
+            .into_iter()
+            .map(|leaf| hash_leaf(&hasher, leaf))
+            .collect::<Vec<_>>();
+        assert!(
+            !leaf_hashes.is_empty(),
+            "Merkle tree needs at least one leaf"
+        );
+
+        let mut levels = vec![leaf_hashes];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(&hasher, left, right),
+                    [promoted] => *promoted,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+
+        Self { hasher, levels }
+    }
+
+    /// Tree with one leaf per encoded piece
+    pub fn from_pieces(hasher: AesHash, pieces: &[Piece]) -> Self {
+        Self::new(hasher, pieces.iter().map(|piece| &piece[..]))
+    }
+
+    /// Tree with one leaf per block of a piece
+    pub fn from_piece_blocks(hasher: AesHash, piece: &Piece) -> Self {
+        Self::new(hasher, piece.chunks_exact(BLOCK_SIZE))
+    }
+
+    pub fn root(&self) -> Hash {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Number of leaves
+    pub fn leaves(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Inclusion proof for leaf `index`, `None` if there is no such leaf
+    pub fn prove(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.leaves() {
+            return None;
+        }
+
+        let mut siblings = Vec::with_capacity(self.levels.len());
+        let mut position = index;
+        for level in &self.levels[..(self.levels.len() - 1)] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+
+        Some(MerkleProof { index, siblings })
+    }
+
+    pub fn hasher(&self) -> &AesHash {
+        &self.hasher
+    }
+}
+
+/// Computes root of `MerkleTree` from leaves added one at a time without keeping the tree in
+/// memory, only one pending node per level is stored
+#[derive(Debug, Clone)]
+pub struct MerkleRootBuilder {
+    hasher: AesHash,
+    /// Roots of complete subtrees, from the largest to the smallest
+    pending: [Hash; 64],
+    leaves: u64,
+}
+
+impl MerkleRootBuilder {
+    pub fn new(hasher: AesHash) -> Self {
+        Self {
+            hasher,
+            pending: [Hash::default(); 64],
+            leaves: 0,
+        }
+    }
+
+    pub fn push(&mut self, leaf: &[u8]) {
+        let mut hash = hash_leaf(&self.hasher, leaf);
+        // Every trailing one bit in the number of leaves is a complete subtree to the left
+        let mut pending = self.pending_count();
+        let mut leaves = self.leaves;
+        while leaves & 1 == 1 {
+            pending -= 1;
+            hash = hash_node(&self.hasher, &self.pending[pending], &hash);
+            leaves >>= 1;
+        }
+        self.pending[pending] = hash;
+        self.leaves += 1;
+    }
+
+    /// Number of leaves added so far
+    pub fn leaves(&self) -> u64 {
+        self.leaves
+    }
+
+    /// Same as `MerkleTree::root()` for the same leaves, `None` if there are no leaves
+    pub fn root(&self) -> Option<Hash> {
+        let pending = &self.pending[..self.pending_count()];
+        // Incomplete subtrees on the right are promoted until they meet subtrees on the left
+        let (last, rest) = pending.split_last()?;
+        Some(
+            rest.iter()
+                .rev()
+                .fold(*last, |hash, left| hash_node(&self.hasher, left, &hash)),
+        )
+    }
+
+    fn pending_count(&self) -> usize {
+        self.leaves.count_ones() as usize
+    }
+}
+
+/// Proof that a leaf is included in Merkle tree with specific root
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MerkleProof {
+    /// Index of the leaf
+    pub index: usize,
+    /// Hashes of siblings from the leaf level up, promoted nodes have no sibling
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+    /// Checks that `leaf` is at `self.index` in tree with `leaves` leaves and `root`
+    pub fn verify(&self, hasher: &AesHash, root: &Hash, leaves: usize, leaf: &[u8]) -> bool {
+        self.compute_root(hasher, leaves, leaf).as_ref() == Some(root)
+    }
+
+    /// Root of tree with `leaves` leaves that has `leaf` at `self.index` according to this proof,
+    /// `None` if proof doesn't match the shape of such tree
+    pub fn compute_root(&self, hasher: &AesHash, leaves: usize, leaf: &[u8]) -> Option<Hash> {
+        if self.index >= leaves {
+            return None;
+        }
+
+        let mut hash = hash_leaf(hasher, leaf);
+        let mut siblings = self.siblings.iter();
+        let mut position = self.index;
+        let mut width = leaves;
+        while width > 1 {
+            if position % 2 == 1 {
+                hash = hash_node(hasher, siblings.next()?, &hash);
+            } else if position + 1 < width {
+                hash = hash_node(hasher, &hash, siblings.next()?);
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+
+        if siblings.next().is_some() {
+            return None;
+        }
+
+        Some(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::test_data::INPUT;
+    use crate::PIECE_SIZE;
+
+    fn leaves(count: usize) -> Vec<[u8; 3]> {
+        (0..count).map(|i| [i as u8, 1, 2]).collect()
+    }
+
+    #[test]
+    fn test_proofs() {
+        let hasher = AesHash::new();
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let tree = MerkleTree::new(hasher, leaves.iter().map(|leaf| &leaf[..]));
+            assert_eq!(tree.leaves(), count);
+            let root = tree.root();
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = tree.prove(index).unwrap();
+                assert!(
+                    proof.verify(&hasher, &root, count, leaf),
+                    "{} {}",
+                    count,
+                    index
+                );
+
+                // Wrong leaf, index, number of leaves or root
+                assert!(!proof.verify(&hasher, &root, count, &[0xff, 1, 2]));
+                assert!(!proof.verify(&hasher, &root, index, leaf));
+                assert!(!proof.verify(&hasher, &[0u8; BLOCK_SIZE], count, leaf));
+                if count > 1 {
+                    let mut wrong_index = proof.clone();
+                    wrong_index.index = (index + 1) % count;
+                    assert!(!wrong_index.verify(&hasher, &root, count, leaf));
+
+                    let mut tampered = proof.clone();
+                    tampered.siblings[0][0] ^= 1;
+                    assert!(!tampered.verify(&hasher, &root, count, leaf));
+                }
+
+                let mut extended = proof;
+                extended.siblings.push(root);
+                assert!(!extended.verify(&hasher, &root, count, leaf));
+            }
+            assert!(tree.prove(count).is_none());
+        }
+    }
+
+    #[test]
+    fn test_structure() {
+        let hasher = AesHash::software();
+        let leaves = leaves(3);
+        let tree = MerkleTree::new(hasher, leaves.iter().map(|leaf| &leaf[..]));
+
+        let leaf_hashes = leaves
+            .iter()
+            .map(|leaf| hasher.hash_in_domain(DOMAIN_LEAF, leaf))
+            .collect::<Vec<_>>();
+        let left = hash_node(&hasher, &leaf_hashes[0], &leaf_hashes[1]);
+        assert_eq!(tree.root(), hash_node(&hasher, &left, &leaf_hashes[2]));
+
+        let single = MerkleTree::new(hasher, leaves.iter().take(1).map(|leaf| &leaf[..]));
+        assert_eq!(single.root(), leaf_hashes[0]);
+        assert!(single.prove(0).unwrap().siblings.is_empty());
+    }
+
+    #[test]
+    fn test_root_builder() {
+        let hasher = AesHash::new();
+        let mut builder = MerkleRootBuilder::new(hasher);
+        assert_eq!(builder.root(), None);
+
+        let leaves = leaves(70);
+        for (count, leaf) in leaves.iter().enumerate() {
+            builder.push(leaf);
+            let tree = MerkleTree::new(hasher, leaves[..=count].iter().map(|leaf| &leaf[..]));
+            assert_eq!(builder.root(), Some(tree.root()), "{}", count + 1);
+            assert_eq!(builder.leaves(), count as u64 + 1);
+        }
+    }
+
+    #[test]
+    fn test_pieces() {
+        let hasher = AesHash::new();
+        let mut pieces = vec![INPUT; 5];
+        pieces[3][0] ^= 1;
+
+        let tree = MerkleTree::from_pieces(hasher, &pieces);
+        assert_eq!(tree.leaves(), 5);
+        let proof = tree.prove(3).unwrap();
+        assert!(proof.verify(&hasher, &tree.root(), 5, &pieces[3]));
+        assert!(!proof.verify(&hasher, &tree.root(), 5, &pieces[2]));
+
+        let tree = MerkleTree::from_piece_blocks(hasher, &pieces[3]);
+        assert_eq!(tree.leaves(), PIECE_SIZE / BLOCK_SIZE);
+        let proof = tree.prove(0).unwrap();
+        assert_eq!(proof.siblings.len(), 8);
+        assert!(proof.verify(&hasher, &tree.root(), 256, &pieces[3][..BLOCK_SIZE]));
+    }
+}
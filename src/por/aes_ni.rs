@@ -1,12 +1,26 @@
+use crate::aes128_load;
 use crate::aes128_load4;
+use crate::aes128_store;
 use crate::aes128_store4;
 use crate::aes_low_level::aes_ni;
 use crate::aes_low_level::aes_ni::ExpandedKeys;
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleRootBuilder;
+use crate::por::iv::IvDerivation;
+use crate::por::self_test;
 use crate::por::utils;
+use crate::round_keys::RoundKeys;
+use crate::utils::AesImplementation;
+use crate::utils::SelfTestFailed;
+use crate::utils::UnsupportedCpu;
 use crate::Block;
 use crate::Piece;
 use crate::BLOCK_SIZE;
 use crate::PIECE_SIZE;
+use core::arch::x86_64::*;
+use core::ops::Range;
+use zeroize::Zeroize;
 
 pub struct AesNiKeys {
     keys_enc: ExpandedKeys,
@@ -14,17 +28,79 @@ pub struct AesNiKeys {
 }
 
 impl AesNiKeys {
-    pub fn new(id: &Block) -> Self {
-        let (keys_enc, keys_dec) = aes_ni::expand(id);
+    /// Returns an error if AES-NI is not supported by this CPU
+    pub fn new(id: &Block) -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::AesNi)?;
+        // Safety: checked above that AES-NI is available
+        let (keys_enc, keys_dec) = unsafe { aes_ni::expand(id) };
+        Ok(Self { keys_enc, keys_dec })
+    }
+
+    /// Only loads round keys into registers, doesn't require AES-NI
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let keys_enc = aes_ni::expanded_keys_from_round_keys(round_keys.enc());
+        let keys_dec = aes_ni::expanded_keys_from_round_keys(round_keys.dec());
         Self { keys_enc, keys_dec }
     }
 }
 
-pub struct AesNi;
+impl Drop for AesNiKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+pub struct AesNi {
+    // Can only be constructed after checking that AES-NI is available
+    _private: (),
+}
 
 impl AesNi {
-    pub fn new() -> Self {
-        Self {}
+    /// Returns an error if AES-NI is not supported by this CPU
+    pub fn new() -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::AesNi)?;
+        Ok(Self { _private: () })
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = AesNiKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+
+        let mut pieces = [self_test::INPUT; 4];
+        self.encode(
+            &mut pieces,
+            &keys,
+            [self_test::IV; 4],
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        let mut pieces_x8 = [self_test::INPUT; 8];
+        self.encode_x8(
+            &mut pieces_x8,
+            &keys,
+            [self_test::IV; 8],
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_encodings(&pieces)?;
+        self_test::check_encodings(&pieces_x8)?;
+
+        self.decode(
+            &mut pieces[0],
+            &keys,
+            &self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self.decode_x8(
+            &mut pieces_x8[0],
+            &keys,
+            &self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_decodings(&[pieces[0], pieces_x8[0]])
     }
 
     /// Pipelined proof of replication encoding with AES-NI
@@ -37,10 +113,49 @@ impl AesNi {
         breadth_iterations: usize,
     ) {
         for _ in 0..breadth_iterations {
-            ivs = encode_internal(pieces, keys.keys_enc, ivs, aes_iterations);
+            // Safety: `AesNi` can only be constructed when AES-NI is available
+            ivs = unsafe { encode_internal(pieces, keys.keys_enc, ivs, aes_iterations, None) };
         }
     }
 
+    /// Same as `encode()`, also returns roots of Merkle trees over blocks of encoded pieces (see
+    /// `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so pieces
+    /// don't need to be read again
+    pub fn seal(
+        &self,
+        pieces: &mut [Piece; 4],
+        keys: &AesNiKeys,
+        ivs: [Block; 4],
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> [Hash; 4] {
+        utils::seal(hasher, ivs, breadth_iterations, |ivs, builders| {
+            // Safety: `AesNi` can only be constructed when AES-NI is available
+            unsafe { encode_internal(pieces, keys.keys_enc, ivs, aes_iterations, builders) }
+        })
+    }
+
+    /// Same as `encode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_indexed(
+        &self,
+        pieces: &mut [Piece; 4],
+        keys: &AesNiKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode(
+            pieces,
+            keys,
+            iv_derivation.piece_ivs(first_index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
     /// Pipelined proof of replication decoding with AES-NI
     pub fn decode(
         &self,
@@ -50,20 +165,162 @@ impl AesNi {
         aes_iterations: usize,
         breadth_iterations: usize,
     ) {
-        for _ in 1..breadth_iterations {
-            decode_internal(piece, keys.keys_dec, None, aes_iterations);
+        // Safety: `AesNi` can only be constructed when AES-NI is available
+        unsafe {
+            for _ in 1..breadth_iterations {
+                decode_internal(piece, keys.keys_dec, None, aes_iterations);
+            }
+
+            decode_internal(piece, keys.keys_dec, Some(iv), aes_iterations);
+        }
+    }
+
+    /// Same as `decode()`, IV is derived from piece index
+    pub fn decode_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &AesNiKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.decode(
+            piece,
+            keys,
+            &iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Decodes only blocks in `block_range` and blocks they depend on (see `DependencyCone`),
+    /// the rest of the piece is left in unspecified state
+    pub fn decode_range(
+        &self,
+        piece: &mut Piece,
+        keys: &AesNiKeys,
+        iv: &Block,
+        block_range: Range<usize>,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        utils::decode_range(block_range, breadth_iterations, |blocks, feedback| {
+            utils::decode_blocks_batched::<4, _>(
+                piece,
+                blocks,
+                feedback,
+                iv,
+                |blocks, feedback| {
+                    // Safety: `AesNi` can only be constructed when AES-NI is available
+                    unsafe {
+                        decode_4_blocks_internal(keys.keys_dec, blocks, feedback, aes_iterations);
+                    }
+                },
+            );
+        });
+    }
+
+    /// Pipelined proof of replication encoding with AES-NI, 8 pieces at a time
+    pub fn encode_x8(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &AesNiKeys,
+        mut ivs: [Block; 8],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        for _ in 0..breadth_iterations {
+            // Safety: `AesNi` can only be constructed when AES-NI is available
+            ivs = unsafe { encode_internal_x8(pieces, keys.keys_enc, ivs, aes_iterations, None) };
+        }
+    }
+
+    /// Same as `encode_x8()`, also returns roots of Merkle trees over blocks of encoded pieces (see
+    /// `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so pieces
+    /// don't need to be read again
+    pub fn seal_x8(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &AesNiKeys,
+        ivs: [Block; 8],
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> [Hash; 8] {
+        utils::seal(hasher, ivs, breadth_iterations, |ivs, builders| {
+            // Safety: `AesNi` can only be constructed when AES-NI is available
+            unsafe { encode_internal_x8(pieces, keys.keys_enc, ivs, aes_iterations, builders) }
+        })
+    }
+
+    /// Same as `encode_x8()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_x8_indexed(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &AesNiKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode_x8(
+            pieces,
+            keys,
+            iv_derivation.piece_ivs(first_index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Pipelined proof of replication decoding with AES-NI, 8 blocks at a time
+    pub fn decode_x8(
+        &self,
+        piece: &mut Piece,
+        keys: &AesNiKeys,
+        iv: &Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        // Safety: `AesNi` can only be constructed when AES-NI is available
+        unsafe {
+            for _ in 1..breadth_iterations {
+                decode_internal_x8(piece, keys.keys_dec, None, aes_iterations);
+            }
+
+            decode_internal_x8(piece, keys.keys_dec, Some(iv), aes_iterations);
         }
+    }
 
-        decode_internal(piece, keys.keys_dec, Some(iv), aes_iterations);
+    /// Same as `decode_x8()`, IV is derived from piece index
+    pub fn decode_x8_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &AesNiKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.decode_x8(
+            piece,
+            keys,
+            &iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
     }
 }
 
 /// Returns iv for the next round
-fn encode_internal(
+#[target_feature(enable = "aes,sse2")]
+unsafe fn encode_internal(
     pieces: &mut [Piece; 4],
     keys_reg: ExpandedKeys,
     mut ivs: [Block; 4],
     aes_iterations: usize,
+    mut builders: Option<&mut [MerkleRootBuilder; 4]>,
 ) -> [Block; 4] {
     let [piece0, piece1, piece2, piece3] = pieces;
 
@@ -74,9 +331,8 @@ fn encode_internal(
         .zip(piece3.chunks_exact_mut(BLOCK_SIZE))
         .map(|(((piece0, piece1), piece2), piece3)| [piece0, piece1, piece2, piece3])
         .for_each(|blocks| {
-            let mut blocks_reg =
-                unsafe { aes128_load4!(blocks[0], blocks[1], blocks[2], blocks[3]) };
-            let feedbacks_reg = unsafe { aes128_load4!(ivs[0], ivs[1], ivs[2], ivs[3]) };
+            let mut blocks_reg = aes128_load4!(blocks[0], blocks[1], blocks[2], blocks[3]);
+            let feedbacks_reg = aes128_load4!(ivs[0], ivs[1], ivs[2], ivs[3]);
 
             aes_ni::por_encode_pipelined_x4_low_level(
                 keys_reg,
@@ -85,16 +341,20 @@ fn encode_internal(
                 aes_iterations,
             );
 
-            unsafe {
-                aes128_store4!(blocks, blocks_reg);
-                aes128_store4!(ivs, blocks_reg);
+            aes128_store4!(blocks, blocks_reg);
+            aes128_store4!(ivs, blocks_reg);
+            if let Some(builders) = &mut builders {
+                for (builder, block) in builders.iter_mut().zip(blocks.iter()) {
+                    builder.push(block);
+                }
             }
         });
 
     ivs
 }
 
-fn decode_internal(
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decode_internal(
     piece: &mut Piece,
     keys_reg: ExpandedKeys,
     iv: Option<&Block>,
@@ -109,7 +369,8 @@ fn decode_internal(
     decode_4_blocks_internal(keys_reg, first_4_blocks, feedback, aes_iterations);
 }
 
-fn decode_4_blocks_internal(
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decode_4_blocks_internal(
     keys_reg: ExpandedKeys,
     blocks: &mut [u8],
     feedback: &Block,
@@ -119,8 +380,8 @@ fn decode_4_blocks_internal(
     let (mut block1, blocks) = blocks.split_at_mut(BLOCK_SIZE);
     let (mut block2, mut block3) = blocks.split_at_mut(BLOCK_SIZE);
 
-    let mut blocks_reg = unsafe { aes128_load4!(block0, block1, block2, block3) };
-    let feedbacks_reg = unsafe { aes128_load4!(feedback, block0, block1, block2) };
+    let mut blocks_reg = aes128_load4!(block0, block1, block2, block3);
+    let feedbacks_reg = aes128_load4!(feedback, block0, block1, block2);
 
     aes_ni::por_decode_pipelined_x4_low_level(
         keys_reg,
@@ -129,17 +390,106 @@ fn decode_4_blocks_internal(
         aes_iterations,
     );
 
-    unsafe {
-        aes128_store4!(
-            [&mut block0, &mut block1, &mut block2, &mut block3],
-            blocks_reg
+    aes128_store4!(
+        [&mut block0, &mut block1, &mut block2, &mut block3],
+        blocks_reg
+    );
+}
+
+/// Returns iv for the next round
+#[target_feature(enable = "aes,sse2")]
+unsafe fn encode_internal_x8(
+    pieces: &mut [Piece; 8],
+    keys_reg: ExpandedKeys,
+    ivs: [Block; 8],
+    aes_iterations: usize,
+    mut builders: Option<&mut [MerkleRootBuilder; 8]>,
+) -> [Block; 8] {
+    let mut feedbacks_reg = [_mm_setzero_si128(); 8];
+    for (feedback_reg, iv) in feedbacks_reg.iter_mut().zip(ivs.iter()) {
+        *feedback_reg = aes128_load!(iv);
+    }
+
+    for offset in (0..PIECE_SIZE).step_by(BLOCK_SIZE) {
+        let mut blocks_reg = [_mm_setzero_si128(); 8];
+        for (block_reg, piece) in blocks_reg.iter_mut().zip(pieces.iter()) {
+            *block_reg = aes128_load!(piece[offset..]);
+        }
+
+        aes_ni::por_encode_pipelined_x8_low_level(
+            keys_reg,
+            &mut blocks_reg,
+            feedbacks_reg,
+            aes_iterations,
         );
+
+        for (piece, block_reg) in pieces.iter_mut().zip(blocks_reg.iter()) {
+            aes128_store!(piece[offset..], *block_reg);
+        }
+        if let Some(builders) = &mut builders {
+            for (builder, piece) in builders.iter_mut().zip(pieces.iter()) {
+                builder.push(&piece[offset..(offset + BLOCK_SIZE)]);
+            }
+        }
+        feedbacks_reg = blocks_reg;
+    }
+
+    let mut ivs = [Block::default(); 8];
+    for (iv, feedback_reg) in ivs.iter_mut().zip(feedbacks_reg.iter()) {
+        aes128_store!(iv, *feedback_reg);
+    }
+
+    ivs
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decode_internal_x8(
+    piece: &mut Piece,
+    keys_reg: ExpandedKeys,
+    iv: Option<&Block>,
+    aes_iterations: usize,
+) {
+    for i in (1..(PIECE_SIZE / BLOCK_SIZE / 8)).rev() {
+        let (blocks, feedback) = utils::piece_to_blocks_and_feedback(piece, i, 8);
+        decode_8_blocks_internal(keys_reg, blocks, feedback, aes_iterations);
+    }
+
+    let (first_8_blocks, feedback) = utils::piece_to_first_blocks_and_feedback(piece, iv, 8);
+    decode_8_blocks_internal(keys_reg, first_8_blocks, feedback, aes_iterations);
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn decode_8_blocks_internal(
+    keys_reg: ExpandedKeys,
+    blocks: &mut [u8],
+    feedback: &Block,
+    aes_iterations: usize,
+) {
+    let mut blocks_reg = [_mm_setzero_si128(); 8];
+    let mut feedbacks_reg = [aes128_load!(feedback); 8];
+    for (i, block) in blocks.chunks_exact(BLOCK_SIZE).enumerate() {
+        blocks_reg[i] = aes128_load!(block);
+        if i < 7 {
+            feedbacks_reg[i + 1] = blocks_reg[i];
+        }
+    }
+
+    aes_ni::por_decode_pipelined_x8_low_level(
+        keys_reg,
+        &mut blocks_reg,
+        feedbacks_reg,
+        aes_iterations,
+    );
+
+    for (block, block_reg) in blocks.chunks_exact_mut(BLOCK_SIZE).zip(blocks_reg.iter()) {
+        aes128_store!(block, *block_reg);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle::MerkleTree;
     use crate::por::test_data::CORRECT_ENCODING;
     use crate::por::test_data::CORRECT_ENCODING_BREADTH_10;
     use crate::por::test_data::ID;
@@ -152,8 +502,8 @@ mod tests {
     fn test() {
         let aes_iterations = 256;
 
-        let keys = AesNiKeys::new(&ID);
-        let por = AesNi::new();
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let por = AesNi::new().unwrap();
 
         let mut encodings = [INPUT; 4];
         por.encode(&mut encodings, &keys, [IV; 4], aes_iterations, 1);
@@ -172,8 +522,8 @@ mod tests {
     fn test_breadth_10() {
         let aes_iterations = 256;
 
-        let keys = AesNiKeys::new(&ID);
-        let por = AesNi::new();
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let por = AesNi::new().unwrap();
 
         let mut encodings = [INPUT; 4];
         por.encode(&mut encodings, &keys, [IV; 4], aes_iterations, 10);
@@ -201,8 +551,8 @@ mod tests {
         let mut iv = [0u8; 16];
         rand::thread_rng().fill(&mut iv[..]);
 
-        let keys = AesNiKeys::new(&id);
-        let por = AesNi::new();
+        let keys = AesNiKeys::new(&id).unwrap();
+        let por = AesNi::new().unwrap();
 
         let mut encodings = [input; 4];
         por.encode(&mut encodings, &keys, [iv; 4], aes_iterations, 1);
@@ -228,8 +578,8 @@ mod tests {
         let mut iv = [0u8; 16];
         rand::thread_rng().fill(&mut iv[..]);
 
-        let keys = AesNiKeys::new(&id);
-        let por = AesNi::new();
+        let keys = AesNiKeys::new(&id).unwrap();
+        let por = AesNi::new().unwrap();
 
         let mut encodings = [input; 4];
         por.encode(&mut encodings, &keys, [iv; 4], aes_iterations, 10);
@@ -241,4 +591,93 @@ mod tests {
             assert_eq!(decoding.to_vec(), input.to_vec());
         }
     }
+
+    #[test]
+    fn test_x8() {
+        let aes_iterations = 256;
+
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let por = AesNi::new().unwrap();
+
+        let mut encodings = [INPUT; 8];
+        por.encode_x8(&mut encodings, &keys, [IV; 8], aes_iterations, 10);
+
+        for encoding in encodings.iter() {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+        }
+
+        let mut decoding = CORRECT_ENCODING_BREADTH_10;
+        por.decode_x8(&mut decoding, &keys, &IV, aes_iterations, 10);
+
+        assert_eq!(decoding.to_vec(), INPUT.to_vec());
+    }
+
+    #[test]
+    fn test_x8_random() {
+        let aes_iterations = 256;
+
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill(&mut id[..]);
+
+        let keys = AesNiKeys::new(&id).unwrap();
+        let por = AesNi::new().unwrap();
+
+        let mut inputs = [[0u8; PIECE_SIZE]; 8];
+        let mut ivs = [[0u8; 16]; 8];
+        for (input, iv) in inputs.iter_mut().zip(ivs.iter_mut()) {
+            rand::thread_rng().fill(&mut input[..]);
+            rand::thread_rng().fill(&mut iv[..]);
+        }
+
+        let mut encodings = inputs;
+        por.encode_x8(&mut encodings, &keys, ivs, aes_iterations, 1);
+
+        // Must match x4 encoding
+        for i in 0..2 {
+            let mut encodings_x4 = [[0u8; PIECE_SIZE]; 4];
+            encodings_x4.copy_from_slice(&inputs[i * 4..][..4]);
+            let mut ivs_x4 = [[0u8; 16]; 4];
+            ivs_x4.copy_from_slice(&ivs[i * 4..][..4]);
+            por.encode(&mut encodings_x4, &keys, ivs_x4, aes_iterations, 1);
+
+            for (encoding_x4, encoding) in encodings_x4.iter().zip(&encodings[i * 4..][..4]) {
+                assert_eq!(encoding_x4.to_vec(), encoding.to_vec());
+            }
+        }
+
+        for ((encoding, input), iv) in encodings.iter().zip(inputs.iter()).zip(ivs.iter()) {
+            let mut decoding = *encoding;
+            por.decode_x8(&mut decoding, &keys, iv, aes_iterations, 1);
+
+            assert_eq!(decoding.to_vec(), input.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(AesNi::new().unwrap().self_test(), Ok(()));
+    }
+
+    /// Sealing produces the same encoding as `encode()` and the same root as hashing it afterwards
+    #[test]
+    fn test_seal() {
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let por = AesNi::new().unwrap();
+        let hasher = AesHash::new();
+        let root = MerkleTree::from_piece_blocks(hasher, &CORRECT_ENCODING_BREADTH_10).root();
+
+        let mut encodings = [INPUT; 4];
+        let roots = por.seal(&mut encodings, &keys, [IV; 4], &hasher, 256, 10);
+        for (encoding, piece_root) in encodings.iter().zip(roots.iter()) {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+            assert_eq!(*piece_root, root);
+        }
+
+        let mut encodings = [INPUT; 8];
+        let roots = por.seal_x8(&mut encodings, &keys, [IV; 8], &hasher, 256, 10);
+        for (encoding, piece_root) in encodings.iter().zip(roots.iter()) {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+            assert_eq!(*piece_root, root);
+        }
+    }
 }
@@ -0,0 +1,558 @@
+//! Storage audit challenge/response protocol.
+//!
+//! Replica is committed to with two-level Merkle tree: every encoded piece has a tree over its
+//! blocks and roots of those trees are leaves of the tree over pieces (see `Commitment`).
+//! Challenges (piece index and block index) are derived from a random seed, prover answers with
+//! encoded blocks and inclusion proofs. For stronger checks prover can be asked for the whole
+//! encoded piece, which verifier decodes and compares against known plaintext commitment.
+
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleProof;
+use crate::merkle::MerkleTree;
+#[cfg(target_arch = "x86_64")]
+use crate::por::aes_ni::AesNi;
+#[cfg(target_arch = "x86_64")]
+use crate::por::aes_ni::AesNiKeys;
+use crate::por::software_lut::SoftwareLuT;
+use crate::por::software_lut::SoftwareLuTKeys;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::Piece;
+use crate::BLOCK_SIZE;
+use crate::PIECE_SIZE;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+
+/// Domain of hashes used for deriving challenges from seed
+pub const DOMAIN_CHALLENGE: u8 = 3;
+pub const BLOCKS_PER_PIECE: usize = PIECE_SIZE / BLOCK_SIZE;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AuditError {
+    /// Answer is for a different challenge
+    WrongChallenge,
+    /// Inclusion proof doesn't match commitment
+    InvalidProof,
+    /// Decoded piece doesn't match plaintext commitment
+    DecodingMismatch,
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::WrongChallenge => write!(f, "Answer is for a different challenge"),
+            AuditError::InvalidProof => write!(f, "Inclusion proof doesn't match commitment"),
+            AuditError::DecodingMismatch => {
+                write!(f, "Decoded piece doesn't match plaintext commitment")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AuditError {}
+
+/// Commitment to a sequence of pieces (encoded replica or plaintext)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Commitment {
+    /// Root of Merkle tree over roots of per-piece trees
+    pub root: Hash,
+    pub pieces: u64,
+}
+
+impl Commitment {
+    /// Panics if there are no pieces
+    pub fn new(hasher: AesHash, pieces: &[Piece]) -> Self {
+        CommitmentTree::new(hasher, pieces).commitment()
+    }
+}
+
+/// Root of Merkle tree over blocks of the piece
+fn piece_root(hasher: AesHash, piece: &Piece) -> Hash {
+    MerkleTree::from_piece_blocks(hasher, piece).root()
+}
+
+/// Tree over roots of per-piece trees, kept by the party that needs to produce proofs (prover for
+/// encoded replica or data owner for plaintext)
+#[derive(Debug, Clone)]
+pub struct CommitmentTree {
+    tree: MerkleTree,
+}
+
+impl CommitmentTree {
+    /// Panics if there are no pieces
+    pub fn new(hasher: AesHash, pieces: &[Piece]) -> Self {
+        let piece_roots = pieces
+            .iter()
+            .map(|piece| piece_root(hasher, piece))
+            .collect::<Vec<_>>();
+
+        Self::from_piece_roots(hasher, &piece_roots)
+    }
+
+    /// Tree over roots of Merkle trees over blocks of pieces (see
+    /// `MerkleTree::from_piece_blocks()`), for instance returned by sealing, panics if there are
+    /// no pieces
+    pub fn from_piece_roots(hasher: AesHash, piece_roots: &[Hash]) -> Self {
+        let tree = MerkleTree::new(hasher, piece_roots.iter().map(|root| &root[..]));
+
+        Self { tree }
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        Commitment {
+            root: self.tree.root(),
+            pieces: self.tree.leaves() as u64,
+        }
+    }
+
+    /// Proof of root of piece `piece_index`, `None` if there is no such piece
+    pub fn prove(&self, piece_index: u64) -> Option<MerkleProof> {
+        self.tree.prove(piece_index as usize)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Challenge {
+    pub piece_index: u64,
+    /// Index of block within the piece
+    pub block_index: usize,
+}
+
+/// Derives `count` challenges for replica with `pieces` pieces from `seed`, the same seed always
+/// produces the same challenges
+pub fn derive_challenges(
+    hasher: &AesHash,
+    seed: &Block,
+    pieces: u64,
+    count: usize,
+) -> Vec<Challenge> {
+    assert!(pieces > 0);
+
+    (0..count as u64)
+        .map(|counter| {
+            let mut input = [0u8; BLOCK_SIZE + 8];
+            input[..BLOCK_SIZE].copy_from_slice(seed);
+            input[BLOCK_SIZE..].copy_from_slice(&counter.to_le_bytes());
+            let hash = hasher.hash_in_domain(DOMAIN_CHALLENGE, &input);
+
+            // Bias is negligible since number of pieces is much smaller than 2^64
+            let piece_index = u64::from_le_bytes(hash[..8].try_into().unwrap()) % pieces;
+            let block_index =
+                u64::from_le_bytes(hash[8..].try_into().unwrap()) % BLOCKS_PER_PIECE as u64;
+
+            Challenge {
+                piece_index,
+                block_index: block_index as usize,
+            }
+        })
+        .collect()
+}
+
+/// Answer to a challenge with a single encoded block
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Answer {
+    pub challenge: Challenge,
+    pub block: Block,
+    /// Proof of block in the tree of the piece
+    pub block_proof: MerkleProof,
+    /// Proof of piece root in the tree over pieces
+    pub piece_proof: MerkleProof,
+}
+
+/// Answer with the whole encoded piece, so that it can be decoded by verifier
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PieceAnswer {
+    pub piece_index: u64,
+    pub piece: Piece,
+    /// Proof of piece root in the tree over pieces
+    pub piece_proof: MerkleProof,
+}
+
+/// Answers challenges for encoded replica it stores
+pub struct Prover<'a> {
+    hasher: AesHash,
+    replica: &'a [Piece],
+    piece_tree: CommitmentTree,
+}
+
+impl<'a> Prover<'a> {
+    /// Panics if replica is empty
+    pub fn new(hasher: AesHash, replica: &'a [Piece]) -> Self {
+        Self {
+            hasher,
+            replica,
+            piece_tree: CommitmentTree::new(hasher, replica),
+        }
+    }
+
+    /// Same as `new()` with tree that was already built for this replica, for instance by sealing
+    pub fn with_commitment_tree(
+        hasher: AesHash,
+        replica: &'a [Piece],
+        piece_tree: CommitmentTree,
+    ) -> Self {
+        Self {
+            hasher,
+            replica,
+            piece_tree,
+        }
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.piece_tree.commitment()
+    }
+
+    /// `None` if challenge is out of range
+    pub fn answer(&self, challenge: &Challenge) -> Option<Answer> {
+        let piece_index = challenge.piece_index as usize;
+        let piece = self.replica.get(piece_index)?;
+        let block_tree = MerkleTree::from_piece_blocks(self.hasher, piece);
+        let block_proof = block_tree.prove(challenge.block_index)?;
+        let offset = challenge.block_index * BLOCK_SIZE;
+
+        Some(Answer {
+            challenge: *challenge,
+            block: piece[offset..][..BLOCK_SIZE].try_into().unwrap(),
+            block_proof,
+            piece_proof: self.piece_tree.prove(challenge.piece_index)?,
+        })
+    }
+
+    /// `None` if piece index is out of range
+    pub fn answer_piece(&self, piece_index: u64) -> Option<PieceAnswer> {
+        let piece = self.replica.get(piece_index as usize)?;
+
+        Some(PieceAnswer {
+            piece_index,
+            piece: *piece,
+            piece_proof: self.piece_tree.prove(piece_index)?,
+        })
+    }
+}
+
+enum Decoder {
+    #[cfg(target_arch = "x86_64")]
+    AesNi(AesNi),
+    SoftwareLuT(SoftwareLuT),
+}
+
+/// Verifies answers against replica commitment
+pub struct Verifier {
+    hasher: AesHash,
+    commitment: Commitment,
+    decoder: Decoder,
+}
+
+impl Verifier {
+    /// Decoding uses AES-NI when available and software implementation otherwise
+    pub fn new(hasher: AesHash, commitment: Commitment) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Ok(aes_ni) = AesNi::new() {
+                return Self {
+                    hasher,
+                    commitment,
+                    decoder: Decoder::AesNi(aes_ni),
+                };
+            }
+        }
+
+        Self {
+            hasher,
+            commitment,
+            decoder: Decoder::SoftwareLuT(SoftwareLuT::new()),
+        }
+    }
+
+    /// Challenges for this replica, see `derive_challenges()`
+    pub fn challenges(&self, seed: &Block, count: usize) -> Vec<Challenge> {
+        derive_challenges(&self.hasher, seed, self.commitment.pieces, count)
+    }
+
+    /// Checks that answered block is included in committed replica
+    pub fn verify(&self, challenge: &Challenge, answer: &Answer) -> Result<(), AuditError> {
+        if answer.challenge != *challenge || answer.block_proof.index != challenge.block_index {
+            return Err(AuditError::WrongChallenge);
+        }
+
+        let piece_root = answer
+            .block_proof
+            .compute_root(&self.hasher, BLOCKS_PER_PIECE, &answer.block)
+            .ok_or(AuditError::InvalidProof)?;
+        self.verify_piece_root(challenge.piece_index, &piece_root, &answer.piece_proof)
+    }
+
+    /// Checks that answered piece is included in committed replica
+    pub fn verify_piece(&self, piece_index: u64, answer: &PieceAnswer) -> Result<(), AuditError> {
+        if answer.piece_index != piece_index {
+            return Err(AuditError::WrongChallenge);
+        }
+
+        let piece_root = piece_root(self.hasher, &answer.piece);
+        self.verify_piece_root(piece_index, &piece_root, &answer.piece_proof)
+    }
+
+    /// Checks that answered piece is included in committed replica, decodes it and checks that
+    /// result is included in `plaintext` commitment at the same index with `plaintext_proof`
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_decoding(
+        &self,
+        piece_index: u64,
+        answer: &PieceAnswer,
+        plaintext: &Commitment,
+        plaintext_proof: &MerkleProof,
+        round_keys: &RoundKeys,
+        iv: &Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Result<(), AuditError> {
+        self.verify_piece(piece_index, answer)?;
+
+        let mut piece = answer.piece;
+        match &self.decoder {
+            #[cfg(target_arch = "x86_64")]
+            Decoder::AesNi(aes_ni) => aes_ni.decode(
+                &mut piece,
+                &AesNiKeys::from_round_keys(round_keys),
+                iv,
+                aes_iterations,
+                breadth_iterations,
+            ),
+            Decoder::SoftwareLuT(software_lut) => software_lut.decode(
+                &mut piece,
+                &SoftwareLuTKeys::from_round_keys(round_keys),
+                iv,
+                aes_iterations,
+                breadth_iterations,
+            ),
+        }
+
+        let valid = plaintext_proof.index as u64 == piece_index
+            && plaintext_proof.verify(
+                &self.hasher,
+                &plaintext.root,
+                plaintext.pieces as usize,
+                &piece_root(self.hasher, &piece),
+            );
+        if valid {
+            Ok(())
+        } else {
+            Err(AuditError::DecodingMismatch)
+        }
+    }
+
+    fn verify_piece_root(
+        &self,
+        piece_index: u64,
+        piece_root: &Hash,
+        piece_proof: &MerkleProof,
+    ) -> Result<(), AuditError> {
+        let valid = piece_proof.index as u64 == piece_index
+            && piece_proof.verify(
+                &self.hasher,
+                &self.commitment.root,
+                self.commitment.pieces as usize,
+                piece_root,
+            );
+        if valid {
+            Ok(())
+        } else {
+            Err(AuditError::InvalidProof)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::iv::IvDerivation;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use alloc::vec;
+
+    const AES_ITERATIONS: usize = 16;
+    const BREADTH_ITERATIONS: usize = 2;
+    const PIECES: usize = 5;
+
+    struct Replica {
+        round_keys: RoundKeys,
+        iv_derivation: IvDerivation,
+        plaintext: Vec<Piece>,
+        encoded: Vec<Piece>,
+    }
+
+    fn replica() -> Replica {
+        let round_keys = RoundKeys::new(&ID);
+        let iv_derivation = IvDerivation::from_round_keys(&round_keys, None);
+        let mut plaintext = vec![INPUT; PIECES];
+        for (index, piece) in plaintext.iter_mut().enumerate() {
+            piece[0] = index as u8;
+        }
+
+        let software_lut = SoftwareLuT::new();
+        let keys = SoftwareLuTKeys::from_round_keys(&round_keys);
+        let mut encoded = plaintext.clone();
+        for (index, piece) in encoded.iter_mut().enumerate() {
+            software_lut.encode_indexed(
+                piece,
+                &keys,
+                &iv_derivation,
+                index as u64,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+        }
+
+        Replica {
+            round_keys,
+            iv_derivation,
+            plaintext,
+            encoded,
+        }
+    }
+
+    #[test]
+    fn test_challenges() {
+        let hasher = AesHash::new();
+        let challenges = derive_challenges(&hasher, &[1u8; BLOCK_SIZE], 3, 100);
+        assert_eq!(challenges.len(), 100);
+        assert!(challenges
+            .iter()
+            .all(|challenge| challenge.piece_index < 3 && challenge.block_index < 256));
+        assert!((0..3).all(|index| challenges
+            .iter()
+            .any(|challenge| challenge.piece_index == index)));
+
+        assert_eq!(
+            challenges,
+            derive_challenges(&AesHash::software(), &[1u8; BLOCK_SIZE], 3, 100),
+        );
+        assert_ne!(
+            challenges,
+            derive_challenges(&hasher, &[2u8; BLOCK_SIZE], 3, 100),
+        );
+    }
+
+    #[test]
+    fn test_answers() {
+        let hasher = AesHash::new();
+        let replica = replica();
+        let prover = Prover::new(hasher, &replica.encoded);
+        let commitment = prover.commitment();
+        assert_eq!(commitment, Commitment::new(hasher, &replica.encoded));
+        assert_eq!(commitment.pieces, PIECES as u64);
+
+        let verifier = Verifier::new(hasher, commitment);
+        let challenges = verifier.challenges(&[3u8; BLOCK_SIZE], 20);
+        for challenge in &challenges {
+            let answer = prover.answer(challenge).unwrap();
+            assert_eq!(verifier.verify(challenge, &answer), Ok(()));
+
+            let mut tampered = answer.clone();
+            tampered.block[0] ^= 1;
+            assert_eq!(
+                verifier.verify(challenge, &tampered),
+                Err(AuditError::InvalidProof),
+            );
+
+            let mut tampered = answer.clone();
+            tampered.piece_proof.siblings[0][0] ^= 1;
+            assert_eq!(
+                verifier.verify(challenge, &tampered),
+                Err(AuditError::InvalidProof),
+            );
+
+            let other = Challenge {
+                piece_index: (challenge.piece_index + 1) % PIECES as u64,
+                block_index: challenge.block_index,
+            };
+            assert_eq!(
+                verifier.verify(&other, &answer),
+                Err(AuditError::WrongChallenge),
+            );
+
+            // Block of a different piece doesn't pass even with correct proofs of that piece
+            let mut forged = prover.answer(&other).unwrap();
+            forged.challenge = *challenge;
+            assert_eq!(
+                verifier.verify(challenge, &forged),
+                Err(AuditError::InvalidProof),
+            );
+        }
+
+        assert!(prover
+            .answer(&Challenge {
+                piece_index: PIECES as u64,
+                block_index: 0,
+            })
+            .is_none());
+        assert!(prover
+            .answer(&Challenge {
+                piece_index: 0,
+                block_index: BLOCKS_PER_PIECE,
+            })
+            .is_none());
+    }
+
+    #[test]
+    fn test_decoding() {
+        let hasher = AesHash::new();
+        let replica = replica();
+        let prover = Prover::new(hasher, &replica.encoded);
+        let verifier = Verifier::new(hasher, prover.commitment());
+        let plaintext_tree = CommitmentTree::new(hasher, &replica.plaintext);
+        let plaintext = plaintext_tree.commitment();
+
+        for piece_index in 0..PIECES as u64 {
+            let answer = prover.answer_piece(piece_index).unwrap();
+            let plaintext_proof = plaintext_tree.prove(piece_index).unwrap();
+            let iv = replica.iv_derivation.piece_iv(piece_index);
+            let verify = |answer: &PieceAnswer, iv: &Block| {
+                verifier.verify_decoding(
+                    piece_index,
+                    answer,
+                    &plaintext,
+                    &plaintext_proof,
+                    &replica.round_keys,
+                    iv,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                )
+            };
+
+            assert_eq!(verify(&answer, &iv), Ok(()));
+            assert_eq!(
+                verify(&answer, &replica.iv_derivation.piece_iv(piece_index + 1)),
+                Err(AuditError::DecodingMismatch),
+            );
+
+            let mut corrupted = answer.clone();
+            corrupted.piece[PIECE_SIZE - 1] ^= 1;
+            assert_eq!(verify(&corrupted, &iv), Err(AuditError::InvalidProof));
+        }
+
+        // Replica of different plaintext with valid commitment of its own
+        let mut encoded = replica.encoded.clone();
+        encoded[0] = encoded[1];
+        let prover = Prover::new(hasher, &encoded);
+        let verifier = Verifier::new(hasher, prover.commitment());
+        let answer = prover.answer_piece(0).unwrap();
+        assert_eq!(
+            verifier.verify_decoding(
+                0,
+                &answer,
+                &plaintext,
+                &plaintext_tree.prove(0).unwrap(),
+                &replica.round_keys,
+                &replica.iv_derivation.piece_iv(0),
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            ),
+            Err(AuditError::DecodingMismatch),
+        );
+        assert!(prover.answer_piece(PIECES as u64).is_none());
+    }
+}
@@ -0,0 +1,61 @@
+//! Cross-piece chained encoding of a sector.
+//!
+//! Normally every piece of a sector is encoded independently with its own IV. In chained mode only
+//! the first piece uses sector IV and the last encoded block of every piece becomes IV of the next
+//! one, so encoding a sector is inherently sequential. Decoding is still parallel, since IVs of all
+//! pieces are known from encoded pieces, see `Dispatcher::encode_chained()` and
+//! `Dispatcher::decode_chained()`.
+
+use crate::Block;
+use crate::Piece;
+use crate::BLOCK_SIZE;
+use crate::PIECE_SIZE;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+/// IV of the piece that follows `encoded` piece in the chain
+pub fn next_iv(encoded: &Piece) -> Block {
+    encoded[(PIECE_SIZE - BLOCK_SIZE)..].try_into().unwrap()
+}
+
+/// IVs of every encoded piece of the chain that starts with sector `iv`
+pub fn chained_ivs(iv: &Block, encoded: &[Piece]) -> Vec<Block> {
+    core::iter::once(*iv)
+        .chain(encoded.iter().map(next_iv))
+        .take(encoded.len())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::software_lut::SoftwareLuT;
+    use crate::por::software_lut::SoftwareLuTKeys;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use alloc::vec;
+
+    #[test]
+    fn test_chain() {
+        let keys = SoftwareLuTKeys::new(&ID);
+        let por = SoftwareLuT::new();
+
+        let mut encoded = vec![INPUT; 3];
+        let mut iv = IV;
+        for piece in encoded.iter_mut() {
+            por.encode(piece, &keys, iv, 16, 2);
+            iv = next_iv(piece);
+        }
+        let ivs = chained_ivs(&IV, &encoded);
+        assert_eq!(ivs, vec![IV, next_iv(&encoded[0]), next_iv(&encoded[1])]);
+
+        // Pieces can be decoded independently
+        for (piece, iv) in encoded.iter_mut().zip(ivs.iter()).rev() {
+            por.decode(piece, &keys, iv, 16, 2);
+            assert_eq!(piece.to_vec(), INPUT.to_vec());
+        }
+
+        assert!(chained_ivs(&IV, &[]).is_empty());
+    }
+}
@@ -0,0 +1,703 @@
+//! Versioned self-describing on-disk container for proof of replication replicas.
+//!
+//! Layout (all integers are little-endian):
+//! * header (`HEADER_SIZE` bytes): magic, format version, algorithm version, IV scheme, checksum
+//!   kind, piece size, iterations, id, IV, number of pieces, original data size and CRC-32 of the
+//!   header itself
+//! * piece table (`PIECE_TABLE_ENTRY_SIZE` bytes per piece): offset of the piece in the file and
+//!   its checksum (zero if checksums are disabled)
+//! * encoded pieces, starting at the first offset after piece table that is a multiple of
+//!   `PIECE_SIZE`
+//!
+//! Readers treat files as untrusted, everything is validated before use.
+
+use crate::por::iv::IvDerivation;
+use crate::por::stream;
+use crate::Block;
+use crate::Piece;
+use crate::PIECE_SIZE;
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+pub const MAGIC: [u8; 8] = *b"AESPROOF";
+/// Version of container format
+pub const FORMAT_VERSION: u16 = 1;
+/// Version of proof of replication algorithm, independent of backend used
+pub const ALGORITHM_VERSION: u16 = 1;
+pub const HEADER_SIZE: usize = 96;
+pub const PIECE_TABLE_ENTRY_SIZE: usize = 16;
+
+/// Offset of header checksum, checksum covers all bytes before it
+const HEADER_CHECKSUM_OFFSET: usize = 80;
+
+#[derive(Debug)]
+pub enum ContainerError {
+    Io(io::Error),
+    /// Not a container file
+    InvalidMagic,
+    /// Container was created by a newer version of this library
+    UnsupportedFormatVersion(u16),
+    /// Replica was encoded with unsupported algorithm version
+    UnsupportedAlgorithmVersion(u16),
+    /// Header is malformed
+    InvalidHeader(&'static str),
+    /// Piece table is malformed
+    InvalidPieceTable(&'static str),
+    /// Piece index is not less than number of pieces
+    PieceOutOfRange(u64),
+    /// Stored piece doesn't match its checksum
+    ChecksumMismatch(u64),
+    /// Writer was finished before all pieces were written
+    MissingPiece(u64),
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerError::Io(error) => write!(f, "I/O error: {}", error),
+            ContainerError::InvalidMagic => write!(f, "Not a replica container"),
+            ContainerError::UnsupportedFormatVersion(version) => {
+                write!(f, "Unsupported container format version {}", version)
+            }
+            ContainerError::UnsupportedAlgorithmVersion(version) => {
+                write!(f, "Unsupported algorithm version {}", version)
+            }
+            ContainerError::InvalidHeader(reason) => {
+                write!(f, "Invalid container header: {}", reason)
+            }
+            ContainerError::InvalidPieceTable(reason) => {
+                write!(f, "Invalid piece table: {}", reason)
+            }
+            ContainerError::PieceOutOfRange(index) => {
+                write!(f, "Piece {} is out of range", index)
+            }
+            ContainerError::ChecksumMismatch(index) => {
+                write!(f, "Piece {} doesn't match its checksum", index)
+            }
+            ContainerError::MissingPiece(index) => write!(f, "Piece {} was not written", index),
+        }
+    }
+}
+
+impl std::error::Error for ContainerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ContainerError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ContainerError {
+    fn from(error: io::Error) -> Self {
+        ContainerError::Io(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ContainerError>;
+
+/// How IV of each piece is derived from IV in the header
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IvScheme {
+    /// IV of piece `i` is `iv + i`, see `stream::piece_iv()`
+    Sequential,
+    /// IV of piece `i` is derived from id and `i` with IV from the header used as salt, see
+    /// `IvDerivation`
+    Derived,
+}
+
+impl IvScheme {
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            IvScheme::Sequential => 0,
+            IvScheme::Derived => 1,
+        }
+    }
+
+    pub(crate) fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(IvScheme::Sequential),
+            1 => Some(IvScheme::Derived),
+            _ => None,
+        }
+    }
+}
+
+/// Per-piece checksums stored in piece table
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Checksum {
+    None,
+    /// CRC-32 (IEEE 802.3) of encoded piece, detects accidental corruption only
+    Crc32,
+}
+
+impl Checksum {
+    fn to_byte(self) -> u8 {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32 => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Checksum::None),
+            1 => Some(Checksum::Crc32),
+            _ => None,
+        }
+    }
+
+    fn compute(self, piece: &Piece) -> u32 {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32 => crc32(piece),
+        }
+    }
+}
+
+/// Everything needed to decode replica
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ContainerHeader {
+    pub id: Block,
+    pub iv: Block,
+    pub iv_scheme: IvScheme,
+    pub checksum: Checksum,
+    pub aes_iterations: u32,
+    pub breadth_iterations: u32,
+    /// Number of encoded pieces
+    pub pieces: u64,
+    /// Size of original data in bytes (without padding)
+    pub size: u64,
+}
+
+impl ContainerHeader {
+    /// IV used to encode piece `index`
+    pub fn piece_iv(&self, index: u64) -> Block {
+        match self.iv_scheme {
+            IvScheme::Sequential => stream::piece_iv(&self.iv, index),
+            IvScheme::Derived => IvDerivation::new(&self.id, Some(&self.iv)).piece_iv(index),
+        }
+    }
+
+    /// Offset of the first piece in the file
+    pub fn data_offset(&self) -> Result<u64> {
+        self.pieces
+            .checked_mul(PIECE_TABLE_ENTRY_SIZE as u64)
+            .and_then(|table_size| table_size.checked_add(HEADER_SIZE as u64))
+            .and_then(|end| end.checked_add(PIECE_SIZE as u64 - 1))
+            .map(|end| end / PIECE_SIZE as u64 * PIECE_SIZE as u64)
+            .ok_or(ContainerError::InvalidHeader("Too many pieces"))
+    }
+
+    /// Size of the whole container file
+    pub fn file_size(&self) -> Result<u64> {
+        self.pieces
+            .checked_mul(PIECE_SIZE as u64)
+            .and_then(|data_size| data_size.checked_add(self.data_offset().ok()?))
+            .ok_or(ContainerError::InvalidHeader("Too many pieces"))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.aes_iterations == 0 {
+            return Err(ContainerError::InvalidHeader(
+                "AES iterations must not be 0",
+            ));
+        }
+        if self.breadth_iterations == 0 {
+            return Err(ContainerError::InvalidHeader(
+                "Breadth iterations must not be 0",
+            ));
+        }
+        let data_size = self.file_size()? - self.data_offset()?;
+        if self.size > data_size {
+            return Err(ContainerError::InvalidHeader(
+                "Data size is larger than pieces",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..8].copy_from_slice(&MAGIC);
+        bytes[8..10].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes[10..12].copy_from_slice(&ALGORITHM_VERSION.to_le_bytes());
+        bytes[12] = self.iv_scheme.to_byte();
+        bytes[13] = self.checksum.to_byte();
+        bytes[16..20].copy_from_slice(&(PIECE_SIZE as u32).to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.aes_iterations.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.breadth_iterations.to_le_bytes());
+        bytes[32..48].copy_from_slice(&self.id);
+        bytes[48..64].copy_from_slice(&self.iv);
+        bytes[64..72].copy_from_slice(&self.pieces.to_le_bytes());
+        bytes[72..80].copy_from_slice(&self.size.to_le_bytes());
+        let checksum = crc32(&bytes[..HEADER_CHECKSUM_OFFSET]);
+        bytes[HEADER_CHECKSUM_OFFSET..][..4].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; HEADER_SIZE]) -> Result<Self> {
+        if bytes[0..8] != MAGIC {
+            return Err(ContainerError::InvalidMagic);
+        }
+        let format_version = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+        if format_version != FORMAT_VERSION {
+            return Err(ContainerError::UnsupportedFormatVersion(format_version));
+        }
+        let checksum = u32::from_le_bytes(bytes[HEADER_CHECKSUM_OFFSET..][..4].try_into().unwrap());
+        if checksum != crc32(&bytes[..HEADER_CHECKSUM_OFFSET]) {
+            return Err(ContainerError::InvalidHeader("Header checksum mismatch"));
+        }
+        let algorithm_version = u16::from_le_bytes(bytes[10..12].try_into().unwrap());
+        if algorithm_version != ALGORITHM_VERSION {
+            return Err(ContainerError::UnsupportedAlgorithmVersion(
+                algorithm_version,
+            ));
+        }
+        let iv_scheme = IvScheme::from_byte(bytes[12])
+            .ok_or(ContainerError::InvalidHeader("Unknown IV scheme"))?;
+        let checksum = Checksum::from_byte(bytes[13])
+            .ok_or(ContainerError::InvalidHeader("Unknown checksum"))?;
+        let piece_size = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        if piece_size as usize != PIECE_SIZE {
+            return Err(ContainerError::InvalidHeader("Unsupported piece size"));
+        }
+        let reserved_zero = bytes[14..16]
+            .iter()
+            .chain(bytes[28..32].iter())
+            .chain(bytes[(HEADER_CHECKSUM_OFFSET + 4)..].iter())
+            .all(|&byte| byte == 0);
+        if !reserved_zero {
+            return Err(ContainerError::InvalidHeader("Reserved bytes are not zero"));
+        }
+
+        let header = Self {
+            id: bytes[32..48].try_into().unwrap(),
+            iv: bytes[48..64].try_into().unwrap(),
+            iv_scheme,
+            checksum,
+            aes_iterations: u32::from_le_bytes(bytes[20..24].try_into().unwrap()),
+            breadth_iterations: u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+            pieces: u64::from_le_bytes(bytes[64..72].try_into().unwrap()),
+            size: u64::from_le_bytes(bytes[72..80].try_into().unwrap()),
+        };
+        header.validate()?;
+
+        Ok(header)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct PieceTableEntry {
+    offset: u64,
+    checksum: u32,
+}
+
+/// Writes container, pieces can be written in any order, but all of them must be written before
+/// `finish()`
+pub struct ContainerWriter<W: Write + Seek> {
+    inner: W,
+    header: ContainerHeader,
+    data_offset: u64,
+    piece_table: Vec<Option<PieceTableEntry>>,
+}
+
+impl<W: Write + Seek> ContainerWriter<W> {
+    /// Writes header into `inner` (at its start), space for pieces is reserved upfront
+    pub fn new(mut inner: W, header: ContainerHeader) -> Result<Self> {
+        header.validate()?;
+        let data_offset = header.data_offset()?;
+        let pieces = header
+            .pieces
+            .try_into()
+            .map_err(|_| ContainerError::InvalidHeader("Too many pieces"))?;
+
+        inner.seek(SeekFrom::Start(0))?;
+        inner.write_all(&header.to_bytes())?;
+
+        Ok(Self {
+            inner,
+            header,
+            data_offset,
+            piece_table: vec![None; pieces],
+        })
+    }
+
+    pub fn header(&self) -> &ContainerHeader {
+        &self.header
+    }
+
+    /// Writes encoded piece `index`
+    pub fn write_piece(&mut self, index: u64, piece: &Piece) -> Result<()> {
+        if index >= self.header.pieces {
+            return Err(ContainerError::PieceOutOfRange(index));
+        }
+        let offset = self.data_offset + index * PIECE_SIZE as u64;
+
+        self.inner.seek(SeekFrom::Start(offset))?;
+        self.inner.write_all(piece)?;
+        self.piece_table[index as usize].replace(PieceTableEntry {
+            offset,
+            checksum: self.header.checksum.compute(piece),
+        });
+
+        Ok(())
+    }
+
+    /// Writes piece table and returns inner writer, all pieces must have been written
+    pub fn finish(mut self) -> Result<W> {
+        let mut table = Vec::with_capacity(self.piece_table.len() * PIECE_TABLE_ENTRY_SIZE);
+        for (index, entry) in self.piece_table.iter().enumerate() {
+            let entry = entry.ok_or(ContainerError::MissingPiece(index as u64))?;
+            table.extend_from_slice(&entry.offset.to_le_bytes());
+            table.extend_from_slice(&entry.checksum.to_le_bytes());
+            table.extend_from_slice(&[0u8; 4]);
+        }
+
+        self.inner.seek(SeekFrom::Start(HEADER_SIZE as u64))?;
+        self.inner.write_all(&table)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+/// Reads container with random access to pieces
+pub struct ContainerReader<R: Read + Seek> {
+    inner: R,
+    header: ContainerHeader,
+    piece_table: Vec<PieceTableEntry>,
+}
+
+impl<R: Read + Seek> ContainerReader<R> {
+    /// Reads and validates header and piece table, container is treated as untrusted input
+    pub fn new(mut inner: R) -> Result<Self> {
+        let file_size = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+
+        let mut header_bytes = [0u8; HEADER_SIZE];
+        inner.read_exact(&mut header_bytes).map_err(|error| {
+            if error.kind() == io::ErrorKind::UnexpectedEof {
+                ContainerError::InvalidMagic
+            } else {
+                ContainerError::Io(error)
+            }
+        })?;
+        let header = ContainerHeader::from_bytes(&header_bytes)?;
+        // Checked before allocating anything based on number of pieces
+        if header.file_size()? > file_size {
+            return Err(ContainerError::InvalidHeader(
+                "File is smaller than header says",
+            ));
+        }
+
+        let data_offset = header.data_offset()?;
+        let mut table = vec![0u8; header.pieces as usize * PIECE_TABLE_ENTRY_SIZE];
+        inner.read_exact(&mut table)?;
+        let mut used = vec![false; header.pieces as usize];
+        let piece_table = table
+            .chunks_exact(PIECE_TABLE_ENTRY_SIZE)
+            .map(|entry| {
+                let offset = u64::from_le_bytes(entry[0..8].try_into().unwrap());
+                let checksum = u32::from_le_bytes(entry[8..12].try_into().unwrap());
+                if entry[12..16] != [0u8; 4] {
+                    return Err(ContainerError::InvalidPieceTable(
+                        "Reserved bytes are not zero",
+                    ));
+                }
+                if header.checksum == Checksum::None && checksum != 0 {
+                    return Err(ContainerError::InvalidPieceTable(
+                        "Checksum present while checksums are disabled",
+                    ));
+                }
+                let slot = offset
+                    .checked_sub(data_offset)
+                    .filter(|relative| relative % PIECE_SIZE as u64 == 0)
+                    .map(|relative| relative / PIECE_SIZE as u64)
+                    .filter(|&slot| slot < header.pieces)
+                    .ok_or(ContainerError::InvalidPieceTable("Invalid piece offset"))?;
+                if std::mem::replace(&mut used[slot as usize], true) {
+                    return Err(ContainerError::InvalidPieceTable(
+                        "Multiple pieces with the same offset",
+                    ));
+                }
+
+                Ok(PieceTableEntry { offset, checksum })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            inner,
+            header,
+            piece_table,
+        })
+    }
+
+    pub fn header(&self) -> &ContainerHeader {
+        &self.header
+    }
+
+    /// Reads encoded piece `index`, verifying its checksum if checksums are enabled
+    pub fn read_piece(&mut self, index: u64) -> Result<Piece> {
+        if index >= self.header.pieces {
+            return Err(ContainerError::PieceOutOfRange(index));
+        }
+        let entry = self.piece_table[index as usize];
+
+        let mut piece = [0u8; PIECE_SIZE];
+        self.inner.seek(SeekFrom::Start(entry.offset))?;
+        self.inner.read_exact(&mut piece)?;
+        if self.header.checksum.compute(&piece) != entry.checksum {
+            return Err(ContainerError::ChecksumMismatch(index));
+        }
+
+        Ok(piece)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use std::io::Cursor;
+
+    fn header(pieces: u64, checksum: Checksum) -> ContainerHeader {
+        ContainerHeader {
+            id: ID,
+            iv: IV,
+            iv_scheme: IvScheme::Sequential,
+            checksum,
+            aes_iterations: 256,
+            breadth_iterations: 16,
+            pieces,
+            size: pieces * PIECE_SIZE as u64 - 10,
+        }
+    }
+
+    fn piece(index: u64) -> Piece {
+        let mut piece = INPUT;
+        piece[..8].copy_from_slice(&index.to_le_bytes());
+        piece
+    }
+
+    fn container(pieces: u64, checksum: Checksum) -> Vec<u8> {
+        let mut writer =
+            ContainerWriter::new(Cursor::new(Vec::new()), header(pieces, checksum)).unwrap();
+        // Out of order on purpose
+        for index in (0..pieces).rev() {
+            writer.write_piece(index, &piece(index)).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &checksum in [Checksum::None, Checksum::Crc32].iter() {
+            let bytes = container(3, checksum);
+            let expected_header = header(3, checksum);
+            assert_eq!(bytes.len() as u64, expected_header.file_size().unwrap());
+            assert_eq!(expected_header.data_offset().unwrap(), PIECE_SIZE as u64);
+
+            let mut reader = ContainerReader::new(Cursor::new(bytes)).unwrap();
+            assert_eq!(reader.header(), &expected_header);
+            assert_eq!(reader.header().piece_iv(2), stream::piece_iv(&IV, 2));
+            for &index in [2, 0, 1].iter() {
+                assert_eq!(
+                    reader.read_piece(index).unwrap().to_vec(),
+                    piece(index).to_vec()
+                );
+            }
+            assert!(matches!(
+                reader.read_piece(3),
+                Err(ContainerError::PieceOutOfRange(3))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_derived_ivs() {
+        let mut header = header(1, Checksum::None);
+        header.iv_scheme = IvScheme::Derived;
+        let mut writer = ContainerWriter::new(Cursor::new(Vec::new()), header).unwrap();
+        writer.write_piece(0, &INPUT).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let reader = ContainerReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.header().iv_scheme, IvScheme::Derived);
+        assert_eq!(
+            reader.header().piece_iv(3),
+            IvDerivation::new(&ID, Some(&IV)).piece_iv(3),
+        );
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut bytes = container(2, Checksum::Crc32);
+        bytes[PIECE_SIZE * 2 + 100] ^= 1;
+
+        let mut reader = ContainerReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.read_piece(0).is_ok());
+        assert!(matches!(
+            reader.read_piece(1),
+            Err(ContainerError::ChecksumMismatch(1))
+        ));
+    }
+
+    #[test]
+    fn test_writer_errors() {
+        let mut writer =
+            ContainerWriter::new(Cursor::new(Vec::new()), header(2, Checksum::None)).unwrap();
+        assert!(matches!(
+            writer.write_piece(2, &INPUT),
+            Err(ContainerError::PieceOutOfRange(2))
+        ));
+        writer.write_piece(0, &INPUT).unwrap();
+        assert!(matches!(
+            writer.finish(),
+            Err(ContainerError::MissingPiece(1))
+        ));
+
+        let mut invalid = header(1, Checksum::None);
+        invalid.size = PIECE_SIZE as u64 + 1;
+        assert!(ContainerWriter::new(Cursor::new(Vec::new()), invalid).is_err());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let bytes = container(2, Checksum::Crc32);
+        let open = |bytes: Vec<u8>| ContainerReader::new(Cursor::new(bytes)).map(|_| ());
+
+        assert!(matches!(
+            open(bytes[..50].to_vec()),
+            Err(ContainerError::InvalidMagic)
+        ));
+        assert!(matches!(
+            open(bytes[..(bytes.len() - 1)].to_vec()),
+            Err(ContainerError::InvalidHeader(_))
+        ));
+
+        let mut modified = bytes.clone();
+        modified[0] ^= 1;
+        assert!(matches!(open(modified), Err(ContainerError::InvalidMagic)));
+
+        let mut modified = bytes.clone();
+        modified[8] = 2;
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::UnsupportedFormatVersion(2))
+        ));
+
+        // Any change without updating header checksum is detected
+        let mut modified = bytes.clone();
+        modified[20] ^= 1;
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::InvalidHeader(_))
+        ));
+
+        // Huge number of pieces with valid header checksum must not cause huge allocation
+        let mut huge = header(2, Checksum::Crc32);
+        huge.pieces = 1 << 40;
+        huge.size = 0;
+        let mut modified = bytes.clone();
+        modified[..HEADER_SIZE].copy_from_slice(&huge.to_bytes());
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::InvalidHeader(_))
+        ));
+
+        let mut unknown_algorithm = header(2, Checksum::Crc32).to_bytes();
+        unknown_algorithm[10] = 2;
+        let checksum = crc32(&unknown_algorithm[..HEADER_CHECKSUM_OFFSET]);
+        unknown_algorithm[HEADER_CHECKSUM_OFFSET..][..4].copy_from_slice(&checksum.to_le_bytes());
+        let mut modified = bytes;
+        modified[..HEADER_SIZE].copy_from_slice(&unknown_algorithm);
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::UnsupportedAlgorithmVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_piece_table() {
+        let bytes = container(2, Checksum::None);
+        let open = |bytes: Vec<u8>| ContainerReader::new(Cursor::new(bytes)).map(|_| ());
+
+        // Both pieces pointing to the same offset
+        let mut modified = bytes.clone();
+        let first_offset = modified[HEADER_SIZE..][..8].to_vec();
+        modified[(HEADER_SIZE + PIECE_TABLE_ENTRY_SIZE)..][..8].copy_from_slice(&first_offset);
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::InvalidPieceTable(_))
+        ));
+
+        // Offset inside of header
+        let mut modified = bytes.clone();
+        modified[HEADER_SIZE..][..8].copy_from_slice(&0u64.to_le_bytes());
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::InvalidPieceTable(_))
+        ));
+
+        // Unaligned offset
+        let mut modified = bytes.clone();
+        modified[HEADER_SIZE] += 1;
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::InvalidPieceTable(_))
+        ));
+
+        // Checksum while checksums are disabled
+        let mut modified = bytes;
+        modified[HEADER_SIZE + 8] = 1;
+        assert!(matches!(
+            open(modified),
+            Err(ContainerError::InvalidPieceTable(_))
+        ));
+    }
+}
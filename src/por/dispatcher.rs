@@ -0,0 +1,1058 @@
+use crate::hash::AesHash;
+use crate::hash::Hash;
+#[cfg(feature = "opencl")]
+use crate::merkle::MerkleTree;
+#[cfg(target_arch = "x86_64")]
+use crate::por::aes_ni::AesNi;
+#[cfg(target_arch = "x86_64")]
+use crate::por::aes_ni::AesNiKeys;
+use crate::por::audit::CommitmentTree;
+use crate::por::chain;
+use crate::por::iv::IvDerivation;
+#[cfg(feature = "opencl")]
+use crate::por::opencl::OpenCL;
+#[cfg(feature = "opencl")]
+use crate::por::opencl::OpenCLKeys;
+use crate::por::software_fix_slicing::SoftwareFixSlicing;
+use crate::por::software_fix_slicing::SoftwareFixSlicingKeys;
+use crate::por::software_lut::SoftwareLuT;
+use crate::por::software_lut::SoftwareLuTKeys;
+#[cfg(target_arch = "x86_64")]
+use crate::por::vaes::VAes;
+#[cfg(target_arch = "x86_64")]
+use crate::por::vaes::VAesKeys;
+use crate::por::verification;
+use crate::por::verification::Verification;
+use crate::por::verification::VerificationError;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::Piece;
+use crate::PIECE_SIZE;
+use rayon::prelude::*;
+use std::convert::TryInto;
+use std::fmt;
+use std::ops::Range;
+
+/// Proof of replication backend that dispatcher can use
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Backend {
+    /// OpenCL device
+    OpenCL,
+    /// VAES instruction set
+    VAes,
+    /// AES-NI instruction set
+    AesNi,
+    /// Constant-time software implementation using fixslicing, always available
+    SoftwareFixSlicing,
+    /// Software implementation using look-up tables, always available
+    SoftwareLuT,
+}
+
+/// Error returned when requested backend can't be used
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BackendError {
+    /// Backend is not supported by this machine or not enabled at compile time
+    Unavailable(Backend),
+    /// Backend produced wrong results in known-answer self-test (faulty hardware, driver bug or
+    /// miscompilation)
+    SelfTestFailed(Backend),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Unavailable(backend) => {
+                write!(f, "{:?} backend is not available", backend)
+            }
+            BackendError::SelfTestFailed(backend) => write!(
+                f,
+                "{:?} backend failed known-answer self-test, its results can't be trusted",
+                backend,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Error that made dispatcher switch to another backend at runtime
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RuntimeError {
+    /// Backend that failed
+    pub backend: Backend,
+    /// Backend used from then on
+    pub fallback: Backend,
+    /// Error reported by failed backend
+    pub message: String,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} backend failed, switched to {:?}: {}",
+            self.backend, self.fallback, self.message,
+        )
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// CPU backends in order of preference
+const CPU_BACKENDS: [Backend; 3] = [Backend::VAes, Backend::AesNi, Backend::SoftwareFixSlicing];
+
+enum Codec {
+    #[cfg(feature = "opencl")]
+    OpenCL(OpenCL),
+    #[cfg(target_arch = "x86_64")]
+    VAes(VAes),
+    #[cfg(target_arch = "x86_64")]
+    AesNi(AesNi),
+    SoftwareFixSlicing(SoftwareFixSlicing),
+    SoftwareLuT(SoftwareLuT),
+}
+
+impl Codec {
+    fn self_test(&mut self) -> bool {
+        match self {
+            #[cfg(feature = "opencl")]
+            Codec::OpenCL(opencl) => opencl.self_test().is_ok(),
+            #[cfg(target_arch = "x86_64")]
+            Codec::VAes(vaes) => vaes.self_test().is_ok(),
+            #[cfg(target_arch = "x86_64")]
+            Codec::AesNi(aes_ni) => aes_ni.self_test().is_ok(),
+            Codec::SoftwareFixSlicing(software_fix_slicing) => {
+                software_fix_slicing.self_test().is_ok()
+            }
+            Codec::SoftwareLuT(software_lut) => software_lut.self_test().is_ok(),
+        }
+    }
+}
+
+/// Proof of replication with the fastest backend available on this machine, OpenCL is preferred
+/// (when enabled), falling back to CPU backends if there is no OpenCL platform or device or if
+/// OpenCL fails at runtime (see `last_error()`). Every backend passes known-answer self-test
+/// before it is used.
+pub struct Dispatcher {
+    codec: Codec,
+    last_error: Option<RuntimeError>,
+    /// CPU dispatcher for operations OpenCL can't do efficiently, created on first use
+    #[cfg(feature = "opencl")]
+    cpu: Option<Box<Dispatcher>>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        #[cfg(feature = "opencl")]
+        {
+            if let Ok(dispatcher) = Self::with_backend(Backend::OpenCL) {
+                return dispatcher;
+            }
+        }
+
+        Self::from_codec(Self::cpu_codec())
+    }
+
+    /// Use specific backend, returns an error if it is not available on this machine or fails
+    /// known-answer self-test
+    pub fn with_backend(backend: Backend) -> Result<Self, BackendError> {
+        let unavailable = BackendError::Unavailable(backend);
+        let mut codec = match backend {
+            #[cfg(feature = "opencl")]
+            Backend::OpenCL => Codec::OpenCL(OpenCL::new().map_err(|_| unavailable)?),
+            #[cfg(target_arch = "x86_64")]
+            Backend::VAes => Codec::VAes(VAes::new().map_err(|_| unavailable)?),
+            #[cfg(target_arch = "x86_64")]
+            Backend::AesNi => Codec::AesNi(AesNi::new().map_err(|_| unavailable)?),
+            Backend::SoftwareFixSlicing => Codec::SoftwareFixSlicing(SoftwareFixSlicing::new()),
+            Backend::SoftwareLuT => Codec::SoftwareLuT(SoftwareLuT::new()),
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(unavailable);
+            }
+        };
+
+        if !codec.self_test() {
+            return Err(BackendError::SelfTestFailed(backend));
+        }
+
+        Ok(Self::from_codec(codec))
+    }
+
+    fn from_codec(codec: Codec) -> Self {
+        Self {
+            codec,
+            last_error: None,
+            #[cfg(feature = "opencl")]
+            cpu: None,
+        }
+    }
+
+    /// Backend that is currently used
+    pub fn backend(&self) -> Backend {
+        match self.codec {
+            #[cfg(feature = "opencl")]
+            Codec::OpenCL(_) => Backend::OpenCL,
+            #[cfg(target_arch = "x86_64")]
+            Codec::VAes(_) => Backend::VAes,
+            #[cfg(target_arch = "x86_64")]
+            Codec::AesNi(_) => Backend::AesNi,
+            Codec::SoftwareFixSlicing(_) => Backend::SoftwareFixSlicing,
+            Codec::SoftwareLuT(_) => Backend::SoftwareLuT,
+        }
+    }
+
+    /// Error that made dispatcher switch from OpenCL to CPU backend for good, `None` if it never
+    /// happened
+    pub fn last_error(&self) -> Option<&RuntimeError> {
+        self.last_error.as_ref()
+    }
+
+    #[cfg(feature = "opencl")]
+    fn fall_back_to_cpu(&mut self, message: String) {
+        let backend = self.backend();
+        self.codec = match self.cpu.take() {
+            Some(cpu) => cpu.codec,
+            None => Self::cpu_codec(),
+        };
+        self.last_error = Some(RuntimeError {
+            backend,
+            fallback: self.backend(),
+            message,
+        });
+    }
+
+    /// Proof of replication encoding of any number of pieces, each with its own IV
+    pub fn encode(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        assert_eq!(pieces.len(), ivs.len());
+
+        #[cfg(feature = "opencl")]
+        {
+            if let Codec::OpenCL(opencl) = &mut self.codec {
+                let result = opencl.encode(
+                    &pieces.concat(),
+                    ivs,
+                    &[&OpenCLKeys::from_round_keys(round_keys)],
+                    None,
+                    aes_iterations as u32,
+                    breadth_iterations as u32,
+                );
+                match result {
+                    Ok(output) => {
+                        copy_from_output(pieces, &output);
+                        return;
+                    }
+                    Err(error) => {
+                        self.fall_back_to_cpu(error.to_string());
+                    }
+                }
+            }
+        }
+
+        match &self.codec {
+            #[cfg(feature = "opencl")]
+            Codec::OpenCL(_) => unreachable!(),
+            #[cfg(target_arch = "x86_64")]
+            Codec::VAes(vaes) => {
+                let keys = VAesKeys::from_round_keys(round_keys);
+                encode_batched(pieces, ivs, |pieces: &mut [Piece; 12], ivs| {
+                    vaes.encode(pieces, &keys, ivs, aes_iterations, breadth_iterations);
+                });
+            }
+            #[cfg(target_arch = "x86_64")]
+            Codec::AesNi(aes_ni) => {
+                let keys = AesNiKeys::from_round_keys(round_keys);
+                encode_batched(pieces, ivs, |pieces: &mut [Piece; 4], ivs| {
+                    aes_ni.encode(pieces, &keys, ivs, aes_iterations, breadth_iterations);
+                });
+            }
+            Codec::SoftwareFixSlicing(software_fix_slicing) => {
+                let keys = SoftwareFixSlicingKeys::from_round_keys(round_keys);
+                encode_batched(pieces, ivs, |pieces: &mut [Piece; 4], ivs| {
+                    software_fix_slicing.encode_x4(
+                        pieces,
+                        &keys,
+                        ivs,
+                        aes_iterations,
+                        breadth_iterations,
+                    );
+                });
+            }
+            Codec::SoftwareLuT(software_lut) => {
+                let keys = SoftwareLuTKeys::from_round_keys(round_keys);
+                encode_batched(pieces, ivs, |[piece]: &mut [Piece; 1], [iv]| {
+                    software_lut.encode(piece, &keys, iv, aes_iterations, breadth_iterations);
+                });
+            }
+        }
+    }
+
+    /// Same as `encode()`, also commits to encoded pieces (see `por::audit`) by hashing blocks
+    /// during the last breadth iteration instead of reading pieces again afterwards. OpenCL
+    /// backend has to hash encoded pieces on CPU. Panics if there are no pieces
+    pub fn seal(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> CommitmentTree {
+        assert_eq!(pieces.len(), ivs.len());
+
+        let piece_roots = match &self.codec {
+            #[cfg(feature = "opencl")]
+            Codec::OpenCL(_) => {
+                self.encode(pieces, round_keys, ivs, aes_iterations, breadth_iterations);
+                pieces
+                    .par_iter()
+                    .map(|piece| MerkleTree::from_piece_blocks(*hasher, piece).root())
+                    .collect()
+            }
+            #[cfg(target_arch = "x86_64")]
+            Codec::VAes(vaes) => {
+                let keys = VAesKeys::from_round_keys(round_keys);
+                seal_batched(pieces, ivs, |pieces: &mut [Piece; 12], ivs| {
+                    vaes.seal(
+                        pieces,
+                        &keys,
+                        ivs,
+                        hasher,
+                        aes_iterations,
+                        breadth_iterations,
+                    )
+                })
+            }
+            #[cfg(target_arch = "x86_64")]
+            Codec::AesNi(aes_ni) => {
+                let keys = AesNiKeys::from_round_keys(round_keys);
+                seal_batched(pieces, ivs, |pieces: &mut [Piece; 4], ivs| {
+                    aes_ni.seal(
+                        pieces,
+                        &keys,
+                        ivs,
+                        hasher,
+                        aes_iterations,
+                        breadth_iterations,
+                    )
+                })
+            }
+            Codec::SoftwareFixSlicing(software_fix_slicing) => {
+                let keys = SoftwareFixSlicingKeys::from_round_keys(round_keys);
+                seal_batched(pieces, ivs, |pieces: &mut [Piece; 4], ivs| {
+                    software_fix_slicing.seal_x4(
+                        pieces,
+                        &keys,
+                        ivs,
+                        hasher,
+                        aes_iterations,
+                        breadth_iterations,
+                    )
+                })
+            }
+            Codec::SoftwareLuT(software_lut) => {
+                let keys = SoftwareLuTKeys::from_round_keys(round_keys);
+                seal_batched(pieces, ivs, |[piece]: &mut [Piece; 1], [iv]| {
+                    [software_lut.seal(
+                        piece,
+                        &keys,
+                        iv,
+                        hasher,
+                        aes_iterations,
+                        breadth_iterations,
+                    )]
+                })
+            }
+        };
+
+        CommitmentTree::from_piece_roots(*hasher, &piece_roots)
+    }
+
+    /// Same as `encode()`, then decodes pieces selected by `verification` with a different
+    /// software backend and compares them with plaintext to detect hardware faults during
+    /// encoding. Indices of pieces that don't match are returned in error
+    pub fn encode_verified(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+        verification: &Verification,
+    ) -> Result<(), VerificationError> {
+        let plaintext = verification
+            .sample(pieces.len())
+            .into_iter()
+            .map(|index| (index, pieces[index]))
+            .collect::<Vec<_>>();
+
+        self.encode(pieces, round_keys, ivs, aes_iterations, breadth_iterations);
+
+        self.verify_encoding(
+            pieces,
+            &plaintext,
+            round_keys,
+            ivs,
+            aes_iterations,
+            breadth_iterations,
+        )
+    }
+
+    /// Same as `encode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_indexed(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut ivs = vec![Block::default(); pieces.len()];
+        iv_derivation.fill(first_index, &mut ivs);
+        self.encode(pieces, round_keys, &ivs, aes_iterations, breadth_iterations);
+    }
+
+    /// Encodes pieces of a sector one after another, the first one with `iv` and every next one
+    /// with the last encoded block of the previous one, see `por::chain`. Returns IV for the piece
+    /// that would follow. OpenCL backend is not used, since pieces can't be encoded in parallel
+    pub fn encode_chained(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        iv: &Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Block {
+        #[cfg(feature = "opencl")]
+        {
+            if let Codec::OpenCL(_) = self.codec {
+                let cpu = self
+                    .cpu
+                    .get_or_insert_with(|| Box::new(Self::from_codec(Self::cpu_codec())));
+                return cpu.encode_chained(
+                    pieces,
+                    round_keys,
+                    iv,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+            }
+        }
+
+        let mut iv = *iv;
+        for piece in pieces.iter_mut() {
+            self.encode(
+                std::slice::from_mut(piece),
+                round_keys,
+                &[iv],
+                aes_iterations,
+                breadth_iterations,
+            );
+            iv = chain::next_iv(piece);
+        }
+        iv
+    }
+
+    /// Proof of replication decoding of any number of pieces, each with its own IV
+    pub fn decode(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        assert_eq!(pieces.len(), ivs.len());
+
+        #[cfg(feature = "opencl")]
+        {
+            if let Codec::OpenCL(opencl) = &mut self.codec {
+                let result = opencl.decode(
+                    &pieces.concat(),
+                    ivs,
+                    &[&OpenCLKeys::from_round_keys(round_keys)],
+                    None,
+                    aes_iterations as u32,
+                    breadth_iterations as u32,
+                );
+                match result {
+                    Ok(output) => {
+                        copy_from_output(pieces, &output);
+                        return;
+                    }
+                    Err(error) => {
+                        self.fall_back_to_cpu(error.to_string());
+                    }
+                }
+            }
+        }
+
+        match &self.codec {
+            #[cfg(feature = "opencl")]
+            Codec::OpenCL(_) => unreachable!(),
+            #[cfg(target_arch = "x86_64")]
+            Codec::VAes(vaes) => {
+                let keys = VAesKeys::from_round_keys(round_keys);
+                pieces
+                    .par_iter_mut()
+                    .zip(ivs.par_iter())
+                    .for_each(|(piece, iv)| {
+                        vaes.decode(piece, &keys, iv, aes_iterations, breadth_iterations);
+                    });
+            }
+            #[cfg(target_arch = "x86_64")]
+            Codec::AesNi(aes_ni) => {
+                let keys = AesNiKeys::from_round_keys(round_keys);
+                pieces
+                    .par_iter_mut()
+                    .zip(ivs.par_iter())
+                    .for_each(|(piece, iv)| {
+                        aes_ni.decode(piece, &keys, iv, aes_iterations, breadth_iterations);
+                    });
+            }
+            Codec::SoftwareFixSlicing(software_fix_slicing) => {
+                let keys = SoftwareFixSlicingKeys::from_round_keys(round_keys);
+                pieces
+                    .par_iter_mut()
+                    .zip(ivs.par_iter())
+                    .for_each(|(piece, iv)| {
+                        software_fix_slicing.decode(
+                            piece,
+                            &keys,
+                            iv,
+                            aes_iterations,
+                            breadth_iterations,
+                        );
+                    });
+            }
+            Codec::SoftwareLuT(software_lut) => {
+                let keys = SoftwareLuTKeys::from_round_keys(round_keys);
+                pieces
+                    .par_iter_mut()
+                    .zip(ivs.par_iter())
+                    .for_each(|(piece, iv)| {
+                        software_lut.decode(piece, &keys, iv, aes_iterations, breadth_iterations);
+                    });
+            }
+        }
+    }
+
+    /// Decodes pieces encoded with `encode_chained()` in parallel, IVs are taken from encoded
+    /// pieces themselves
+    pub fn decode_chained(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        iv: &Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let ivs = chain::chained_ivs(iv, pieces);
+        self.decode(pieces, round_keys, &ivs, aes_iterations, breadth_iterations);
+    }
+
+    /// Same as `decode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn decode_indexed(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut ivs = vec![Block::default(); pieces.len()];
+        iv_derivation.fill(first_index, &mut ivs);
+        self.decode(pieces, round_keys, &ivs, aes_iterations, breadth_iterations);
+    }
+
+    /// Decodes only blocks in `block_range` of a single piece, see `DependencyCone`. OpenCL
+    /// backend is not worth it for a few blocks, so it falls back to AES-NI or software here
+    pub fn decode_range(
+        &self,
+        piece: &mut Piece,
+        round_keys: &RoundKeys,
+        iv: &Block,
+        block_range: Range<usize>,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        match &self.codec {
+            #[cfg(feature = "opencl")]
+            Codec::OpenCL(_) => {
+                #[cfg(target_arch = "x86_64")]
+                {
+                    if let Ok(aes_ni) = AesNi::new() {
+                        aes_ni.decode_range(
+                            piece,
+                            &AesNiKeys::from_round_keys(round_keys),
+                            iv,
+                            block_range,
+                            aes_iterations,
+                            breadth_iterations,
+                        );
+                        return;
+                    }
+                }
+
+                SoftwareFixSlicing::new().decode_range(
+                    piece,
+                    &SoftwareFixSlicingKeys::from_round_keys(round_keys),
+                    iv,
+                    block_range,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+            }
+            #[cfg(target_arch = "x86_64")]
+            Codec::VAes(vaes) => {
+                vaes.decode_range(
+                    piece,
+                    &VAesKeys::from_round_keys(round_keys),
+                    iv,
+                    block_range,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+            }
+            #[cfg(target_arch = "x86_64")]
+            Codec::AesNi(aes_ni) => {
+                aes_ni.decode_range(
+                    piece,
+                    &AesNiKeys::from_round_keys(round_keys),
+                    iv,
+                    block_range,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+            }
+            Codec::SoftwareFixSlicing(software_fix_slicing) => {
+                software_fix_slicing.decode_range(
+                    piece,
+                    &SoftwareFixSlicingKeys::from_round_keys(round_keys),
+                    iv,
+                    block_range,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+            }
+            Codec::SoftwareLuT(software_lut) => {
+                software_lut.decode_range(
+                    piece,
+                    &SoftwareLuTKeys::from_round_keys(round_keys),
+                    iv,
+                    block_range,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+            }
+        }
+    }
+
+    /// Decodes encoded pieces at indices from `plaintext` and compares them with corresponding
+    /// plaintext
+    fn verify_encoding(
+        &self,
+        pieces: &[Piece],
+        plaintext: &[(usize, Piece)],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Result<(), VerificationError> {
+        let mut verifier = Self::with_backend(verification::verification_backend(self.backend()))
+            .expect("Software backends always pass self-test unless the build is broken");
+
+        let mut decoded = plaintext
+            .iter()
+            .map(|&(index, _)| pieces[index])
+            .collect::<Vec<_>>();
+        let decoded_ivs = plaintext
+            .iter()
+            .map(|&(index, _)| ivs[index])
+            .collect::<Vec<_>>();
+        verifier.decode(
+            &mut decoded,
+            round_keys,
+            &decoded_ivs,
+            aes_iterations,
+            breadth_iterations,
+        );
+
+        let mismatched = plaintext
+            .iter()
+            .zip(decoded.iter())
+            .filter(|((_, plaintext), decoded)| plaintext != *decoded)
+            .map(|((index, _), _)| *index)
+            .collect::<Vec<_>>();
+        if mismatched.is_empty() {
+            Ok(())
+        } else {
+            Err(VerificationError {
+                verified: plaintext.len(),
+                mismatched,
+            })
+        }
+    }
+
+    /// The fastest CPU backend available on this machine that passes known-answer self-test,
+    /// software fallback is constant-time
+    pub fn cpu_backend() -> Backend {
+        Self::from_codec(Self::cpu_codec()).backend()
+    }
+
+    fn cpu_codec() -> Codec {
+        CPU_BACKENDS
+            .iter()
+            .find_map(|&backend| Self::with_backend(backend).ok())
+            .expect("Software backend always passes self-test unless the build is broken")
+            .codec
+    }
+}
+
+/// Encodes pieces in parallel batches of `N` pieces, the last incomplete batch is padded with
+/// zero pieces
+fn encode_batched<const N: usize, F>(pieces: &mut [Piece], ivs: &[Block], encode: F)
+where
+    F: Fn(&mut [Piece; N], [Block; N]) + Sync,
+{
+    pieces
+        .par_chunks_mut(N)
+        .zip(ivs.par_chunks(N))
+        .for_each(|(pieces, ivs)| {
+            if let Ok(pieces) = pieces.try_into() {
+                encode(pieces, ivs.try_into().unwrap());
+            } else {
+                let mut padded_pieces = [[0u8; PIECE_SIZE]; N];
+                let mut padded_ivs = [Block::default(); N];
+                padded_pieces[..pieces.len()].copy_from_slice(pieces);
+                padded_ivs[..ivs.len()].copy_from_slice(ivs);
+
+                encode(&mut padded_pieces, padded_ivs);
+
+                pieces.copy_from_slice(&padded_pieces[..pieces.len()]);
+            }
+        });
+}
+
+/// Same as `encode_batched()`, but also collects roots returned by `seal`
+fn seal_batched<const N: usize, F>(pieces: &mut [Piece], ivs: &[Block], seal: F) -> Vec<Hash>
+where
+    F: Fn(&mut [Piece; N], [Block; N]) -> [Hash; N] + Sync,
+{
+    pieces
+        .par_chunks_mut(N)
+        .zip(ivs.par_chunks(N))
+        .flat_map_iter(|(pieces, ivs)| {
+            if let Ok(pieces) = pieces.try_into() {
+                seal(pieces, ivs.try_into().unwrap()).to_vec()
+            } else {
+                let mut padded_pieces = [[0u8; PIECE_SIZE]; N];
+                let mut padded_ivs = [Block::default(); N];
+                padded_pieces[..pieces.len()].copy_from_slice(pieces);
+                padded_ivs[..ivs.len()].copy_from_slice(ivs);
+
+                let roots = seal(&mut padded_pieces, padded_ivs);
+
+                pieces.copy_from_slice(&padded_pieces[..pieces.len()]);
+                roots[..pieces.len()].to_vec()
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "opencl")]
+fn copy_from_output(pieces: &mut [Piece], output: &[u8]) {
+    pieces
+        .iter_mut()
+        .zip(output.chunks_exact(PIECE_SIZE))
+        .for_each(|(piece, output)| {
+            piece.copy_from_slice(output);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::test_data::CORRECT_ENCODING;
+    use crate::por::test_data::CORRECT_ENCODING_BREADTH_10;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use crate::BLOCK_SIZE;
+
+    #[test]
+    fn test_backends() {
+        for &backend in [
+            Backend::OpenCL,
+            Backend::VAes,
+            Backend::AesNi,
+            Backend::SoftwareFixSlicing,
+            Backend::SoftwareLuT,
+        ]
+        .iter()
+        {
+            let mut dispatcher = match Dispatcher::with_backend(backend) {
+                Ok(dispatcher) => dispatcher,
+                Err(BackendError::Unavailable(_)) => {
+                    continue;
+                }
+                Err(error) => {
+                    panic!("{}", error);
+                }
+            };
+            assert_eq!(dispatcher.backend(), backend);
+            let round_keys = RoundKeys::new(&ID);
+
+            // Not a multiple of any batch size to check padding
+            let mut pieces = vec![INPUT; 5];
+            let ivs = vec![IV; 5];
+
+            dispatcher.encode(&mut pieces, &round_keys, &ivs, 256, 1);
+            for piece in pieces.iter() {
+                assert_eq!(piece.to_vec(), CORRECT_ENCODING.to_vec(), "{:?}", backend);
+            }
+
+            dispatcher.decode(&mut pieces, &round_keys, &ivs, 256, 1);
+            for piece in pieces.iter() {
+                assert_eq!(piece.to_vec(), INPUT.to_vec(), "{:?}", backend);
+            }
+
+            // Single piece is enough for breadth
+            let mut pieces = [INPUT];
+            let ivs = [IV];
+
+            dispatcher.encode(&mut pieces, &round_keys, &ivs, 256, 10);
+            assert_eq!(
+                pieces[0].to_vec(),
+                CORRECT_ENCODING_BREADTH_10.to_vec(),
+                "{:?}",
+                backend,
+            );
+
+            dispatcher.decode(&mut pieces, &round_keys, &ivs, 256, 10);
+            assert_eq!(pieces[0].to_vec(), INPUT.to_vec(), "{:?}", backend);
+
+            assert_eq!(dispatcher.backend(), backend);
+            assert_eq!(dispatcher.last_error(), None, "{:?}", backend);
+        }
+    }
+
+    #[test]
+    fn test_indexed() {
+        let mut dispatcher = Dispatcher::new();
+        let round_keys = RoundKeys::new(&ID);
+        let iv_derivation = IvDerivation::from_round_keys(&round_keys, None);
+        let ivs = iv_derivation.piece_ivs::<5>(10);
+
+        let mut pieces = vec![INPUT; 5];
+        dispatcher.encode(&mut pieces, &round_keys, &ivs, 16, 2);
+        let mut indexed_pieces = vec![INPUT; 5];
+        dispatcher.encode_indexed(&mut indexed_pieces, &round_keys, &iv_derivation, 10, 16, 2);
+        assert_eq!(pieces, indexed_pieces);
+        // Identical plaintext pieces are encoded differently
+        assert_ne!(pieces[0].to_vec(), pieces[1].to_vec());
+
+        dispatcher.decode_indexed(&mut indexed_pieces, &round_keys, &iv_derivation, 10, 16, 2);
+        assert_eq!(indexed_pieces, vec![INPUT; 5]);
+    }
+
+    #[test]
+    fn test_seal() {
+        let hasher = AesHash::new();
+        let round_keys = RoundKeys::new(&ID);
+        let ivs = IvDerivation::from_round_keys(&round_keys, None).piece_ivs::<5>(0);
+        for &backend in CPU_BACKENDS.iter() {
+            if let Ok(mut dispatcher) = Dispatcher::with_backend(backend) {
+                let mut expected = vec![INPUT; 5];
+                dispatcher.encode(&mut expected, &round_keys, &ivs, 16, 2);
+
+                let mut pieces = vec![INPUT; 5];
+                let tree = dispatcher.seal(&mut pieces, &round_keys, &ivs, &hasher, 16, 2);
+                assert_eq!(pieces, expected, "{:?}", backend);
+                assert_eq!(
+                    tree.commitment(),
+                    CommitmentTree::new(hasher, &pieces).commitment(),
+                    "{:?}",
+                    backend,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_verified() {
+        let round_keys = RoundKeys::new(&ID);
+        let ivs = IvDerivation::from_round_keys(&round_keys, None).piece_ivs::<6>(0);
+        let verifications = [
+            Verification::All,
+            Verification::Sample {
+                count: 2,
+                seed: [1u8; 16],
+            },
+        ];
+        for &backend in CPU_BACKENDS.iter() {
+            if let Ok(mut dispatcher) = Dispatcher::with_backend(backend) {
+                for verification in verifications.iter() {
+                    let mut pieces = vec![INPUT; 6];
+                    assert_eq!(
+                        dispatcher.encode_verified(
+                            &mut pieces,
+                            &round_keys,
+                            &ivs,
+                            16,
+                            2,
+                            verification,
+                        ),
+                        Ok(()),
+                        "{:?}",
+                        backend,
+                    );
+                    let mut decoded = pieces.clone();
+                    dispatcher.decode(&mut decoded, &round_keys, &ivs, 16, 2);
+                    assert_eq!(decoded, vec![INPUT; 6]);
+                }
+            }
+        }
+
+        // Bit flips after encoding are reported
+        let dispatcher = Dispatcher::new();
+        let mut pieces = vec![INPUT; 6];
+        let plaintext = pieces.iter().copied().enumerate().collect::<Vec<_>>();
+        Dispatcher::with_backend(Backend::SoftwareLuT)
+            .unwrap()
+            .encode(&mut pieces, &round_keys, &ivs, 16, 2);
+        pieces[1][100] ^= 1;
+        pieces[4][4095] ^= 0x80;
+        assert_eq!(
+            dispatcher.verify_encoding(&pieces, &plaintext, &round_keys, &ivs, 16, 2),
+            Err(VerificationError {
+                verified: 6,
+                mismatched: vec![1, 4],
+            }),
+        );
+    }
+
+    #[test]
+    fn test_chained() {
+        let round_keys = RoundKeys::new(&ID);
+        let mut plaintext = vec![INPUT; 5];
+        plaintext[0][0] ^= 1;
+
+        let mut expected = plaintext.clone();
+        let mut iv = IV;
+        for piece in expected.iter_mut() {
+            SoftwareLuT::new().encode(
+                piece,
+                &SoftwareLuTKeys::from_round_keys(&round_keys),
+                iv,
+                16,
+                2,
+            );
+            iv = chain::next_iv(piece);
+        }
+
+        for &backend in CPU_BACKENDS.iter() {
+            if let Ok(mut dispatcher) = Dispatcher::with_backend(backend) {
+                let mut pieces = plaintext.clone();
+                let next_iv = dispatcher.encode_chained(&mut pieces, &round_keys, &IV, 16, 2);
+                assert_eq!(pieces, expected, "{:?}", backend);
+                assert_eq!(next_iv, iv);
+
+                dispatcher.decode_chained(&mut pieces, &round_keys, &IV, 16, 2);
+                assert_eq!(pieces, plaintext, "{:?}", backend);
+            }
+        }
+
+        // Change in the first piece affects every following piece
+        let mut pieces = vec![INPUT; 5];
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.encode_chained(&mut pieces, &round_keys, &IV, 16, 2);
+        // CPU dispatcher that OpenCL delegates chained encoding to is kept for subsequent calls
+        #[cfg(feature = "opencl")]
+        assert_eq!(
+            dispatcher.cpu.is_some(),
+            dispatcher.backend() == Backend::OpenCL,
+        );
+        for (piece, expected) in pieces.iter().zip(expected.iter()) {
+            assert_ne!(piece.to_vec(), expected.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_decode_range() {
+        let round_keys = RoundKeys::new(&ID);
+        for &backend in CPU_BACKENDS.iter() {
+            if let Ok(mut dispatcher) = Dispatcher::with_backend(backend) {
+                let mut pieces = vec![INPUT];
+                dispatcher.encode(&mut pieces, &round_keys, &[IV], 16, 3);
+
+                dispatcher.decode_range(&mut pieces[0], &round_keys, &IV, 100..120, 16, 3);
+                assert_eq!(
+                    pieces[0][100 * BLOCK_SIZE..120 * BLOCK_SIZE],
+                    INPUT[100 * BLOCK_SIZE..120 * BLOCK_SIZE],
+                    "{:?}",
+                    backend,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback() {
+        let dispatcher = Dispatcher::new();
+
+        #[cfg(feature = "opencl")]
+        {
+            if Dispatcher::with_backend(Backend::OpenCL).is_ok() {
+                assert_eq!(dispatcher.backend(), Backend::OpenCL);
+                return;
+            }
+        }
+
+        assert_eq!(dispatcher.backend(), Dispatcher::cpu_backend());
+    }
+
+    #[cfg(feature = "opencl")]
+    #[test]
+    fn test_runtime_fallback() {
+        let mut dispatcher = Dispatcher::with_backend(Backend::SoftwareLuT).unwrap();
+        dispatcher.fall_back_to_cpu("Out of device memory".to_string());
+
+        assert_eq!(dispatcher.backend(), Dispatcher::cpu_backend());
+        assert_eq!(
+            dispatcher.last_error(),
+            Some(&RuntimeError {
+                backend: Backend::SoftwareLuT,
+                fallback: Dispatcher::cpu_backend(),
+                message: "Out of device memory".to_string(),
+            }),
+        );
+    }
+
+    #[cfg(not(feature = "opencl"))]
+    #[test]
+    fn test_unavailable() {
+        assert_eq!(
+            Dispatcher::with_backend(Backend::OpenCL).err(),
+            Some(BackendError::Unavailable(Backend::OpenCL)),
+        );
+    }
+}
@@ -0,0 +1,493 @@
+use crate::por::dispatcher::Backend;
+use crate::por::dispatcher::BackendError;
+use crate::por::dispatcher::Dispatcher;
+use crate::por::dispatcher::RuntimeError;
+use crate::por::iv::IvDerivation;
+use crate::por::opencl::OpenCL;
+use crate::por::opencl::OpenCLError;
+use crate::por::opencl::OpenCLKeys;
+use crate::por::opencl::OpenCLOptions;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::Piece;
+use crate::PIECE_SIZE;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Number of pieces OpenCL device gets before its throughput is known
+const INITIAL_DEVICE_CHUNK: usize = 64;
+/// Number of pieces per CPU thread before CPU throughput is known
+const INITIAL_CPU_CHUNK_PER_THREAD: usize = 4;
+/// Chunks are sized such that each of them takes approximately this much time to process
+const TARGET_CHUNK_DURATION: Duration = Duration::from_millis(100);
+
+pub type Result<T> = std::result::Result<T, HybridError>;
+
+#[derive(Debug)]
+pub enum HybridError {
+    /// OpenCL device can't be used
+    OpenCL(OpenCLError),
+    /// CPU backend can't be used
+    Cpu(BackendError),
+}
+
+impl fmt::Display for HybridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HybridError::OpenCL(error) => write!(f, "{}", error),
+            HybridError::Cpu(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for HybridError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HybridError::OpenCL(error) => Some(error),
+            HybridError::Cpu(error) => Some(error),
+        }
+    }
+}
+
+impl From<OpenCLError> for HybridError {
+    fn from(error: OpenCLError) -> Self {
+        HybridError::OpenCL(error)
+    }
+}
+
+impl From<BackendError> for HybridError {
+    fn from(error: BackendError) -> Self {
+        HybridError::Cpu(error)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Operation {
+    Encode,
+    Decode,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Worker {
+    Device,
+    Cpu,
+}
+
+/// Pieces that are not processed yet, workers take chunks from the front, such that pieces are
+/// modified in place and their order is preserved
+struct Queue<'a> {
+    pieces: &'a mut [Piece],
+    ivs: &'a [Block],
+    /// Pieces per second
+    device_throughput: Option<f64>,
+    /// Pieces per second
+    cpu_throughput: Option<f64>,
+}
+
+impl<'a> Queue<'a> {
+    fn new(pieces: &'a mut [Piece], ivs: &'a [Block]) -> Self {
+        Self {
+            pieces,
+            ivs,
+            device_throughput: None,
+            cpu_throughput: None,
+        }
+    }
+
+    /// Takes the next chunk for a worker, sized by its measured throughput, returns `None` when
+    /// there is nothing left
+    fn take(
+        &mut self,
+        worker: Worker,
+        initial_chunk: usize,
+    ) -> Option<(&'a mut [Piece], &'a [Block])> {
+        let remaining = self.pieces.len();
+        if remaining == 0 {
+            return None;
+        }
+
+        let (own_throughput, other_throughput) = match worker {
+            Worker::Device => (self.device_throughput, self.cpu_throughput),
+            Worker::Cpu => (self.cpu_throughput, self.device_throughput),
+        };
+
+        let mut chunk = match own_throughput {
+            Some(throughput) => (throughput * TARGET_CHUNK_DURATION.as_secs_f64()).ceil() as usize,
+            None => initial_chunk,
+        };
+        // Taking more than own share of what is left would leave the other worker idle at the end
+        if let (Some(own_throughput), Some(other_throughput)) = (own_throughput, other_throughput) {
+            let share = own_throughput / (own_throughput + other_throughput);
+            chunk = chunk.min((remaining as f64 * share).ceil() as usize);
+        }
+        let chunk = chunk.max(1).min(remaining);
+
+        let (pieces, rest_pieces) = std::mem::take(&mut self.pieces).split_at_mut(chunk);
+        let (ivs, rest_ivs) = self.ivs.split_at(chunk);
+        self.pieces = rest_pieces;
+        self.ivs = rest_ivs;
+
+        Some((pieces, ivs))
+    }
+
+    fn record(&mut self, worker: Worker, pieces: usize, elapsed: Duration) {
+        let throughput = pieces as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        match worker {
+            Worker::Device => self.device_throughput.replace(throughput),
+            Worker::Cpu => self.cpu_throughput.replace(throughput),
+        };
+    }
+}
+
+/// Proof of replication that splits large batches of pieces between OpenCL device and CPU
+/// dynamically, proportionally to their measured throughput
+pub struct Hybrid {
+    opencl: Option<OpenCL>,
+    /// Shared by CPU worker and device worker after device failure
+    cpu: Dispatcher,
+    last_error: Option<RuntimeError>,
+}
+
+impl Hybrid {
+    pub fn new() -> Result<Self> {
+        Self::with_options(OpenCLOptions::default())
+    }
+
+    pub fn with_options(options: OpenCLOptions) -> Result<Self> {
+        let mut opencl = OpenCL::with_options(options)?;
+        opencl.self_test()?;
+
+        let cpu = Dispatcher::with_backend(Dispatcher::cpu_backend())?;
+
+        Ok(Self {
+            opencl: Some(opencl),
+            cpu,
+            last_error: None,
+        })
+    }
+
+    /// Whether OpenCL device is still used, it is abandoned after the first runtime error and
+    /// only CPU is used afterwards
+    pub fn device_available(&self) -> bool {
+        self.opencl.is_some()
+    }
+
+    /// Error that made OpenCL device abandoned, if any
+    pub fn last_error(&self) -> Option<&RuntimeError> {
+        self.last_error.as_ref()
+    }
+
+    /// Proof of replication encoding of any number of pieces, each with its own IV, result is the
+    /// same as if all pieces were encoded by one backend
+    pub fn encode(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.process(
+            Operation::Encode,
+            pieces,
+            round_keys,
+            ivs,
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Same as `encode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_indexed(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut ivs = vec![Block::default(); pieces.len()];
+        iv_derivation.fill(first_index, &mut ivs);
+        self.encode(pieces, round_keys, &ivs, aes_iterations, breadth_iterations);
+    }
+
+    /// Proof of replication decoding of any number of pieces, each with its own IV, result is the
+    /// same as if all pieces were decoded by one backend
+    pub fn decode(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.process(
+            Operation::Decode,
+            pieces,
+            round_keys,
+            ivs,
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Same as `decode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn decode_indexed(
+        &mut self,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut ivs = vec![Block::default(); pieces.len()];
+        iv_derivation.fill(first_index, &mut ivs);
+        self.decode(pieces, round_keys, &ivs, aes_iterations, breadth_iterations);
+    }
+
+    fn process(
+        &mut self,
+        operation: Operation,
+        pieces: &mut [Piece],
+        round_keys: &RoundKeys,
+        ivs: &[Block],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        assert_eq!(pieces.len(), ivs.len());
+
+        let queue = Mutex::new(Queue::new(pieces, ivs));
+        let opencl = &mut self.opencl;
+        let cpu = Mutex::new(&mut self.cpu);
+        let mut device_error = None;
+
+        let cpu_worker = || {
+            let initial_chunk = rayon::current_num_threads() * INITIAL_CPU_CHUNK_PER_THREAD;
+
+            loop {
+                let chunk = queue.lock().unwrap().take(Worker::Cpu, initial_chunk);
+                let (pieces, ivs) = match chunk {
+                    Some(chunk) => chunk,
+                    None => {
+                        break;
+                    }
+                };
+
+                let start = Instant::now();
+                process_cpu(
+                    &mut cpu.lock().unwrap(),
+                    operation,
+                    pieces,
+                    round_keys,
+                    ivs,
+                    aes_iterations,
+                    breadth_iterations,
+                );
+                queue
+                    .lock()
+                    .unwrap()
+                    .record(Worker::Cpu, pieces.len(), start.elapsed());
+            }
+        };
+
+        let device_worker = || {
+            let keys = OpenCLKeys::from_round_keys(round_keys);
+
+            while let Some(codec) = opencl {
+                let chunk = queue
+                    .lock()
+                    .unwrap()
+                    .take(Worker::Device, INITIAL_DEVICE_CHUNK);
+                let (pieces, ivs) = match chunk {
+                    Some(chunk) => chunk,
+                    None => {
+                        break;
+                    }
+                };
+
+                let start = Instant::now();
+                let input = pieces.concat();
+                let result = match operation {
+                    Operation::Encode => codec.encode(
+                        &input,
+                        ivs,
+                        &[&keys],
+                        None,
+                        aes_iterations as u32,
+                        breadth_iterations as u32,
+                    ),
+                    Operation::Decode => codec.decode(
+                        &input,
+                        ivs,
+                        &[&keys],
+                        None,
+                        aes_iterations as u32,
+                        breadth_iterations as u32,
+                    ),
+                };
+
+                match result {
+                    Ok(output) => {
+                        pieces
+                            .iter_mut()
+                            .zip(output.chunks_exact(PIECE_SIZE))
+                            .for_each(|(piece, output)| {
+                                piece.copy_from_slice(output);
+                            });
+                        queue
+                            .lock()
+                            .unwrap()
+                            .record(Worker::Device, pieces.len(), start.elapsed());
+                    }
+                    Err(error) => {
+                        // Pieces are untouched on error, so CPU can process this chunk instead
+                        opencl.take();
+                        device_error.replace(error.to_string());
+                        process_cpu(
+                            &mut cpu.lock().unwrap(),
+                            operation,
+                            pieces,
+                            round_keys,
+                            ivs,
+                            aes_iterations,
+                            breadth_iterations,
+                        );
+                    }
+                }
+            }
+        };
+
+        rayon::join(device_worker, cpu_worker);
+
+        if let Some(message) = device_error {
+            self.last_error = Some(RuntimeError {
+                backend: Backend::OpenCL,
+                fallback: self.cpu.backend(),
+                message,
+            });
+        }
+    }
+}
+
+fn process_cpu(
+    cpu: &mut Dispatcher,
+    operation: Operation,
+    pieces: &mut [Piece],
+    round_keys: &RoundKeys,
+    ivs: &[Block],
+    aes_iterations: usize,
+    breadth_iterations: usize,
+) {
+    match operation {
+        Operation::Encode => {
+            cpu.encode(pieces, round_keys, ivs, aes_iterations, breadth_iterations)
+        }
+        Operation::Decode => {
+            cpu.decode(pieces, round_keys, ivs, aes_iterations, breadth_iterations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::test_data::CORRECT_ENCODING;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use rand::Rng;
+
+    #[test]
+    fn test_queue() {
+        let mut pieces = vec![[0u8; PIECE_SIZE]; 100];
+        for (index, piece) in pieces.iter_mut().enumerate() {
+            piece[0] = index as u8;
+        }
+        let ivs = vec![IV; 100];
+        let mut queue = Queue::new(&mut pieces, &ivs);
+
+        // Initial chunk is used before throughput is known
+        let (chunk, chunk_ivs) = queue.take(Worker::Device, 10).unwrap();
+        assert_eq!(chunk.len(), 10);
+        assert_eq!(chunk_ivs.len(), 10);
+        assert_eq!(chunk[0][0], 0);
+        queue.record(Worker::Device, 10, Duration::from_millis(10));
+
+        let (chunk, _) = queue.take(Worker::Cpu, 5).unwrap();
+        assert_eq!(chunk.len(), 5);
+        assert_eq!(chunk[0][0], 10);
+        queue.record(Worker::Cpu, 5, Duration::from_millis(20));
+
+        // Device is 4 times faster than CPU, so it can't take more than 4/5 of what is left
+        let (chunk, _) = queue.take(Worker::Device, 10).unwrap();
+        assert_eq!(chunk.len(), 68);
+        assert_eq!(chunk[0][0], 15);
+
+        let (chunk, _) = queue.take(Worker::Cpu, 5).unwrap();
+        assert_eq!(chunk.len(), 4);
+        assert_eq!(chunk[0][0], 83);
+
+        let mut taken = 87;
+        while let Some((chunk, _)) = queue.take(Worker::Cpu, 5) {
+            assert_eq!(chunk[0][0] as usize, taken);
+            taken += chunk.len();
+        }
+        assert_eq!(taken, 100);
+    }
+
+    #[test]
+    fn test_hybrid() {
+        let mut hybrid = match Hybrid::new() {
+            Ok(hybrid) => hybrid,
+            Err(HybridError::OpenCL(error @ OpenCLError::NoPlatform))
+            | Err(HybridError::OpenCL(error @ OpenCLError::NoDevice)) => {
+                eprintln!("{}, skipping test", error);
+                return;
+            }
+            Err(error) => panic!("{}", error),
+        };
+
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill(&mut id[..]);
+
+        let mut pieces = vec![INPUT; 3];
+        let mut ivs = vec![IV; 3];
+        for _ in 0..200 {
+            let mut piece = [0u8; PIECE_SIZE];
+            rand::thread_rng().fill(&mut piece[..]);
+            pieces.push(piece);
+
+            let mut iv = [0u8; 16];
+            rand::thread_rng().fill(&mut iv[..]);
+            ivs.push(iv);
+        }
+
+        let round_keys = RoundKeys::new(&ID);
+        let random_round_keys = RoundKeys::new(&id);
+
+        let mut encodings = pieces.clone();
+        hybrid.encode(&mut encodings[..3], &round_keys, &ivs[..3], 256, 1);
+        for encoding in encodings[..3].iter() {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING.to_vec());
+        }
+
+        let mut encodings = pieces.clone();
+        hybrid.encode(&mut encodings, &random_round_keys, &ivs, 256, 1);
+
+        let mut cpu = Dispatcher::with_backend(Dispatcher::cpu_backend()).unwrap();
+        let mut cpu_encodings = pieces.clone();
+        cpu.encode(&mut cpu_encodings, &random_round_keys, &ivs, 256, 1);
+        assert!(encodings == cpu_encodings);
+
+        hybrid.decode(&mut encodings, &random_round_keys, &ivs, 256, 1);
+        assert!(encodings == pieces);
+        assert!(hybrid.device_available());
+        assert!(hybrid.last_error().is_none());
+    }
+}
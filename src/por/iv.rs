@@ -0,0 +1,321 @@
+//! Deterministic per-piece IV derivation using AES as a PRF.
+//!
+//! PRF key is `AES-128(id, salt)` (salt is all zeroes when not provided) and IV of piece `index` is
+//! `AES-128(key, index)` with index encoded as little-endian 128-bit integer. Different pieces of
+//! the same replica always get different IVs, so identical plaintext pieces don't encode
+//! identically, and IVs never need to be stored. Constant-time fixsliced AES is used.
+
+use crate::aes_low_level::fixslice;
+use crate::aes_low_level::fixslice::State;
+use crate::aes_low_level::fixslice::LANES;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::BLOCK_SIZE;
+use zeroize::Zeroize;
+
+/// Derives IVs of pieces of one replica from their indices
+pub struct IvDerivation {
+    keys: [State; 11],
+}
+
+impl IvDerivation {
+    /// Different salts produce unrelated IVs for the same id
+    pub fn new(id: &Block, salt: Option<&Block>) -> Self {
+        Self::from_round_keys(&RoundKeys::new(id), salt)
+    }
+
+    pub fn from_round_keys(round_keys: &RoundKeys, salt: Option<&Block>) -> Self {
+        let mut id_keys = fixslice::enc_keys(round_keys.enc());
+        let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+        if let Some(salt) = salt {
+            blocks[0] = *salt;
+        }
+        encrypt(&id_keys, &mut blocks);
+
+        let (mut round_keys_enc, mut round_keys_dec) = fixslice::expand_keys_128(&blocks[0]);
+        let keys = fixslice::enc_keys(&round_keys_enc);
+        id_keys.zeroize();
+        blocks.zeroize();
+        round_keys_enc.zeroize();
+        round_keys_dec.zeroize();
+
+        Self { keys }
+    }
+
+    /// IV of piece `index`
+    pub fn piece_iv(&self, index: u64) -> Block {
+        let [iv] = self.piece_ivs(index);
+        iv
+    }
+
+    /// IVs of `N` consecutive pieces starting with `first_index`, suitable for batch APIs of
+    /// backends
+    pub fn piece_ivs<const N: usize>(&self, first_index: u64) -> [Block; N] {
+        let mut ivs = [[0u8; BLOCK_SIZE]; N];
+        self.fill(first_index, &mut ivs);
+        ivs
+    }
+
+    /// Fills `ivs` with IVs of consecutive pieces starting with `first_index`
+    pub fn fill(&self, first_index: u64, ivs: &mut [Block]) {
+        for (chunk_index, chunk) in ivs.chunks_mut(LANES).enumerate() {
+            let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+            for (lane, block) in blocks.iter_mut().enumerate() {
+                let index = first_index.wrapping_add((chunk_index * LANES + lane) as u64);
+                block[..8].copy_from_slice(&index.to_le_bytes());
+            }
+
+            encrypt(&self.keys, &mut blocks);
+
+            chunk.copy_from_slice(&blocks[..chunk.len()]);
+        }
+    }
+}
+
+impl Drop for IvDerivation {
+    fn drop(&mut self) {
+        self.keys.zeroize();
+    }
+}
+
+fn encrypt(keys: &[State; 11], blocks: &mut [Block; LANES]) {
+    let mut state = fixslice::bitslice(blocks);
+    fixslice::encrypt(keys, &mut state);
+    *blocks = fixslice::un_bitslice(&state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::aes_ni::AesNi;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::aes_ni::AesNiKeys;
+    use crate::por::software_bit_slicing::SoftwareBitSlicing;
+    use crate::por::software_bit_slicing::SoftwareBitSlicingKeys;
+    use crate::por::software_fix_slicing::SoftwareFixSlicing;
+    use crate::por::software_fix_slicing::SoftwareFixSlicingKeys;
+    use crate::por::software_lut::SoftwareLuT;
+    use crate::por::software_lut::SoftwareLuTKeys;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::vaes::VAes;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::vaes::VAesKeys;
+    use crate::Piece;
+    use aes_frast::aes_core::block_enc_k128;
+    use aes_frast::aes_core::setkey_enc_k128;
+
+    const AES_ITERATIONS: usize = 16;
+    const BREADTH_ITERATIONS: usize = 2;
+
+    fn aes_128(key: &Block, input: &Block) -> Block {
+        let mut keys = [0u32; 44];
+        setkey_enc_k128(key, &mut keys);
+        let mut output = [0u8; BLOCK_SIZE];
+        block_enc_k128(input, &mut output, &keys);
+        output
+    }
+
+    #[test]
+    fn test_reference() {
+        let salt = [7u8; BLOCK_SIZE];
+        for &salt in [None, Some(&salt)].iter() {
+            let iv_derivation = IvDerivation::new(&ID, salt);
+            let key = aes_128(&ID, salt.unwrap_or(&[0u8; BLOCK_SIZE]));
+
+            let ivs = iv_derivation.piece_ivs::<7>(u64::MAX - 2);
+            for (offset, iv) in ivs.iter().enumerate() {
+                let index = (u64::MAX - 2).wrapping_add(offset as u64);
+                let mut input = [0u8; BLOCK_SIZE];
+                input[..8].copy_from_slice(&index.to_le_bytes());
+                assert_eq!(*iv, aes_128(&key, &input));
+                assert_eq!(iv_derivation.piece_iv(index), *iv);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distinct() {
+        let ivs = IvDerivation::new(&ID, None).piece_ivs::<16>(0);
+        for (i, iv) in ivs.iter().enumerate() {
+            assert!(!ivs[..i].contains(iv));
+        }
+
+        let salted_ivs = IvDerivation::new(&ID, Some(&[1u8; BLOCK_SIZE])).piece_ivs::<16>(0);
+        assert!(ivs.iter().all(|iv| !salted_ivs.contains(iv)));
+    }
+
+    /// `*_indexed()` methods of every backend must match regular methods with derived IVs
+    #[test]
+    fn test_backends() {
+        let round_keys = RoundKeys::new(&ID);
+        let iv_derivation = IvDerivation::from_round_keys(&round_keys, None);
+        let ivs = iv_derivation.piece_ivs::<12>(5);
+
+        let mut expected = [INPUT; 12];
+        {
+            let software_lut = SoftwareLuT::new();
+            let keys = SoftwareLuTKeys::from_round_keys(&round_keys);
+            for (piece, iv) in expected.iter_mut().zip(ivs.iter()) {
+                software_lut.encode(piece, &keys, *iv, AES_ITERATIONS, BREADTH_ITERATIONS);
+            }
+
+            let mut piece = INPUT;
+            software_lut.encode_indexed(
+                &mut piece,
+                &keys,
+                &iv_derivation,
+                5,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            assert_eq!(piece.to_vec(), expected[0].to_vec());
+            software_lut.decode_indexed(
+                &mut piece,
+                &keys,
+                &iv_derivation,
+                5,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            assert_eq!(piece.to_vec(), INPUT.to_vec());
+        }
+        let check = |pieces: &[Piece]| {
+            for (piece, expected) in pieces.iter().zip(expected.iter()) {
+                assert_eq!(piece.to_vec(), expected.to_vec());
+            }
+        };
+        let check_decoded = |pieces: &[Piece]| {
+            for piece in pieces {
+                assert_eq!(piece.to_vec(), INPUT.to_vec());
+            }
+        };
+
+        {
+            let software_fix_slicing = SoftwareFixSlicing::new();
+            let keys = SoftwareFixSlicingKeys::from_round_keys(&round_keys);
+            let mut piece = INPUT;
+            software_fix_slicing.encode_indexed(
+                &mut piece,
+                &keys,
+                &iv_derivation,
+                5,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            let mut pieces = [INPUT; 4];
+            software_fix_slicing.encode_x4_indexed(
+                &mut pieces,
+                &keys,
+                &iv_derivation,
+                5,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            check(&[piece]);
+            check(&pieces);
+            software_fix_slicing.decode_indexed(
+                &mut pieces[3],
+                &keys,
+                &iv_derivation,
+                8,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            check_decoded(&pieces[3..]);
+        }
+
+        {
+            let software_bit_slicing = SoftwareBitSlicing::new();
+            let keys = SoftwareBitSlicingKeys::from_round_keys(&round_keys);
+            let mut pieces = [INPUT; 8];
+            software_bit_slicing.encode_indexed(
+                &mut pieces,
+                &keys,
+                &iv_derivation,
+                5,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            check(&pieces);
+            software_bit_slicing.decode_indexed(
+                &mut pieces,
+                &keys,
+                &iv_derivation,
+                5,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+            );
+            check_decoded(&pieces);
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Ok(aes_ni) = AesNi::new() {
+                let keys = AesNiKeys::from_round_keys(&round_keys);
+                let mut pieces = [INPUT; 4];
+                aes_ni.encode_indexed(
+                    &mut pieces,
+                    &keys,
+                    &iv_derivation,
+                    5,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+                let mut pieces_x8 = [INPUT; 8];
+                aes_ni.encode_x8_indexed(
+                    &mut pieces_x8,
+                    &keys,
+                    &iv_derivation,
+                    5,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+                check(&pieces);
+                check(&pieces_x8);
+                aes_ni.decode_indexed(
+                    &mut pieces[1],
+                    &keys,
+                    &iv_derivation,
+                    6,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+                aes_ni.decode_x8_indexed(
+                    &mut pieces_x8[7],
+                    &keys,
+                    &iv_derivation,
+                    12,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+                check_decoded(&[pieces[1], pieces_x8[7]]);
+            }
+
+            if let Ok(vaes) = VAes::new() {
+                let keys = VAesKeys::from_round_keys(&round_keys);
+                let mut pieces = [INPUT; 12];
+                vaes.encode_indexed(
+                    &mut pieces,
+                    &keys,
+                    &iv_derivation,
+                    5,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+                check(&pieces);
+                vaes.decode_indexed(
+                    &mut pieces[11],
+                    &keys,
+                    &iv_derivation,
+                    16,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+                check_decoded(&pieces[11..]);
+            }
+        }
+    }
+}
@@ -0,0 +1,612 @@
+//! Crash-safe resumable encoding of files.
+//!
+//! `EncodingJob` encodes input file batch by batch, every batch is encoded with all breadth
+//! iterations in memory, written into output and synced, after which the number of pieces written
+//! is saved into a journal next to the output. Journal is replaced atomically, so it always
+//! describes a consistent state, and after restart encoding continues from the first batch that
+//! is not recorded in it (rewriting it if the previous write was interrupted). Journal is removed
+//! once the whole file is encoded.
+//!
+//! Journal doesn't contain the id (which is the AES key), only its fingerprint in
+//! `DOMAIN_JOURNAL` that is compared when the job is resumed.
+//!
+//! Journal layout (all integers are little-endian, `JOURNAL_SIZE` bytes in total): magic, format
+//! version, IV scheme, piece size, iterations, id fingerprint, IV, number of pieces, number of
+//! pieces written into output and CRC-32 of everything before it.
+
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::por::container::crc32;
+use crate::por::container::IvScheme;
+use crate::por::dispatcher::Dispatcher;
+use crate::por::iv::IvDerivation;
+use crate::por::stream;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::PIECE_SIZE;
+use std::convert::TryInto;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+/// Domain of hashes used for fingerprints of ids stored in journals
+pub const DOMAIN_JOURNAL: u8 = 5;
+/// Prefix of fingerprinted id, so that fingerprint differs from other hashes of the id
+const FINGERPRINT_TAG: &[u8] = b"rust-aes-proofs journal id";
+
+pub(crate) const MAGIC: [u8; 8] = *b"AESPJRNL";
+/// Version of journal format
+pub(crate) const FORMAT_VERSION: u16 = 2;
+pub(crate) const JOURNAL_SIZE: usize = 84;
+
+/// Number of pieces encoded at once by default
+pub(crate) const DEFAULT_BATCH_SIZE: usize = 64;
+
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    /// Input size is not a multiple of piece size
+    InvalidInputSize(u64),
+    /// Journal is malformed or corrupted
+    InvalidJournal(&'static str),
+    /// Journal belongs to a job with different parameters or input
+    ParametersMismatch,
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(error) => write!(f, "I/O error: {}", error),
+            JournalError::InvalidInputSize(size) => {
+                write!(f, "Input size {} is not a multiple of piece size", size)
+            }
+            JournalError::InvalidJournal(reason) => write!(f, "Invalid journal: {}", reason),
+            JournalError::ParametersMismatch => {
+                write!(f, "Journal belongs to a job with different parameters")
+            }
+        }
+    }
+}
+
+impl std::error::Error for JournalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JournalError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for JournalError {
+    fn from(error: io::Error) -> Self {
+        JournalError::Io(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, JournalError>;
+
+/// Path of the journal of encoding into `output`
+pub fn journal_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".journal");
+    PathBuf::from(path)
+}
+
+/// One-way fingerprint of `id` that is stored in journal instead of the id itself
+pub fn id_fingerprint(id: &Block) -> Hash {
+    let mut data = Zeroizing::new(FINGERPRINT_TAG.to_vec());
+    data.extend_from_slice(id);
+    AesHash::new().hash_in_domain(DOMAIN_JOURNAL, &data)
+}
+
+/// Progress of an encoding job
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Journal {
+    /// See `id_fingerprint()`
+    pub id_fingerprint: Hash,
+    pub iv: Block,
+    pub iv_scheme: IvScheme,
+    pub aes_iterations: u32,
+    pub breadth_iterations: u32,
+    /// Number of pieces in input
+    pub pieces: u64,
+    /// Number of pieces already encoded and written into output
+    pub encoded_pieces: u64,
+}
+
+impl Journal {
+    /// Loads journal from `path`, returns `None` if there is no journal
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match fs::read(path) {
+            Ok(bytes) => Self::from_bytes(&bytes).map(Some),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Whether all pieces are written into output
+    pub fn is_finished(&self) -> bool {
+        self.encoded_pieces == self.pieces
+    }
+
+    /// IVs of pieces in `piece_range`
+    fn piece_ivs(&self, id: &Block, piece_range: Range<u64>) -> Vec<Block> {
+        match self.iv_scheme {
+            IvScheme::Sequential => piece_range
+                .map(|index| stream::piece_iv(&self.iv, index))
+                .collect(),
+            IvScheme::Derived => {
+                let mut ivs =
+                    vec![Block::default(); (piece_range.end - piece_range.start) as usize];
+                IvDerivation::new(id, Some(&self.iv)).fill(piece_range.start, &mut ivs);
+                ivs
+            }
+        }
+    }
+
+    /// Replaces journal at `path` atomically
+    fn save(&self, path: &Path) -> Result<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&self.to_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(tmp_path, path)?;
+        sync_parent(path)
+    }
+
+    fn to_bytes(&self) -> [u8; JOURNAL_SIZE] {
+        let mut bytes = [0u8; JOURNAL_SIZE];
+        bytes[0..8].copy_from_slice(&MAGIC);
+        bytes[8..10].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes[10] = self.iv_scheme.to_byte();
+        bytes[12..16].copy_from_slice(&(PIECE_SIZE as u32).to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.aes_iterations.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.breadth_iterations.to_le_bytes());
+        bytes[32..48].copy_from_slice(&self.id_fingerprint);
+        bytes[48..64].copy_from_slice(&self.iv);
+        bytes[64..72].copy_from_slice(&self.pieces.to_le_bytes());
+        bytes[72..80].copy_from_slice(&self.encoded_pieces.to_le_bytes());
+        let checksum = crc32(&bytes[..80]);
+        bytes[80..84].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != JOURNAL_SIZE || bytes[0..8] != MAGIC {
+            return Err(JournalError::InvalidJournal("Not a journal"));
+        }
+        if u32::from_le_bytes(bytes[80..84].try_into().unwrap()) != crc32(&bytes[..80]) {
+            return Err(JournalError::InvalidJournal("Checksum mismatch"));
+        }
+        let format_version = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+        if format_version != FORMAT_VERSION {
+            return Err(JournalError::InvalidJournal("Unsupported format version"));
+        }
+        let iv_scheme = IvScheme::from_byte(bytes[10])
+            .ok_or(JournalError::InvalidJournal("Unknown IV scheme"))?;
+        let piece_size = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        if piece_size as usize != PIECE_SIZE {
+            return Err(JournalError::InvalidJournal("Unsupported piece size"));
+        }
+        let reserved_zero = bytes[11..12]
+            .iter()
+            .chain(bytes[24..32].iter())
+            .all(|&byte| byte == 0);
+        if !reserved_zero {
+            return Err(JournalError::InvalidJournal("Reserved bytes are not zero"));
+        }
+
+        let aes_iterations = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        let breadth_iterations = u32::from_le_bytes(bytes[20..24].try_into().unwrap());
+        if aes_iterations == 0 || breadth_iterations == 0 {
+            return Err(JournalError::InvalidJournal("Iterations must not be 0"));
+        }
+        let pieces = u64::from_le_bytes(bytes[64..72].try_into().unwrap());
+        let encoded_pieces = u64::from_le_bytes(bytes[72..80].try_into().unwrap());
+        if encoded_pieces > pieces {
+            return Err(JournalError::InvalidJournal(
+                "Encoded pieces are out of range",
+            ));
+        }
+
+        Ok(Self {
+            id_fingerprint: bytes[32..48].try_into().unwrap(),
+            iv: bytes[48..64].try_into().unwrap(),
+            iv_scheme,
+            aes_iterations,
+            breadth_iterations,
+            pieces,
+            encoded_pieces,
+        })
+    }
+}
+
+/// Encodes input file into output file (IV of piece `i` is derived from `iv` and `i` according to
+/// IV scheme, input size must be a multiple of piece size), saving progress into journal next to
+/// the output (see `journal_path()`) after every batch of pieces, so that encoding continues from
+/// the last batch written after restart
+pub struct EncodingJob {
+    dispatcher: Dispatcher,
+    round_keys: RoundKeys,
+    batch_size: usize,
+    input: File,
+    output: File,
+    journal_path: PathBuf,
+    journal: Journal,
+}
+
+impl EncodingJob {
+    /// Starts a new job or resumes the one recorded in the journal next to `output`, returns
+    /// `JournalError::ParametersMismatch` if the journal belongs to a job with different
+    /// parameters (including id) or input size
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        dispatcher: Dispatcher,
+        round_keys: RoundKeys,
+        iv: Block,
+        iv_scheme: IvScheme,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+        input: &Path,
+        output: &Path,
+    ) -> Result<Self> {
+        assert!(aes_iterations > 0 && breadth_iterations > 0);
+
+        let input = File::open(input)?;
+        let input_size = input.metadata()?.len();
+        if input_size % PIECE_SIZE as u64 != 0 {
+            return Err(JournalError::InvalidInputSize(input_size));
+        }
+        let aes_iterations: u32 = aes_iterations
+            .try_into()
+            .expect("AES iterations must fit into u32");
+        let breadth_iterations: u32 = breadth_iterations
+            .try_into()
+            .expect("Breadth iterations must fit into u32");
+        let new_journal = Journal {
+            id_fingerprint: id_fingerprint(round_keys.id()),
+            iv,
+            iv_scheme,
+            aes_iterations,
+            breadth_iterations,
+            pieces: input_size / PIECE_SIZE as u64,
+            encoded_pieces: 0,
+        };
+
+        let journal_path = journal_path(output);
+        let journal = match Journal::load(&journal_path)? {
+            Some(journal) => {
+                let same_parameters = Journal {
+                    encoded_pieces: journal.encoded_pieces,
+                    ..new_journal
+                } == journal;
+                if !same_parameters {
+                    return Err(JournalError::ParametersMismatch);
+                }
+                journal
+            }
+            None => new_journal,
+        };
+
+        let output = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(output)?;
+        output.set_len(input_size)?;
+
+        Ok(Self {
+            dispatcher,
+            round_keys,
+            batch_size: DEFAULT_BATCH_SIZE,
+            input,
+            output,
+            journal_path,
+            journal,
+        })
+    }
+
+    /// Number of pieces encoded at once, larger batches use more memory, but make better use of
+    /// parallelism and need fewer syncs
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0);
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Current progress
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Encodes the next batch of pieces, writes it into output and saves progress into the
+    /// journal. Returns `false` if all pieces are already encoded
+    pub fn step(&mut self) -> Result<bool> {
+        let journal = &mut self.journal;
+        if journal.is_finished() {
+            return Ok(false);
+        }
+
+        let start = journal.encoded_pieces;
+        let end = journal.pieces.min(start + self.batch_size as u64);
+        let mut pieces = vec![[0u8; PIECE_SIZE]; (end - start) as usize];
+        self.input
+            .seek(SeekFrom::Start(start * PIECE_SIZE as u64))?;
+        for piece in pieces.iter_mut() {
+            self.input.read_exact(piece)?;
+        }
+
+        self.dispatcher.encode(
+            &mut pieces,
+            &self.round_keys,
+            &journal.piece_ivs(self.round_keys.id(), start..end),
+            journal.aes_iterations as usize,
+            journal.breadth_iterations as usize,
+        );
+
+        // Repeated after restart if journal was not updated yet
+        self.output
+            .seek(SeekFrom::Start(start * PIECE_SIZE as u64))?;
+        for piece in &pieces {
+            self.output.write_all(piece)?;
+        }
+        self.output.sync_data()?;
+
+        journal.encoded_pieces = end;
+        journal.save(&self.journal_path)?;
+        Ok(true)
+    }
+
+    /// Encodes all remaining pieces and removes the journal, returns number of pieces
+    pub fn run(mut self) -> Result<u64> {
+        while self.step()? {}
+
+        // Empty input finishes before the journal is ever saved
+        match fs::remove_file(&self.journal_path) {
+            Ok(()) => sync_parent(&self.journal_path)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+        Ok(self.journal.pieces)
+    }
+}
+
+/// Makes rename or removal of a file in the directory durable
+fn sync_parent(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::dispatcher::Backend;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use crate::Piece;
+    use rand::Rng;
+    use std::env;
+
+    const AES_ITERATIONS: usize = 16;
+    const BREADTH_ITERATIONS: usize = 3;
+    const PIECES: usize = 5;
+
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new() -> Self {
+            let dir = env::temp_dir().join(format!(
+                "rust-aes-proofs-journal-{:016x}",
+                rand::thread_rng().gen::<u64>(),
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn input() -> Vec<Piece> {
+        (0..PIECES)
+            .map(|index| {
+                let mut piece = INPUT;
+                piece[0] ^= index as u8;
+                piece
+            })
+            .collect()
+    }
+
+    fn open(iv_scheme: IvScheme, input: &Path, output: &Path) -> Result<EncodingJob> {
+        EncodingJob::open(
+            Dispatcher::with_backend(Backend::SoftwareFixSlicing).unwrap(),
+            RoundKeys::new(&ID),
+            IV,
+            iv_scheme,
+            AES_ITERATIONS,
+            BREADTH_ITERATIONS,
+            input,
+            output,
+        )
+        .map(|job| job.with_batch_size(2))
+    }
+
+    #[test]
+    fn test_resume() {
+        let dir = TestDir::new();
+        let input_path = dir.0.join("input");
+        let output_path = dir.0.join("output");
+        fs::write(&input_path, input().concat()).unwrap();
+
+        for &iv_scheme in [IvScheme::Sequential, IvScheme::Derived].iter() {
+            let ivs = match iv_scheme {
+                IvScheme::Sequential => (0..PIECES as u64)
+                    .map(|index| stream::piece_iv(&IV, index))
+                    .collect::<Vec<_>>(),
+                IvScheme::Derived => IvDerivation::new(&ID, Some(&IV))
+                    .piece_ivs::<PIECES>(0)
+                    .to_vec(),
+            };
+            let mut expected = input();
+            Dispatcher::with_backend(Backend::SoftwareLuT)
+                .unwrap()
+                .encode(
+                    &mut expected,
+                    &RoundKeys::new(&ID),
+                    &ivs,
+                    AES_ITERATIONS,
+                    BREADTH_ITERATIONS,
+                );
+            let expected = expected.concat();
+
+            // 3 batches
+            for interrupt_after in 0..=3 {
+                let mut job = open(iv_scheme, &input_path, &output_path).unwrap();
+                for _ in 0..interrupt_after {
+                    assert!(job.step().unwrap());
+                }
+                drop(job);
+
+                // Simulate interrupted write of the next batch and stale temporary journal
+                if let Some(journal) = Journal::load(&journal_path(&output_path)).unwrap() {
+                    let mut output = fs::read(&output_path).unwrap();
+                    let start = journal.encoded_pieces as usize * PIECE_SIZE;
+                    output[start..].iter_mut().for_each(|byte| *byte = 0xAA);
+                    fs::write(&output_path, output).unwrap();
+                }
+                fs::write(dir.0.join("output.journal.tmp"), b"garbage").unwrap();
+
+                let job = open(iv_scheme, &input_path, &output_path).unwrap();
+                assert_eq!(job.run().unwrap(), PIECES as u64);
+                assert_eq!(
+                    fs::read(&output_path).unwrap(),
+                    expected,
+                    "{:?} {}",
+                    iv_scheme,
+                    interrupt_after
+                );
+                assert!(!journal_path(&output_path).exists());
+            }
+        }
+
+        fs::write(&input_path, []).unwrap();
+        let job = open(IvScheme::Sequential, &input_path, &output_path).unwrap();
+        assert_eq!(job.run().unwrap(), 0);
+        assert!(fs::read(&output_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = TestDir::new();
+        let input_path = dir.0.join("input");
+        let output_path = dir.0.join("output");
+
+        fs::write(&input_path, [0u8; PIECE_SIZE + 1]).unwrap();
+        assert!(matches!(
+            open(IvScheme::Sequential, &input_path, &output_path),
+            Err(JournalError::InvalidInputSize(size)) if size == PIECE_SIZE as u64 + 1,
+        ));
+
+        fs::write(&input_path, input().concat()).unwrap();
+        let mut job = open(IvScheme::Sequential, &input_path, &output_path).unwrap();
+        for _ in 0..2 {
+            job.step().unwrap();
+        }
+        drop(job);
+
+        let bytes = fs::read(journal_path(&output_path)).unwrap();
+        let journal = Journal::from_bytes(&bytes).unwrap();
+        assert_eq!(journal.encoded_pieces, 4);
+        assert_eq!(Journal::from_bytes(&journal.to_bytes()).unwrap(), journal);
+        // Id is never stored
+        assert!(!bytes.windows(ID.len()).any(|window| window == ID));
+        assert_eq!(journal.id_fingerprint, id_fingerprint(&ID));
+        assert_ne!(id_fingerprint(&ID), AesHash::new().hash(&ID));
+
+        // Different parameters, id or input
+        assert!(matches!(
+            open(IvScheme::Derived, &input_path, &output_path),
+            Err(JournalError::ParametersMismatch),
+        ));
+        assert!(matches!(
+            EncodingJob::open(
+                Dispatcher::with_backend(Backend::SoftwareFixSlicing).unwrap(),
+                RoundKeys::new(&IV),
+                IV,
+                IvScheme::Sequential,
+                AES_ITERATIONS,
+                BREADTH_ITERATIONS,
+                &input_path,
+                &output_path,
+            ),
+            Err(JournalError::ParametersMismatch),
+        ));
+        assert!(matches!(
+            EncodingJob::open(
+                Dispatcher::with_backend(Backend::SoftwareFixSlicing).unwrap(),
+                RoundKeys::new(&ID),
+                IV,
+                IvScheme::Sequential,
+                AES_ITERATIONS + 1,
+                BREADTH_ITERATIONS,
+                &input_path,
+                &output_path,
+            ),
+            Err(JournalError::ParametersMismatch),
+        ));
+        fs::write(&input_path, input()[..4].concat()).unwrap();
+        assert!(matches!(
+            open(IvScheme::Sequential, &input_path, &output_path),
+            Err(JournalError::ParametersMismatch),
+        ));
+
+        // Corrupted journal
+        let mut corrupted = bytes.clone();
+        corrupted[40] ^= 1;
+        assert!(matches!(
+            Journal::from_bytes(&corrupted),
+            Err(JournalError::InvalidJournal("Checksum mismatch")),
+        ));
+        assert!(matches!(
+            Journal::from_bytes(&bytes[..80]),
+            Err(JournalError::InvalidJournal("Not a journal")),
+        ));
+        let mut wrong_range = journal;
+        wrong_range.encoded_pieces = 6;
+        assert!(matches!(
+            Journal::from_bytes(&wrong_range.to_bytes()),
+            Err(JournalError::InvalidJournal(
+                "Encoded pieces are out of range"
+            )),
+        ));
+    }
+}
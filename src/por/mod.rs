@@ -1,13 +1,32 @@
-/// Proof of replication implementations
+//! Proof of replication implementations
 
 #[cfg(target_arch = "x86_64")]
 pub mod aes_ni;
+pub mod audit;
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod container;
+#[cfg(feature = "std")]
+pub mod dispatcher;
+#[cfg(feature = "opencl")]
+pub mod hybrid;
+pub mod iv;
+#[cfg(feature = "std")]
+pub mod journal;
 #[cfg(feature = "opencl")]
 pub mod opencl;
+pub mod range;
+mod self_test;
 pub mod software_bit_slicing;
+pub mod software_fix_slicing;
 pub mod software_lut;
-#[cfg(test)]
-mod test_data;
+#[cfg(feature = "std")]
+pub mod stream;
+// Also used by self-tests
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) mod test_data;
 mod utils;
 #[cfg(target_arch = "x86_64")]
 pub mod vaes;
+#[cfg(feature = "std")]
+pub mod verification;
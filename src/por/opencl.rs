@@ -1,23 +1,80 @@
+mod program_cache;
 mod utils;
 
-use crate::aes_low_level::software;
+use crate::por::iv::IvDerivation;
+use crate::por::self_test;
+use crate::round_keys::RoundKeys;
 use crate::Block;
+use crate::Piece;
 use crate::BLOCK_SIZE;
 use crate::PIECE_SIZE;
 use ocl::{
     core::{
         build_program, create_buffer, create_command_queue, create_context, create_kernel,
-        create_program_with_source, enqueue_kernel, enqueue_read_buffer, enqueue_write_buffer,
-        finish, set_kernel_arg, ArgVal, CommandQueue, Context, ContextProperties, Event, Kernel,
-        Mem, Uchar16, Uint,
+        create_program_with_binary, create_program_with_source, enqueue_kernel,
+        enqueue_read_buffer, enqueue_write_buffer, ffi, finish, get_program_info, set_kernel_arg,
+        ArgVal, CommandQueue, Context, ContextProperties, DeviceInfo, DeviceInfoResult, Event,
+        Kernel, Mem, Program, ProgramInfo, ProgramInfoResult, Uchar16, Uint,
     },
-    flags, Device, MemFlags, OclPrm, Platform, Result,
+    flags, Device, MemFlags, OclPrm, Platform, Result as OclResult,
 };
 use std::ffi::CString;
+use std::fmt;
+use std::path::PathBuf;
+use std::ptr;
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
 
 const AES_OPEN_CL: &str = include_str!("opencl/kernels.cl");
 const ROUND_KEYS_LENGTH_128: usize = 44;
 
+pub type Result<T> = std::result::Result<T, OpenCLError>;
+
+#[derive(Debug)]
+pub enum OpenCLError {
+    /// No OpenCL platform is available (no ICD is installed)
+    NoPlatform,
+    /// None of available OpenCL platforms has any devices
+    NoDevice,
+    /// Error returned by OpenCL
+    Ocl(ocl::Error),
+    /// Device produced wrong results in known-answer self-test
+    SelfTestFailed,
+}
+
+impl fmt::Display for OpenCLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenCLError::NoPlatform => write!(f, "No OpenCL platform found"),
+            OpenCLError::NoDevice => write!(f, "No OpenCL device found"),
+            OpenCLError::Ocl(error) => write!(f, "OpenCL error: {}", error),
+            OpenCLError::SelfTestFailed => write!(f, "OpenCL known-answer self-test failed"),
+        }
+    }
+}
+
+impl std::error::Error for OpenCLError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenCLError::Ocl(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ocl::Error> for OpenCLError {
+    fn from(error: ocl::Error) -> Self {
+        OpenCLError::Ocl(error)
+    }
+}
+
+impl From<ocl::core::Error> for OpenCLError {
+    fn from(error: ocl::core::Error) -> Self {
+        OpenCLError::Ocl(error.into())
+    }
+}
+
 struct CachedBuffer {
     mem: Mem,
     buffer_size: usize,
@@ -30,16 +87,73 @@ pub struct OpenCLKeys {
 
 impl OpenCLKeys {
     pub fn new(id: &Block) -> Self {
-        let keys_enc = software::expand_keys_aes_128_enc(&id);
-        let keys_dec = software::expand_keys_aes_128_dec(&id);
+        Self::from_round_keys(&RoundKeys::new(id))
+    }
+
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let keys_enc = *round_keys.enc();
+        let keys_dec = *round_keys.dec();
         Self { keys_enc, keys_dec }
     }
 }
 
+impl Drop for OpenCLKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+/// Implementation of AES used by OpenCL kernels, which one is the fastest depends heavily on the
+/// device, see `OpenCL::with_fastest_strategy()`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum OpenCLStrategy {
+    /// Lookup tables are read from `__constant` memory
+    #[default]
+    ConstantTables,
+    /// Lookup tables are copied into `__local` memory by each work group
+    LocalTables,
+    /// Bitsliced implementation that doesn't use lookup tables at all
+    BitSliced,
+}
+
+impl OpenCLStrategy {
+    /// All supported strategies
+    pub const ALL: [OpenCLStrategy; 3] = [
+        OpenCLStrategy::ConstantTables,
+        OpenCLStrategy::LocalTables,
+        OpenCLStrategy::BitSliced,
+    ];
+
+    /// Define that selects this strategy in OpenCL program
+    fn define(self) -> &'static str {
+        match self {
+            OpenCLStrategy::ConstantTables => "AES_STRATEGY_CONSTANT_TABLES",
+            OpenCLStrategy::LocalTables => "AES_STRATEGY_LOCAL_TABLES",
+            OpenCLStrategy::BitSliced => "AES_STRATEGY_BITSLICED",
+        }
+    }
+}
+
+/// Options for OpenCL implementation
+#[derive(Debug, Clone, Default)]
+pub struct OpenCLOptions {
+    /// Directory where compiled program binaries are cached, program is built from source every
+    /// time if `None`
+    pub cache_dir: Option<PathBuf>,
+    /// Options passed to OpenCL compiler when building program from source
+    pub build_options: String,
+    /// Implementation of AES used by kernels
+    pub strategy: OpenCLStrategy,
+}
+
 pub struct OpenCL {
     buffer_state: Option<CachedBuffer>,
     buffer_iv: Option<CachedBuffer>,
-    buffer_round_keys: Mem,
+    buffer_round_keys: Option<CachedBuffer>,
+    buffer_key_indices: Option<CachedBuffer>,
+    max_constant_buffer_size: usize,
+    strategy: OpenCLStrategy,
     context: Context,
     por_128_enc_kernel: Kernel,
     por_128_dec_kernel: Kernel,
@@ -48,41 +162,38 @@ pub struct OpenCL {
 
 impl OpenCL {
     pub fn new() -> Result<Self> {
-        let platform = Platform::first()?;
+        Self::with_options(OpenCLOptions::default())
+    }
 
-        let device = Device::first(platform)?;
+    pub fn with_options(options: OpenCLOptions) -> Result<Self> {
+        let (platform, device) = Self::find_device()?;
+
+        let max_constant_buffer_size = match device.info(DeviceInfo::MaxConstantBufferSize)? {
+            DeviceInfoResult::MaxConstantBufferSize(size) => size as usize,
+            _ => unreachable!(),
+        };
 
         let context_properties = ContextProperties::new().platform(platform);
         let context = create_context(Some(&context_properties), &[&device], None, None)?;
 
-        let queue = create_command_queue(&context, &device, None)?;
+        let queue = create_command_queue(&context, device, None)?;
 
-        let program = create_program_with_source(&context, &[CString::new(AES_OPEN_CL)?])?;
-
-        let options = CString::new("").unwrap();
-        build_program(&program, Some(&[&device]), &options, None, None)?;
+        let program = Self::create_program(&context, platform, device, &options)?;
 
         let por_128_enc_kernel = create_kernel(&program, "por_128_enc")?;
         let por_128_dec_kernel = create_kernel(&program, "por_128_dec")?;
 
-        let buffer_round_keys = unsafe {
-            create_buffer(
-                &context,
-                flags::MEM_READ_ONLY | flags::MEM_ALLOC_HOST_PTR,
-                ROUND_KEYS_LENGTH_128,
-                None::<&[Uint]>,
-            )?
-        };
-
-        set_kernel_arg(&por_128_enc_kernel, 2, ArgVal::mem(&buffer_round_keys))?;
-        set_kernel_arg(&por_128_dec_kernel, 2, ArgVal::mem(&buffer_round_keys))?;
-
         let buffer_state = Default::default();
         let buffer_iv = Default::default();
+        let buffer_round_keys = Default::default();
+        let buffer_key_indices = Default::default();
         Ok(Self {
             buffer_state,
             buffer_iv,
             buffer_round_keys,
+            buffer_key_indices,
+            max_constant_buffer_size,
+            strategy: options.strategy,
             context,
             por_128_enc_kernel,
             por_128_dec_kernel,
@@ -90,23 +201,175 @@ impl OpenCL {
         })
     }
 
-    /// Takes plaintext input that is multiple of piece size (4096 bytes), same number of IVs and
-    /// expanded round keys
+    /// Returns the first device found along with its platform
+    fn find_device() -> Result<(Platform, Device)> {
+        // `Platform::list()` panics and `Platform::first()` retries for several seconds when no ICD
+        // is installed, so check for platforms presence directly first
+        let mut platforms_count = 0;
+        let status = unsafe { ffi::clGetPlatformIDs(0, ptr::null_mut(), &mut platforms_count) };
+        if status == ffi::CL_PLATFORM_NOT_FOUND_KHR || platforms_count == 0 {
+            return Err(OpenCLError::NoPlatform);
+        }
+
+        for platform in Platform::list() {
+            if let Some(&device) = Device::list_all(platform)?.first() {
+                return Ok((platform, device));
+            }
+        }
+
+        Err(OpenCLError::NoDevice)
+    }
+
+    /// Loads program binary from cache if available, otherwise builds program from source and stores
+    /// its binary in cache for future use
+    fn create_program(
+        context: &Context,
+        platform: Platform,
+        device: Device,
+        options: &OpenCLOptions,
+    ) -> OclResult<Program> {
+        let build_options = format!("-D {} {}", options.strategy.define(), options.build_options);
+
+        let cache = match &options.cache_dir {
+            Some(cache_dir) => {
+                let cache_key =
+                    program_cache::cache_key(platform, device, AES_OPEN_CL, &build_options)?;
+                Some((cache_dir, cache_key))
+            }
+            None => None,
+        };
+        let build_options = CString::new(build_options)?;
+
+        if let Some((cache_dir, cache_key)) = &cache {
+            if let Some(binary) = program_cache::load(cache_dir, cache_key) {
+                // Binary that driver refuses to load is not fatal, program will be rebuilt instead
+                let program = create_program_with_binary(context, &[&device], &[&binary]).and_then(
+                    |program| {
+                        build_program(&program, Some(&[&device]), &build_options, None, None)?;
+                        Ok(program)
+                    },
+                );
+                if let Ok(program) = program {
+                    return Ok(program);
+                }
+            }
+        }
+
+        let program = create_program_with_source(context, &[CString::new(AES_OPEN_CL)?])?;
+        build_program(&program, Some(&[&device]), &build_options, None, None)?;
+
+        // Cache is only an optimization, so failing to store the binary (read-only directory, full
+        // disk) doesn't fail initialization, program will simply be built again next time
+        if let Some((cache_dir, cache_key)) = &cache {
+            if let Ok(ProgramInfoResult::Binaries(binaries)) =
+                get_program_info(&program, ProgramInfo::Binaries)
+            {
+                if let Some(binary) = binaries.first() {
+                    let _ = program_cache::store(cache_dir, cache_key, binary);
+                }
+            }
+        }
+
+        Ok(program)
+    }
+
+    /// Builds program with every strategy, benchmarks each of them on the device and returns
+    /// instance that uses the fastest one, `options.strategy` is ignored
+    pub fn with_fastest_strategy(options: OpenCLOptions) -> Result<Self> {
+        // Enough pieces to keep a reasonably large device busy
+        const PIECES: usize = 256;
+        const AES_ITERATIONS: u32 = 64;
+
+        let input = vec![0u8; PIECES * PIECE_SIZE];
+        let ivs = vec![[0u8; BLOCK_SIZE]; PIECES];
+        let keys = OpenCLKeys::new(&[0u8; BLOCK_SIZE]);
+
+        let mut fastest: Option<(Self, Duration)> = None;
+        for &strategy in OpenCLStrategy::ALL.iter() {
+            let mut codec = Self::with_options(OpenCLOptions {
+                strategy,
+                ..options.clone()
+            })?;
+
+            // First run is not measured, it includes buffers allocation and possibly lazy
+            // compilation by the driver
+            codec.encode(&input, &ivs, &[&keys], None, AES_ITERATIONS, 1)?;
+
+            let start = Instant::now();
+            codec.encode(&input, &ivs, &[&keys], None, AES_ITERATIONS, 1)?;
+            let elapsed = start.elapsed();
+
+            if fastest
+                .as_ref()
+                .is_none_or(|(_, fastest_elapsed)| elapsed < *fastest_elapsed)
+            {
+                fastest.replace((codec, elapsed));
+            }
+        }
+
+        Ok(fastest.unwrap().0)
+    }
+
+    /// Implementation of AES used by this instance
+    pub fn strategy(&self) -> OpenCLStrategy {
+        self.strategy
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&mut self) -> Result<()> {
+        let keys = OpenCLKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+        // Few pieces to exercise more than one work item
+        let ivs = [self_test::IV; 3];
+
+        let encoding = self.encode(
+            &[self_test::INPUT; 3].concat(),
+            &ivs,
+            &[&keys],
+            None,
+            self_test::AES_ITERATIONS as u32,
+            self_test::BREADTH_ITERATIONS as u32,
+        )?;
+        let mut pieces = [[0u8; PIECE_SIZE]; 3];
+        copy_pieces(&mut pieces, &encoding);
+        self_test::check_encodings(&pieces).map_err(|_| OpenCLError::SelfTestFailed)?;
+
+        let decoding = self.decode(
+            &encoding,
+            &ivs,
+            &[&keys],
+            None,
+            self_test::AES_ITERATIONS as u32,
+            self_test::BREADTH_ITERATIONS as u32,
+        )?;
+        copy_pieces(&mut pieces, &decoding);
+        self_test::check_decodings(&pieces).map_err(|_| OpenCLError::SelfTestFailed)
+    }
+
+    /// Takes plaintext input that is multiple of piece size (4096 bytes), same number of IVs, one or
+    /// more sets of expanded round keys and optional per-piece indices into those keys (if indices
+    /// are not provided, all pieces are encoded with the first set of keys)
     ///
     /// Produces ciphertext
     pub fn encode(
         &mut self,
         input: &[u8],
         ivs: &[Block],
-        keys: &OpenCLKeys,
+        keys: &[&OpenCLKeys],
+        key_indices: Option<&[u32]>,
         aes_iterations: u32,
         breadth_iterations: u32,
     ) -> Result<Vec<u8>> {
-        assert!(input.len() % PIECE_SIZE == 0);
+        assert!(input.len().is_multiple_of(PIECE_SIZE));
 
         let blocks_count = input.len() / PIECE_SIZE;
         assert!(blocks_count == ivs.len());
 
+        let (buffer_round_keys, buffer_key_indices) = self.write_keys(
+            keys.iter().map(|keys| &keys.keys_enc),
+            key_indices,
+            blocks_count,
+        )?;
+
         let buffer_state = Self::validate_or_allocate_buffer::<Uchar16>(
             &self.context,
             &mut self.buffer_state,
@@ -123,10 +386,16 @@ impl OpenCL {
 
         set_kernel_arg(&self.por_128_enc_kernel, 0, ArgVal::mem(&buffer_state))?;
         set_kernel_arg(&self.por_128_enc_kernel, 1, ArgVal::mem(&buffer_ivs))?;
-        set_kernel_arg(&self.por_128_enc_kernel, 3, ArgVal::scalar(&aes_iterations))?;
+        set_kernel_arg(&self.por_128_enc_kernel, 2, ArgVal::mem(&buffer_round_keys))?;
+        set_kernel_arg(
+            &self.por_128_enc_kernel,
+            3,
+            ArgVal::mem(&buffer_key_indices),
+        )?;
+        set_kernel_arg(&self.por_128_enc_kernel, 4, ArgVal::scalar(&aes_iterations))?;
         set_kernel_arg(
             &self.por_128_enc_kernel,
-            4,
+            5,
             ArgVal::scalar(&breadth_iterations),
         )?;
 
@@ -148,19 +417,7 @@ impl OpenCL {
                 &buffer_ivs,
                 true,
                 0,
-                &utils::ivs_to_uchar16_vec(&ivs),
-                None::<Event>,
-                None::<&mut Event>,
-            )?;
-        }
-
-        unsafe {
-            enqueue_write_buffer(
-                &self.queue,
-                &self.buffer_round_keys,
-                true,
-                0,
-                &utils::keys_to_uint_vec(&keys.keys_enc),
+                &utils::ivs_to_uchar16_vec(ivs),
                 None::<Event>,
                 None::<&mut Event>,
             )?;
@@ -204,23 +461,54 @@ impl OpenCL {
         Ok(output)
     }
 
-    /// Takes ciphertext input that is multiple of piece size (4096 bytes), same number of IVs and
-    /// expanded round keys
+    /// Same as `encode()` with one set of keys, IVs are derived from indices of consecutive pieces
+    /// starting with `first_index`
+    pub fn encode_indexed(
+        &mut self,
+        input: &[u8],
+        keys: &OpenCLKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: u32,
+        breadth_iterations: u32,
+    ) -> Result<Vec<u8>> {
+        let mut ivs = vec![Block::default(); input.len() / PIECE_SIZE];
+        iv_derivation.fill(first_index, &mut ivs);
+        self.encode(
+            input,
+            &ivs,
+            &[keys],
+            None,
+            aes_iterations,
+            breadth_iterations,
+        )
+    }
+
+    /// Takes ciphertext input that is multiple of piece size (4096 bytes), same number of IVs, one
+    /// or more sets of expanded round keys and optional per-piece indices into those keys (if
+    /// indices are not provided, all pieces are decoded with the first set of keys)
     ///
     /// Produces plaintext
     pub fn decode(
         &mut self,
         input: &[u8],
         ivs: &[Block],
-        keys: &OpenCLKeys,
+        keys: &[&OpenCLKeys],
+        key_indices: Option<&[u32]>,
         aes_iterations: u32,
         breadth_iterations: u32,
     ) -> Result<Vec<u8>> {
-        assert!(input.len() % PIECE_SIZE == 0);
+        assert!(input.len().is_multiple_of(PIECE_SIZE));
 
         let blocks_count = input.len() / PIECE_SIZE;
         assert!(blocks_count == ivs.len());
 
+        let (buffer_round_keys, buffer_key_indices) = self.write_keys(
+            keys.iter().map(|keys| &keys.keys_dec),
+            key_indices,
+            blocks_count,
+        )?;
+
         let buffer_state = Self::validate_or_allocate_buffer::<Uchar16>(
             &self.context,
             &mut self.buffer_state,
@@ -237,10 +525,16 @@ impl OpenCL {
 
         set_kernel_arg(&self.por_128_dec_kernel, 0, ArgVal::mem(&buffer_state))?;
         set_kernel_arg(&self.por_128_dec_kernel, 1, ArgVal::mem(&buffer_ivs))?;
-        set_kernel_arg(&self.por_128_dec_kernel, 3, ArgVal::scalar(&aes_iterations))?;
+        set_kernel_arg(&self.por_128_dec_kernel, 2, ArgVal::mem(&buffer_round_keys))?;
         set_kernel_arg(
             &self.por_128_dec_kernel,
-            4,
+            3,
+            ArgVal::mem(&buffer_key_indices),
+        )?;
+        set_kernel_arg(&self.por_128_dec_kernel, 4, ArgVal::scalar(&aes_iterations))?;
+        set_kernel_arg(
+            &self.por_128_dec_kernel,
+            5,
             ArgVal::scalar(&breadth_iterations),
         )?;
 
@@ -262,19 +556,7 @@ impl OpenCL {
                 &buffer_ivs,
                 true,
                 0,
-                &utils::ivs_to_uchar16_vec(&ivs),
-                None::<Event>,
-                None::<&mut Event>,
-            )?;
-        }
-
-        unsafe {
-            enqueue_write_buffer(
-                &self.queue,
-                &self.buffer_round_keys,
-                true,
-                0,
-                &utils::keys_to_uint_vec(&keys.keys_dec),
+                &utils::ivs_to_uchar16_vec(ivs),
                 None::<Event>,
                 None::<&mut Event>,
             )?;
@@ -318,12 +600,136 @@ impl OpenCL {
         Ok(output)
     }
 
+    /// Same as `decode()` with one set of keys, IVs are derived from indices of consecutive pieces
+    /// starting with `first_index`
+    pub fn decode_indexed(
+        &mut self,
+        input: &[u8],
+        keys: &OpenCLKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: u32,
+        breadth_iterations: u32,
+    ) -> Result<Vec<u8>> {
+        let mut ivs = vec![Block::default(); input.len() / PIECE_SIZE];
+        iv_derivation.fill(first_index, &mut ivs);
+        self.decode(
+            input,
+            &ivs,
+            &[keys],
+            None,
+            aes_iterations,
+            breadth_iterations,
+        )
+    }
+
+    /// Uploads all sets of round keys and per-piece key indices to the device, returns buffers
+    /// with keys and indices respectively
+    fn write_keys<'a>(
+        &mut self,
+        keys: impl ExactSizeIterator<Item = &'a [Block; 11]>,
+        key_indices: Option<&[u32]>,
+        blocks_count: usize,
+    ) -> OclResult<(Mem, Mem)> {
+        let keys_count = keys.len();
+        assert!(keys_count > 0);
+
+        let keys_size = keys_count * ROUND_KEYS_LENGTH_128 * std::mem::size_of::<Uint>();
+        if keys_size > self.max_constant_buffer_size {
+            return Err(format!(
+                "{} sets of round keys take {} bytes, which exceeds device limit of {} bytes",
+                keys_count, keys_size, self.max_constant_buffer_size,
+            )
+            .into());
+        }
+
+        let key_indices: Vec<Uint> = match key_indices {
+            Some(key_indices) => {
+                assert!(blocks_count == key_indices.len());
+                assert!(key_indices
+                    .iter()
+                    .all(|&index| (index as usize) < keys_count));
+
+                key_indices.iter().copied().map(Uint::from).collect()
+            }
+            None => vec![Uint::from(0); blocks_count],
+        };
+
+        // Key schedules must not stay in device memory after the buffer is released
+        if let Some(cached_buffer) = &self.buffer_round_keys {
+            if cached_buffer.buffer_size != keys_count * ROUND_KEYS_LENGTH_128 {
+                Self::zero_buffer(&self.queue, cached_buffer)?;
+            }
+        }
+        let buffer_round_keys = Self::validate_or_allocate_buffer::<Uint>(
+            &self.context,
+            &mut self.buffer_round_keys,
+            keys_count * ROUND_KEYS_LENGTH_128,
+            flags::MEM_READ_ONLY | flags::MEM_ALLOC_HOST_PTR,
+        )?;
+
+        let buffer_key_indices = Self::validate_or_allocate_buffer::<Uint>(
+            &self.context,
+            &mut self.buffer_key_indices,
+            blocks_count,
+            flags::MEM_READ_ONLY | flags::MEM_ALLOC_HOST_PTR,
+        )?;
+
+        // Capacity is exact, so keys are never left behind by reallocation
+        let mut keys_u32 = Zeroizing::new(Vec::with_capacity(keys_count * ROUND_KEYS_LENGTH_128));
+        for keys in keys {
+            utils::extend_with_keys(&mut keys_u32, keys);
+        }
+        unsafe {
+            enqueue_write_buffer(
+                &self.queue,
+                &buffer_round_keys,
+                true,
+                0,
+                &keys_u32,
+                None::<Event>,
+                None::<&mut Event>,
+            )?;
+        }
+
+        unsafe {
+            enqueue_write_buffer(
+                &self.queue,
+                &buffer_key_indices,
+                true,
+                0,
+                &key_indices,
+                None::<Event>,
+                None::<&mut Event>,
+            )?;
+        }
+
+        Ok((buffer_round_keys, buffer_key_indices))
+    }
+
+    fn zero_buffer(queue: &CommandQueue, cached_buffer: &CachedBuffer) -> OclResult<()> {
+        let zeroes = vec![0u32; cached_buffer.buffer_size];
+        unsafe {
+            enqueue_write_buffer(
+                queue,
+                &cached_buffer.mem,
+                true,
+                0,
+                &zeroes,
+                None::<Event>,
+                None::<&mut Event>,
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn validate_or_allocate_buffer<T: OclPrm>(
         context: &Context,
         buffer: &mut Option<CachedBuffer>,
         buffer_size: usize,
         flags: MemFlags,
-    ) -> Result<Mem> {
+    ) -> OclResult<Mem> {
         if let Some(cached_buffer) = buffer {
             if cached_buffer.buffer_size == buffer_size {
                 return Ok(cached_buffer.mem.clone());
@@ -340,6 +746,25 @@ impl OpenCL {
     }
 }
 
+impl Drop for OpenCL {
+    /// Overwrites round keys left in device memory, errors are ignored since there is nothing
+    /// useful to do with them at this point
+    fn drop(&mut self) {
+        if let Some(cached_buffer) = &self.buffer_round_keys {
+            let _ = Self::zero_buffer(&self.queue, cached_buffer);
+        }
+    }
+}
+
+fn copy_pieces(pieces: &mut [Piece], output: &[u8]) {
+    pieces
+        .iter_mut()
+        .zip(output.chunks_exact(PIECE_SIZE))
+        .for_each(|(piece, output)| {
+            piece.copy_from_slice(output);
+        });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,24 +775,42 @@ mod tests {
     use crate::por::test_data::IV;
     use rand::Rng;
 
+    /// Tests are skipped on machines without OpenCL, they can still be run in software with POCL
+    /// or Oclgrind
+    fn no_opencl_device() -> bool {
+        match OpenCL::find_device() {
+            Ok(_) => false,
+            Err(error @ OpenCLError::NoPlatform) | Err(error @ OpenCLError::NoDevice) => {
+                eprintln!("{}, skipping test", error);
+                true
+            }
+            Err(error) => panic!("{}", error),
+        }
+    }
+
     #[test]
     fn test_simple() {
+        if no_opencl_device() {
+            return;
+        }
+
         let mut codec = OpenCL::new().unwrap();
 
         let keys = OpenCLKeys::new(&ID);
 
-        let encryption = codec.encode(&INPUT, &[IV], &keys, 256, 1).unwrap();
+        let encryption = codec.encode(&INPUT, &[IV], &[&keys], None, 256, 1).unwrap();
         assert_eq!(encryption, CORRECT_ENCODING.to_vec());
 
         let ivs = vec![IV, IV];
         let encryptions = codec
             .encode(
-                &(0..2)
+                (0..2)
                     .flat_map(|_| INPUT.as_ref().to_vec())
                     .collect::<Vec<u8>>()
                     .as_ref(),
                 &ivs,
-                &keys,
+                &[&keys],
+                None,
                 256,
                 1,
             )
@@ -381,10 +824,14 @@ mod tests {
             CORRECT_ENCODING.to_vec(),
         );
 
-        let decryption = codec.decode(&encryption, &[IV], &keys, 256, 1).unwrap();
+        let decryption = codec
+            .decode(&encryption, &[IV], &[&keys], None, 256, 1)
+            .unwrap();
         assert_eq!(decryption, INPUT.to_vec());
 
-        let decryptions = codec.decode(&encryptions, &ivs, &keys, 256, 1).unwrap();
+        let decryptions = codec
+            .decode(&encryptions, &ivs, &[&keys], None, 256, 1)
+            .unwrap();
         assert_eq!(
             decryptions[PIECE_SIZE..].to_vec(),
             decryptions[..PIECE_SIZE].to_vec(),
@@ -394,22 +841,29 @@ mod tests {
 
     #[test]
     fn test_breadth_10() {
+        if no_opencl_device() {
+            return;
+        }
+
         let mut codec = OpenCL::new().unwrap();
 
         let keys = OpenCLKeys::new(&ID);
 
-        let encryption = codec.encode(&INPUT, &[IV], &keys, 256, 10).unwrap();
+        let encryption = codec
+            .encode(&INPUT, &[IV], &[&keys], None, 256, 10)
+            .unwrap();
         assert_eq!(encryption, CORRECT_ENCODING_BREADTH_10.to_vec());
 
         let ivs = vec![IV, IV];
         let encryptions = codec
             .encode(
-                &(0..2)
+                (0..2)
                     .flat_map(|_| INPUT.as_ref().to_vec())
                     .collect::<Vec<u8>>()
                     .as_ref(),
                 &ivs,
-                &keys,
+                &[&keys],
+                None,
                 256,
                 10,
             )
@@ -423,10 +877,14 @@ mod tests {
             CORRECT_ENCODING_BREADTH_10.to_vec(),
         );
 
-        let decryption = codec.decode(&encryption, &[IV], &keys, 256, 10).unwrap();
+        let decryption = codec
+            .decode(&encryption, &[IV], &[&keys], None, 256, 10)
+            .unwrap();
         assert_eq!(decryption, INPUT.to_vec());
 
-        let decryptions = codec.decode(&encryptions, &ivs, &keys, 256, 10).unwrap();
+        let decryptions = codec
+            .decode(&encryptions, &ivs, &[&keys], None, 256, 10)
+            .unwrap();
         assert_eq!(
             decryptions[PIECE_SIZE..].to_vec(),
             decryptions[..PIECE_SIZE].to_vec(),
@@ -436,6 +894,10 @@ mod tests {
 
     #[test]
     fn test_random_simple() {
+        if no_opencl_device() {
+            return;
+        }
+
         let mut codec = OpenCL::new().unwrap();
 
         let mut id = [0u8; 16];
@@ -449,17 +911,18 @@ mod tests {
 
         let keys = OpenCLKeys::new(&id);
 
-        let encryption = codec.encode(&input, &[iv], &keys, 256, 1).unwrap();
+        let encryption = codec.encode(&input, &[iv], &[&keys], None, 256, 1).unwrap();
 
         let ivs = vec![iv, iv];
         let encryptions = codec
             .encode(
-                &(0..2)
+                (0..2)
                     .flat_map(|_| input.as_ref().to_vec())
                     .collect::<Vec<u8>>()
                     .as_ref(),
                 &ivs,
-                &keys,
+                &[&keys],
+                None,
                 256,
                 1,
             )
@@ -469,10 +932,14 @@ mod tests {
             assert_eq!(single_encryption.to_vec(), encryption.to_vec(),);
         }
 
-        let decryption = codec.decode(&encryption, &[iv], &keys, 256, 1).unwrap();
+        let decryption = codec
+            .decode(&encryption, &[iv], &[&keys], None, 256, 1)
+            .unwrap();
         assert_eq!(decryption, input.to_vec());
 
-        let decryptions = codec.decode(&encryptions, &ivs, &keys, 256, 1).unwrap();
+        let decryptions = codec
+            .decode(&encryptions, &ivs, &[&keys], None, 256, 1)
+            .unwrap();
 
         for decryption in decryptions.chunks_exact(PIECE_SIZE) {
             assert_eq!(decryption.to_vec(), input.to_vec(),);
@@ -481,6 +948,10 @@ mod tests {
 
     #[test]
     fn test_random_breadth_10() {
+        if no_opencl_device() {
+            return;
+        }
+
         let mut codec = OpenCL::new().unwrap();
 
         let mut id = [0u8; 16];
@@ -494,17 +965,20 @@ mod tests {
 
         let keys = OpenCLKeys::new(&id);
 
-        let encryption = codec.encode(&input, &[iv], &keys, 256, 10).unwrap();
+        let encryption = codec
+            .encode(&input, &[iv], &[&keys], None, 256, 10)
+            .unwrap();
 
         let ivs = vec![iv, iv];
         let encryptions = codec
             .encode(
-                &(0..2)
+                (0..2)
                     .flat_map(|_| input.as_ref().to_vec())
                     .collect::<Vec<u8>>()
                     .as_ref(),
                 &ivs,
-                &keys,
+                &[&keys],
+                None,
                 256,
                 10,
             )
@@ -514,13 +988,218 @@ mod tests {
             assert_eq!(single_encryption.to_vec(), encryption.to_vec(),);
         }
 
-        let decryption = codec.decode(&encryption, &[iv], &keys, 256, 10).unwrap();
+        let decryption = codec
+            .decode(&encryption, &[iv], &[&keys], None, 256, 10)
+            .unwrap();
         assert_eq!(decryption, input.to_vec());
 
-        let decryptions = codec.decode(&encryptions, &ivs, &keys, 256, 10).unwrap();
+        let decryptions = codec
+            .decode(&encryptions, &ivs, &[&keys], None, 256, 10)
+            .unwrap();
 
         for decryption in decryptions.chunks_exact(PIECE_SIZE) {
             assert_eq!(decryption.to_vec(), input.to_vec(),);
         }
     }
+
+    #[test]
+    fn test_random_multiple_keys() {
+        if no_opencl_device() {
+            return;
+        }
+
+        let mut codec = OpenCL::new().unwrap();
+
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill(&mut id[..]);
+
+        let mut input = [0u8; PIECE_SIZE];
+        rand::thread_rng().fill(&mut input[..]);
+
+        let mut iv = [0u8; 16];
+        rand::thread_rng().fill(&mut iv[..]);
+
+        let keys = OpenCLKeys::new(&ID);
+        let random_keys = OpenCLKeys::new(&id);
+
+        let encryption = codec
+            .encode(&input, &[iv], &[&random_keys], None, 256, 10)
+            .unwrap();
+
+        let inputs: Vec<u8> = [INPUT.as_ref(), input.as_ref(), INPUT.as_ref()].concat();
+        let ivs = vec![IV, iv, IV];
+        let key_indices = [0, 1, 0];
+        let encryptions = codec
+            .encode(
+                &inputs,
+                &ivs,
+                &[&keys, &random_keys],
+                Some(&key_indices),
+                256,
+                10,
+            )
+            .unwrap();
+
+        assert_eq!(
+            encryptions[..PIECE_SIZE].to_vec(),
+            CORRECT_ENCODING_BREADTH_10.to_vec(),
+        );
+        assert_eq!(
+            encryptions[PIECE_SIZE..PIECE_SIZE * 2].to_vec(),
+            encryption.to_vec(),
+        );
+        assert_eq!(
+            encryptions[PIECE_SIZE * 2..].to_vec(),
+            CORRECT_ENCODING_BREADTH_10.to_vec(),
+        );
+
+        let decryptions = codec
+            .decode(
+                &encryptions,
+                &ivs,
+                &[&keys, &random_keys],
+                Some(&key_indices),
+                256,
+                10,
+            )
+            .unwrap();
+        assert_eq!(decryptions, inputs);
+    }
+
+    #[test]
+    fn test_strategies() {
+        if no_opencl_device() {
+            return;
+        }
+
+        let keys = OpenCLKeys::new(&ID);
+
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill(&mut id[..]);
+        let random_keys = OpenCLKeys::new(&id);
+
+        let mut input = [0u8; PIECE_SIZE];
+        rand::thread_rng().fill(&mut input[..]);
+
+        let inputs: Vec<u8> = [INPUT.as_ref(), input.as_ref(), INPUT.as_ref()].concat();
+        let ivs = vec![IV, IV, IV];
+        let key_indices = [0, 1, 0];
+
+        for &strategy in OpenCLStrategy::ALL.iter() {
+            let mut codec = OpenCL::with_options(OpenCLOptions {
+                strategy,
+                ..OpenCLOptions::default()
+            })
+            .unwrap();
+            assert_eq!(codec.strategy(), strategy);
+
+            let encryption = codec.encode(&INPUT, &[IV], &[&keys], None, 256, 1).unwrap();
+            assert_eq!(encryption, CORRECT_ENCODING.to_vec(), "{:?}", strategy);
+
+            let decryption = codec
+                .decode(&encryption, &[IV], &[&keys], None, 256, 1)
+                .unwrap();
+            assert_eq!(decryption, INPUT.to_vec(), "{:?}", strategy);
+
+            let encryptions = codec
+                .encode(
+                    &inputs,
+                    &ivs,
+                    &[&keys, &random_keys],
+                    Some(&key_indices),
+                    256,
+                    10,
+                )
+                .unwrap();
+            assert_eq!(
+                encryptions[..PIECE_SIZE].to_vec(),
+                CORRECT_ENCODING_BREADTH_10.to_vec(),
+                "{:?}",
+                strategy,
+            );
+            assert_eq!(
+                encryptions[PIECE_SIZE * 2..].to_vec(),
+                CORRECT_ENCODING_BREADTH_10.to_vec(),
+                "{:?}",
+                strategy,
+            );
+
+            let decryptions = codec
+                .decode(
+                    &encryptions,
+                    &ivs,
+                    &[&keys, &random_keys],
+                    Some(&key_indices),
+                    256,
+                    10,
+                )
+                .unwrap();
+            assert_eq!(decryptions, inputs, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_fastest_strategy() {
+        if no_opencl_device() {
+            return;
+        }
+
+        let mut codec = OpenCL::with_fastest_strategy(OpenCLOptions::default()).unwrap();
+
+        let keys = OpenCLKeys::new(&ID);
+
+        let encryption = codec.encode(&INPUT, &[IV], &[&keys], None, 256, 1).unwrap();
+        assert_eq!(encryption, CORRECT_ENCODING.to_vec());
+    }
+
+    #[test]
+    fn test_program_cache() {
+        if no_opencl_device() {
+            return;
+        }
+
+        let cache_dir = std::env::temp_dir().join(format!(
+            "rust-aes-proofs-opencl-cache-{:016x}",
+            rand::thread_rng().gen::<u64>(),
+        ));
+        let options = OpenCLOptions {
+            cache_dir: Some(cache_dir.clone()),
+            ..OpenCLOptions::default()
+        };
+
+        let keys = OpenCLKeys::new(&ID);
+
+        // First instance builds program from source and populates cache, second loads from cache
+        for _ in 0..2 {
+            let mut codec = OpenCL::with_options(options.clone()).unwrap();
+            assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+            let encryption = codec.encode(&INPUT, &[IV], &[&keys], None, 256, 1).unwrap();
+            assert_eq!(encryption, CORRECT_ENCODING.to_vec());
+        }
+
+        // Different build options result in a separate binary
+        let mut codec = OpenCL::with_options(OpenCLOptions {
+            build_options: "-cl-mad-enable".to_string(),
+            ..options
+        })
+        .unwrap();
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 2);
+
+        let decryption = codec
+            .decode(&CORRECT_ENCODING, &[IV], &[&keys], None, 256, 1)
+            .unwrap();
+        assert_eq!(decryption, INPUT.to_vec());
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_self_test() {
+        if no_opencl_device() {
+            return;
+        }
+
+        OpenCL::new().unwrap().self_test().unwrap();
+    }
 }
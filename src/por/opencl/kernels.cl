@@ -1,7 +1,24 @@
 // Ported over from `cargo expand`-ed version of https://github.com/KaneGreen/aes_frast, which is under Apache-2.0 license
 
+// AES implementation is selected at build time with one of the following defines:
+// * `AES_STRATEGY_CONSTANT_TABLES` (default) - lookup tables are read from `__constant` memory
+// * `AES_STRATEGY_LOCAL_TABLES` - lookup tables are copied into `__local` memory by each work group
+// * `AES_STRATEGY_BITSLICED` - lookup tables are not used at all, see `aes_128_enc_bitsliced()`
+#if defined(AES_STRATEGY_LOCAL_TABLES)
+#define TABLES_SPACE __local
+#else
+#define TABLES_SPACE __constant
+#endif
+
+typedef struct {
+    uchar sbox[256];
+    uchar sinv[256];
+    uint te[4][256];
+    uint td[4][256];
+} aes_tables_t;
+
+__constant aes_tables_t AES_TABLES = {
 // S-Box
-__constant uchar SBOX[256] =
 {
    0x63, 0x7C, 0x77, 0x7B, 0xF2, 0x6B, 0x6F, 0xC5, 0x30, 0x01, 0x67, 0x2B, 0xFE, 0xD7, 0xAB, 0x76,
    0xCA, 0x82, 0xC9, 0x7D, 0xFA, 0x59, 0x47, 0xF0, 0xAD, 0xD4, 0xA2, 0xAF, 0x9C, 0xA4, 0x72, 0xC0,
@@ -19,10 +36,9 @@ __constant uchar SBOX[256] =
    0x70, 0x3E, 0xB5, 0x66, 0x48, 0x03, 0xF6, 0x0E, 0x61, 0x35, 0x57, 0xB9, 0x86, 0xC1, 0x1D, 0x9E,
    0xE1, 0xF8, 0x98, 0x11, 0x69, 0xD9, 0x8E, 0x94, 0x9B, 0x1E, 0x87, 0xE9, 0xCE, 0x55, 0x28, 0xDF,
    0x8C, 0xA1, 0x89, 0x0D, 0xBF, 0xE6, 0x42, 0x68, 0x41, 0x99, 0x2D, 0x0F, 0xB0, 0x54, 0xBB, 0x16
-};
+},
 
 // Inversed S-Box
-__constant uchar SINV[256] =
 {
    0x52, 0x09, 0x6A, 0xD5, 0x30, 0x36, 0xA5, 0x38, 0xBF, 0x40, 0xA3, 0x9E, 0x81, 0xF3, 0xD7, 0xFB,
    0x7C, 0xE3, 0x39, 0x82, 0x9B, 0x2F, 0xFF, 0x87, 0x34, 0x8E, 0x43, 0x44, 0xC4, 0xDE, 0xE9, 0xCB,
@@ -40,9 +56,10 @@ __constant uchar SINV[256] =
    0x60, 0x51, 0x7F, 0xA9, 0x19, 0xB5, 0x4A, 0x0D, 0x2D, 0xE5, 0x7A, 0x9F, 0x93, 0xC9, 0x9C, 0xEF,
    0xA0, 0xE0, 0x3B, 0x4D, 0xAE, 0x2A, 0xF5, 0xB0, 0xC8, 0xEB, 0xBB, 0x3C, 0x83, 0x53, 0x99, 0x61,
    0x17, 0x2B, 0x04, 0x7E, 0xBA, 0x77, 0xD6, 0x26, 0xE1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0C, 0x7D
-};
+},
+{
 // T-Box 0 for encryption
-__constant uint TE0[256] = {
+{
     0xC66363A5, 0xF87C7C84, 0xEE777799, 0xF67B7B8D, 0xFFF2F20D, 0xD66B6BBD, 0xDE6F6FB1, 0x91C5C554,
     0x60303050, 0x02010103, 0xCE6767A9, 0x562B2B7D, 0xE7FEFE19, 0xB5D7D762, 0x4DABABE6, 0xEC76769A,
     0x8FCACA45, 0x1F82829D, 0x89C9C940, 0xFA7D7D87, 0xEFFAFA15, 0xB25959EB, 0x8E4747C9, 0xFBF0F00B,
@@ -75,9 +92,9 @@ __constant uint TE0[256] = {
     0x2D9B9BB6, 0x3C1E1E22, 0x15878792, 0xC9E9E920, 0x87CECE49, 0xAA5555FF, 0x50282878, 0xA5DFDF7A,
     0x038C8C8F, 0x59A1A1F8, 0x09898980, 0x1A0D0D17, 0x65BFBFDA, 0xD7E6E631, 0x844242C6, 0xD06868B8,
     0x824141C3, 0x299999B0, 0x5A2D2D77, 0x1E0F0F11, 0x7BB0B0CB, 0xA85454FC, 0x6DBBBBD6, 0x2C16163A,
-};
+},
 // T-Box 1 for encryption
-__constant uint TE1[256] = {
+{
     0xA5C66363, 0x84F87C7C, 0x99EE7777, 0x8DF67B7B, 0x0DFFF2F2, 0xBDD66B6B, 0xB1DE6F6F, 0x5491C5C5,
     0x50603030, 0x03020101, 0xA9CE6767, 0x7D562B2B, 0x19E7FEFE, 0x62B5D7D7, 0xE64DABAB, 0x9AEC7676,
     0x458FCACA, 0x9D1F8282, 0x4089C9C9, 0x87FA7D7D, 0x15EFFAFA, 0xEBB25959, 0xC98E4747, 0x0BFBF0F0,
@@ -110,9 +127,9 @@ __constant uint TE1[256] = {
     0xB62D9B9B, 0x223C1E1E, 0x92158787, 0x20C9E9E9, 0x4987CECE, 0xFFAA5555, 0x78502828, 0x7AA5DFDF,
     0x8F038C8C, 0xF859A1A1, 0x80098989, 0x171A0D0D, 0xDA65BFBF, 0x31D7E6E6, 0xC6844242, 0xB8D06868,
     0xC3824141, 0xB0299999, 0x775A2D2D, 0x111E0F0F, 0xCB7BB0B0, 0xFCA85454, 0xD66DBBBB, 0x3A2C1616,
-};
+},
 // T-Box 2 for encryption
-__constant uint TE2[256] = {
+{
     0x63A5C663, 0x7C84F87C, 0x7799EE77, 0x7B8DF67B, 0xF20DFFF2, 0x6BBDD66B, 0x6FB1DE6F, 0xC55491C5,
     0x30506030, 0x01030201, 0x67A9CE67, 0x2B7D562B, 0xFE19E7FE, 0xD762B5D7, 0xABE64DAB, 0x769AEC76,
     0xCA458FCA, 0x829D1F82, 0xC94089C9, 0x7D87FA7D, 0xFA15EFFA, 0x59EBB259, 0x47C98E47, 0xF00BFBF0,
@@ -145,9 +162,9 @@ __constant uint TE2[256] = {
     0x9BB62D9B, 0x1E223C1E, 0x87921587, 0xE920C9E9, 0xCE4987CE, 0x55FFAA55, 0x28785028, 0xDF7AA5DF,
     0x8C8F038C, 0xA1F859A1, 0x89800989, 0x0D171A0D, 0xBFDA65BF, 0xE631D7E6, 0x42C68442, 0x68B8D068,
     0x41C38241, 0x99B02999, 0x2D775A2D, 0x0F111E0F, 0xB0CB7BB0, 0x54FCA854, 0xBBD66DBB, 0x163A2C16,
-};
+},
 // T-Box 3 for encryption
-__constant uint TE3[256] = {
+{
     0x6363A5C6, 0x7C7C84F8, 0x777799EE, 0x7B7B8DF6, 0xF2F20DFF, 0x6B6BBDD6, 0x6F6FB1DE, 0xC5C55491,
     0x30305060, 0x01010302, 0x6767A9CE, 0x2B2B7D56, 0xFEFE19E7, 0xD7D762B5, 0xABABE64D, 0x76769AEC,
     0xCACA458F, 0x82829D1F, 0xC9C94089, 0x7D7D87FA, 0xFAFA15EF, 0x5959EBB2, 0x4747C98E, 0xF0F00BFB,
@@ -180,9 +197,11 @@ __constant uint TE3[256] = {
     0x9B9BB62D, 0x1E1E223C, 0x87879215, 0xE9E920C9, 0xCECE4987, 0x5555FFAA, 0x28287850, 0xDFDF7AA5,
     0x8C8C8F03, 0xA1A1F859, 0x89898009, 0x0D0D171A, 0xBFBFDA65, 0xE6E631D7, 0x4242C684, 0x6868B8D0,
     0x4141C382, 0x9999B029, 0x2D2D775A, 0x0F0F111E, 0xB0B0CB7B, 0x5454FCA8, 0xBBBBD66D, 0x16163A2C,
-};
+},
+},
+{
 // T-Box 0 for decryption
-__constant uint TD0[256] = {
+{
     0x51F4A750, 0x7E416553, 0x1A17A4C3, 0x3A275E96, 0x3BAB6BCB, 0x1F9D45F1, 0xACFA58AB, 0x4BE30393,
     0x2030FA55, 0xAD766DF6, 0x88CC7691, 0xF5024C25, 0x4FE5D7FC, 0xC52ACBD7, 0x26354480, 0xB562A38F,
     0xDEB15A49, 0x25BA1B67, 0x45EA0E98, 0x5DFEC0E1, 0xC32F7502, 0x814CF012, 0x8D4697A3, 0x6BD3F9C6,
@@ -215,9 +234,9 @@ __constant uint TD0[256] = {
     0x9CD2DF59, 0x55F2733F, 0x1814CE79, 0x73C737BF, 0x53F7CDEA, 0x5FFDAA5B, 0xDF3D6F14, 0x7844DB86,
     0xCAAFF381, 0xB968C43E, 0x3824342C, 0xC2A3405F, 0x161DC372, 0xBCE2250C, 0x283C498B, 0xFF0D9541,
     0x39A80171, 0x080CB3DE, 0xD8B4E49C, 0x6456C190, 0x7BCB8461, 0xD532B670, 0x486C5C74, 0xD0B85742,
-};
+},
 // T-Box 1 for decryption
-__constant uint TD1[256] = {
+{
     0x5051F4A7, 0x537E4165, 0xC31A17A4, 0x963A275E, 0xCB3BAB6B, 0xF11F9D45, 0xABACFA58, 0x934BE303,
     0x552030FA, 0xF6AD766D, 0x9188CC76, 0x25F5024C, 0xFC4FE5D7, 0xD7C52ACB, 0x80263544, 0x8FB562A3,
     0x49DEB15A, 0x6725BA1B, 0x9845EA0E, 0xE15DFEC0, 0x02C32F75, 0x12814CF0, 0xA38D4697, 0xC66BD3F9,
@@ -250,9 +269,9 @@ __constant uint TD1[256] = {
     0x599CD2DF, 0x3F55F273, 0x791814CE, 0xBF73C737, 0xEA53F7CD, 0x5B5FFDAA, 0x14DF3D6F, 0x867844DB,
     0x81CAAFF3, 0x3EB968C4, 0x2C382434, 0x5FC2A340, 0x72161DC3, 0x0CBCE225, 0x8B283C49, 0x41FF0D95,
     0x7139A801, 0xDE080CB3, 0x9CD8B4E4, 0x906456C1, 0x617BCB84, 0x70D532B6, 0x74486C5C, 0x42D0B857,
-};
+},
 // T-Box 2 for decryption
-__constant uint TD2[256] = {
+{
     0xA75051F4, 0x65537E41, 0xA4C31A17, 0x5E963A27, 0x6BCB3BAB, 0x45F11F9D, 0x58ABACFA, 0x03934BE3,
     0xFA552030, 0x6DF6AD76, 0x769188CC, 0x4C25F502, 0xD7FC4FE5, 0xCBD7C52A, 0x44802635, 0xA38FB562,
     0x5A49DEB1, 0x1B6725BA, 0x0E9845EA, 0xC0E15DFE, 0x7502C32F, 0xF012814C, 0x97A38D46, 0xF9C66BD3,
@@ -285,9 +304,9 @@ __constant uint TD2[256] = {
     0xDF599CD2, 0x733F55F2, 0xCE791814, 0x37BF73C7, 0xCDEA53F7, 0xAA5B5FFD, 0x6F14DF3D, 0xDB867844,
     0xF381CAAF, 0xC43EB968, 0x342C3824, 0x405FC2A3, 0xC372161D, 0x250CBCE2, 0x498B283C, 0x9541FF0D,
     0x017139A8, 0xB3DE080C, 0xE49CD8B4, 0xC1906456, 0x84617BCB, 0xB670D532, 0x5C74486C, 0x5742D0B8,
-};
+},
 // T-Box 3 for decryption
-__constant uint TD3[256] = {
+{
     0xF4A75051, 0x4165537E, 0x17A4C31A, 0x275E963A, 0xAB6BCB3B, 0x9D45F11F, 0xFA58ABAC, 0xE303934B,
     0x30FA5520, 0x766DF6AD, 0xCC769188, 0x024C25F5, 0xE5D7FC4F, 0x2ACBD7C5, 0x35448026, 0x62A38FB5,
     0xB15A49DE, 0xBA1B6725, 0xEA0E9845, 0xFEC0E15D, 0x2F7502C3, 0x4CF01281, 0x4697A38D, 0xD3F9C66B,
@@ -320,11 +339,14 @@ __constant uint TD3[256] = {
     0xD2DF599C, 0xF2733F55, 0x14CE7918, 0xC737BF73, 0xF7CDEA53, 0xFDAA5B5F, 0x3D6F14DF, 0x44DB8678,
     0xAFF381CA, 0x68C43EB9, 0x24342C38, 0xA3405FC2, 0x1DC37216, 0xE2250CBC, 0x3C498B28, 0x0D9541FF,
     0xA8017139, 0x0CB3DE08, 0xB4E49CD8, 0x56C19064, 0xCB84617B, 0x32B670D5, 0x6C5C7448, 0xB85742D0,
+},
+},
 };
 
-inline void aes_128_enc(
+inline void aes_128_enc_tables(
 	__global uchar16* state,
-	__constant const uint* keys
+	__constant const uint* keys,
+	TABLES_SPACE const aes_tables_t* tables
 ) {
     uint wa0 = (
         ((uint)(*state)[0] << 24)
@@ -351,90 +373,91 @@ inline void aes_128_enc(
         ^ (uint)(*state)[15]
     ) ^ keys[3];
 
-    uint wb0 = TE0[(size_t)(wa0 >> 24)]
-        ^ TE1[(size_t)(wa1 >> 16) & 0xFF]
-        ^ TE2[(size_t)(wa2 >> 8) & 0xFF]
-        ^ TE3[(size_t)wa3 & 0xFF]
+    uint wb0 = tables->te[0][(size_t)(wa0 >> 24)]
+        ^ tables->te[1][(size_t)(wa1 >> 16) & 0xFF]
+        ^ tables->te[2][(size_t)(wa2 >> 8) & 0xFF]
+        ^ tables->te[3][(size_t)wa3 & 0xFF]
         ^ keys[4];
-    uint wb1 = TE0[(size_t)(wa1 >> 24)]
-        ^ TE1[(size_t)(wa2 >> 16) & 0xFF]
-        ^ TE2[(size_t)(wa3 >> 8) & 0xFF]
-        ^ TE3[(size_t)wa0 & 0xFF]
+    uint wb1 = tables->te[0][(size_t)(wa1 >> 24)]
+        ^ tables->te[1][(size_t)(wa2 >> 16) & 0xFF]
+        ^ tables->te[2][(size_t)(wa3 >> 8) & 0xFF]
+        ^ tables->te[3][(size_t)wa0 & 0xFF]
         ^ keys[5];
-    uint wb2 = TE0[(size_t)(wa2 >> 24)]
-        ^ TE1[(size_t)(wa3 >> 16) & 0xFF]
-        ^ TE2[(size_t)(wa0 >> 8) & 0xFF]
-        ^ TE3[(size_t)wa1 & 0xFF]
+    uint wb2 = tables->te[0][(size_t)(wa2 >> 24)]
+        ^ tables->te[1][(size_t)(wa3 >> 16) & 0xFF]
+        ^ tables->te[2][(size_t)(wa0 >> 8) & 0xFF]
+        ^ tables->te[3][(size_t)wa1 & 0xFF]
         ^ keys[6];
-    uint wb3 = TE0[(size_t)(wa3 >> 24)]
-        ^ TE1[(size_t)(wa0 >> 16) & 0xFF]
-        ^ TE2[(size_t)(wa1 >> 8) & 0xFF]
-        ^ TE3[(size_t)wa2 & 0xFF]
+    uint wb3 = tables->te[0][(size_t)(wa3 >> 24)]
+        ^ tables->te[1][(size_t)(wa0 >> 16) & 0xFF]
+        ^ tables->te[2][(size_t)(wa1 >> 8) & 0xFF]
+        ^ tables->te[3][(size_t)wa2 & 0xFF]
         ^ keys[7];
     for (uint i = 1; i < 5; i++) {
-        wa0 = TE0[(size_t)(wb0 >> 24)]
-            ^ TE1[(size_t)(wb1 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wb2 >> 8) & 0xFF]
-            ^ TE3[(size_t)wb3 & 0xFF]
+        wa0 = tables->te[0][(size_t)(wb0 >> 24)]
+            ^ tables->te[1][(size_t)(wb1 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wb2 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wb3 & 0xFF]
             ^ keys[8 * i];
-        wa1 = TE0[(size_t)(wb1 >> 24)]
-            ^ TE1[(size_t)(wb2 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wb3 >> 8) & 0xFF]
-            ^ TE3[(size_t)wb0 & 0xFF]
+        wa1 = tables->te[0][(size_t)(wb1 >> 24)]
+            ^ tables->te[1][(size_t)(wb2 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wb3 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wb0 & 0xFF]
             ^ keys[8 * i + 1];
-        wa2 = TE0[(size_t)(wb2 >> 24)]
-            ^ TE1[(size_t)(wb3 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wb0 >> 8) & 0xFF]
-            ^ TE3[(size_t)wb1 & 0xFF]
+        wa2 = tables->te[0][(size_t)(wb2 >> 24)]
+            ^ tables->te[1][(size_t)(wb3 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wb0 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wb1 & 0xFF]
             ^ keys[8 * i + 2];
-        wa3 = TE0[(size_t)(wb3 >> 24)]
-            ^ TE1[(size_t)(wb0 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wb1 >> 8) & 0xFF]
-            ^ TE3[(size_t)wb2 & 0xFF]
+        wa3 = tables->te[0][(size_t)(wb3 >> 24)]
+            ^ tables->te[1][(size_t)(wb0 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wb1 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wb2 & 0xFF]
             ^ keys[8 * i + 3];
 
-        wb0 = TE0[(size_t)(wa0 >> 24)]
-            ^ TE1[(size_t)(wa1 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wa2 >> 8) & 0xFF]
-            ^ TE3[(size_t)wa3 & 0xFF]
+        wb0 = tables->te[0][(size_t)(wa0 >> 24)]
+            ^ tables->te[1][(size_t)(wa1 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wa2 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wa3 & 0xFF]
             ^ keys[8 * i + 4];
-        wb1 = TE0[(size_t)(wa1 >> 24)]
-            ^ TE1[(size_t)(wa2 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wa3 >> 8) & 0xFF]
-            ^ TE3[(size_t)wa0 & 0xFF]
+        wb1 = tables->te[0][(size_t)(wa1 >> 24)]
+            ^ tables->te[1][(size_t)(wa2 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wa3 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wa0 & 0xFF]
             ^ keys[8 * i + 5];
-        wb2 = TE0[(size_t)(wa2 >> 24)]
-            ^ TE1[(size_t)(wa3 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wa0 >> 8) & 0xFF]
-            ^ TE3[(size_t)wa1 & 0xFF]
+        wb2 = tables->te[0][(size_t)(wa2 >> 24)]
+            ^ tables->te[1][(size_t)(wa3 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wa0 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wa1 & 0xFF]
             ^ keys[8 * i + 6];
-        wb3 = TE0[(size_t)(wa3 >> 24)]
-            ^ TE1[(size_t)(wa0 >> 16) & 0xFF]
-            ^ TE2[(size_t)(wa1 >> 8) & 0xFF]
-            ^ TE3[(size_t)wa2 & 0xFF]
+        wb3 = tables->te[0][(size_t)(wa3 >> 24)]
+            ^ tables->te[1][(size_t)(wa0 >> 16) & 0xFF]
+            ^ tables->te[2][(size_t)(wa1 >> 8) & 0xFF]
+            ^ tables->te[3][(size_t)wa2 & 0xFF]
             ^ keys[8 * i + 7];
     }
-    (*state)[0] = SBOX[(size_t)(wb0 >> 24)] ^ (uchar)(keys[44 - 4] >> 24);
-    (*state)[1] = SBOX[(size_t)(wb1 >> 16) & 0xFF] ^ (uchar)(keys[44 - 4] >> 16);
-    (*state)[2] = SBOX[(size_t)(wb2 >> 8) & 0xFF] ^ (uchar)(keys[44 - 4] >> 8);
-    (*state)[3] = SBOX[(size_t)wb3 & 0xFF] ^ (uchar)keys[44 - 4];
-    (*state)[4] = SBOX[(size_t)(wb1 >> 24)] ^ (uchar)(keys[44 - 3] >> 24);
-    (*state)[5] = SBOX[(size_t)(wb2 >> 16) & 0xFF] ^ (uchar)(keys[44 - 3] >> 16);
-    (*state)[6] = SBOX[(size_t)(wb3 >> 8) & 0xFF] ^ (uchar)(keys[44 - 3] >> 8);
-    (*state)[7] = SBOX[(size_t)wb0 & 0xFF] ^ (uchar)keys[44 - 3];
-    (*state)[8] = SBOX[(size_t)(wb2 >> 24)] ^ (uchar)(keys[44 - 2] >> 24);
-    (*state)[9] = SBOX[(size_t)(wb3 >> 16) & 0xFF] ^ (uchar)(keys[44 - 2] >> 16);
-    (*state)[10] = SBOX[(size_t)(wb0 >> 8) & 0xFF] ^ (uchar)(keys[44 - 2] >> 8);
-    (*state)[11] = SBOX[(size_t)wb1 & 0xFF] ^ (uchar)keys[44 - 2];
-    (*state)[12] = SBOX[(size_t)(wb3 >> 24)] ^ (uchar)(keys[44 - 1] >> 24);
-    (*state)[13] = SBOX[(size_t)(wb0 >> 16) & 0xFF] ^ (uchar)(keys[44 - 1] >> 16);
-    (*state)[14] = SBOX[(size_t)(wb1 >> 8) & 0xFF] ^ (uchar)(keys[44 - 1] >> 8);
-    (*state)[15] = SBOX[(size_t)wb2 & 0xFF] ^ (uchar)keys[44 - 1];
+    (*state)[0] = tables->sbox[(size_t)(wb0 >> 24)] ^ (uchar)(keys[44 - 4] >> 24);
+    (*state)[1] = tables->sbox[(size_t)(wb1 >> 16) & 0xFF] ^ (uchar)(keys[44 - 4] >> 16);
+    (*state)[2] = tables->sbox[(size_t)(wb2 >> 8) & 0xFF] ^ (uchar)(keys[44 - 4] >> 8);
+    (*state)[3] = tables->sbox[(size_t)wb3 & 0xFF] ^ (uchar)keys[44 - 4];
+    (*state)[4] = tables->sbox[(size_t)(wb1 >> 24)] ^ (uchar)(keys[44 - 3] >> 24);
+    (*state)[5] = tables->sbox[(size_t)(wb2 >> 16) & 0xFF] ^ (uchar)(keys[44 - 3] >> 16);
+    (*state)[6] = tables->sbox[(size_t)(wb3 >> 8) & 0xFF] ^ (uchar)(keys[44 - 3] >> 8);
+    (*state)[7] = tables->sbox[(size_t)wb0 & 0xFF] ^ (uchar)keys[44 - 3];
+    (*state)[8] = tables->sbox[(size_t)(wb2 >> 24)] ^ (uchar)(keys[44 - 2] >> 24);
+    (*state)[9] = tables->sbox[(size_t)(wb3 >> 16) & 0xFF] ^ (uchar)(keys[44 - 2] >> 16);
+    (*state)[10] = tables->sbox[(size_t)(wb0 >> 8) & 0xFF] ^ (uchar)(keys[44 - 2] >> 8);
+    (*state)[11] = tables->sbox[(size_t)wb1 & 0xFF] ^ (uchar)keys[44 - 2];
+    (*state)[12] = tables->sbox[(size_t)(wb3 >> 24)] ^ (uchar)(keys[44 - 1] >> 24);
+    (*state)[13] = tables->sbox[(size_t)(wb0 >> 16) & 0xFF] ^ (uchar)(keys[44 - 1] >> 16);
+    (*state)[14] = tables->sbox[(size_t)(wb1 >> 8) & 0xFF] ^ (uchar)(keys[44 - 1] >> 8);
+    (*state)[15] = tables->sbox[(size_t)wb2 & 0xFF] ^ (uchar)keys[44 - 1];
 }
 
-inline void aes_128_dec(
+inline void aes_128_dec_tables(
 	__global uchar16* state,
-	__constant const uint* keys
+	__constant const uint* keys,
+	TABLES_SPACE const aes_tables_t* tables
 ) {
     uint wa0 = (
         ((uint)(*state)[0] << 24)
@@ -461,113 +484,460 @@ inline void aes_128_dec(
         ^ (uint)(*state)[15]
     ) ^ keys[44 - 1];
 
-    uint wb0 = TD0[(size_t)(wa0 >> 24)]
-        ^ TD1[(size_t)(wa3 >> 16) & 0xFF]
-        ^ TD2[(size_t)(wa2 >> 8) & 0xFF]
-        ^ TD3[(size_t)wa1 & 0xFF]
+    uint wb0 = tables->td[0][(size_t)(wa0 >> 24)]
+        ^ tables->td[1][(size_t)(wa3 >> 16) & 0xFF]
+        ^ tables->td[2][(size_t)(wa2 >> 8) & 0xFF]
+        ^ tables->td[3][(size_t)wa1 & 0xFF]
         ^ keys[44 - 8];
-    uint wb1 = TD0[(size_t)(wa1 >> 24)]
-        ^ TD1[(size_t)(wa0 >> 16) & 0xFF]
-        ^ TD2[(size_t)(wa3 >> 8) & 0xFF]
-        ^ TD3[(size_t)wa2 & 0xFF]
+    uint wb1 = tables->td[0][(size_t)(wa1 >> 24)]
+        ^ tables->td[1][(size_t)(wa0 >> 16) & 0xFF]
+        ^ tables->td[2][(size_t)(wa3 >> 8) & 0xFF]
+        ^ tables->td[3][(size_t)wa2 & 0xFF]
         ^ keys[44 - 7];
-    uint wb2 = TD0[(size_t)(wa2 >> 24)]
-        ^ TD1[(size_t)(wa1 >> 16) & 0xFF]
-        ^ TD2[(size_t)(wa0 >> 8) & 0xFF]
-        ^ TD3[(size_t)wa3 & 0xFF]
+    uint wb2 = tables->td[0][(size_t)(wa2 >> 24)]
+        ^ tables->td[1][(size_t)(wa1 >> 16) & 0xFF]
+        ^ tables->td[2][(size_t)(wa0 >> 8) & 0xFF]
+        ^ tables->td[3][(size_t)wa3 & 0xFF]
         ^ keys[44 - 6];
-    uint wb3 = TD0[(size_t)(wa3 >> 24)]
-        ^ TD1[(size_t)(wa2 >> 16) & 0xFF]
-        ^ TD2[(size_t)(wa1 >> 8) & 0xFF]
-        ^ TD3[(size_t)wa0 & 0xFF]
+    uint wb3 = tables->td[0][(size_t)(wa3 >> 24)]
+        ^ tables->td[1][(size_t)(wa2 >> 16) & 0xFF]
+        ^ tables->td[2][(size_t)(wa1 >> 8) & 0xFF]
+        ^ tables->td[3][(size_t)wa0 & 0xFF]
         ^ keys[44 - 5];
 
     for (uint i = 1; i < 5; i++) {
-        wa0 = TD0[(size_t)(wb0 >> 24)]
-            ^ TD1[(size_t)(wb3 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wb2 >> 8) & 0xFF]
-            ^ TD3[(size_t)wb1 & 0xFF]
+        wa0 = tables->td[0][(size_t)(wb0 >> 24)]
+            ^ tables->td[1][(size_t)(wb3 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wb2 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wb1 & 0xFF]
             ^ keys[44 - 4 - (8 * i)];
-        wa1 = TD0[(size_t)(wb1 >> 24)]
-            ^ TD1[(size_t)(wb0 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wb3 >> 8) & 0xFF]
-            ^ TD3[(size_t)wb2 & 0xFF]
+        wa1 = tables->td[0][(size_t)(wb1 >> 24)]
+            ^ tables->td[1][(size_t)(wb0 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wb3 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wb2 & 0xFF]
             ^ keys[44 - 3 - (8 * i)];
-        wa2 = TD0[(size_t)(wb2 >> 24)]
-            ^ TD1[(size_t)(wb1 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wb0 >> 8) & 0xFF]
-            ^ TD3[(size_t)wb3 & 0xFF]
+        wa2 = tables->td[0][(size_t)(wb2 >> 24)]
+            ^ tables->td[1][(size_t)(wb1 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wb0 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wb3 & 0xFF]
             ^ keys[44 - 2 - (8 * i)];
-        wa3 = TD0[(size_t)(wb3 >> 24)]
-            ^ TD1[(size_t)(wb2 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wb1 >> 8) & 0xFF]
-            ^ TD3[(size_t)wb0 & 0xFF]
+        wa3 = tables->td[0][(size_t)(wb3 >> 24)]
+            ^ tables->td[1][(size_t)(wb2 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wb1 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wb0 & 0xFF]
             ^ keys[44 - 1 - (8 * i)];
 
-        wb0 = TD0[(size_t)(wa0 >> 24)]
-            ^ TD1[(size_t)(wa3 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wa2 >> 8) & 0xFF]
-            ^ TD3[(size_t)wa1 & 0xFF]
+        wb0 = tables->td[0][(size_t)(wa0 >> 24)]
+            ^ tables->td[1][(size_t)(wa3 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wa2 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wa1 & 0xFF]
             ^ keys[44 - 8 - (8 * i)];
-        wb1 = TD0[(size_t)(wa1 >> 24)]
-            ^ TD1[(size_t)(wa0 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wa3 >> 8) & 0xFF]
-            ^ TD3[(size_t)wa2 & 0xFF]
+        wb1 = tables->td[0][(size_t)(wa1 >> 24)]
+            ^ tables->td[1][(size_t)(wa0 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wa3 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wa2 & 0xFF]
             ^ keys[44 - 7 - (8 * i)];
-        wb2 = TD0[(size_t)(wa2 >> 24)]
-            ^ TD1[(size_t)(wa1 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wa0 >> 8) & 0xFF]
-            ^ TD3[(size_t)wa3 & 0xFF]
+        wb2 = tables->td[0][(size_t)(wa2 >> 24)]
+            ^ tables->td[1][(size_t)(wa1 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wa0 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wa3 & 0xFF]
             ^ keys[44 - 6 - (8 * i)];
-        wb3 = TD0[(size_t)(wa3 >> 24)]
-            ^ TD1[(size_t)(wa2 >> 16) & 0xFF]
-            ^ TD2[(size_t)(wa1 >> 8) & 0xFF]
-            ^ TD3[(size_t)wa0 & 0xFF]
+        wb3 = tables->td[0][(size_t)(wa3 >> 24)]
+            ^ tables->td[1][(size_t)(wa2 >> 16) & 0xFF]
+            ^ tables->td[2][(size_t)(wa1 >> 8) & 0xFF]
+            ^ tables->td[3][(size_t)wa0 & 0xFF]
             ^ keys[44 - 5 - (8 * i)];
     }
 
-    (*state)[0] = SINV[(size_t)(wb0 >> 24)] ^ (uchar)(keys[0] >> 24);
-    (*state)[1] = SINV[(size_t)(wb3 >> 16) & 0xFF] ^ (uchar)(keys[0] >> 16);
-    (*state)[2] = SINV[(size_t)(wb2 >> 8) & 0xFF] ^ (uchar)(keys[0] >> 8);
-    (*state)[3] = SINV[(size_t)wb1 & 0xFF] ^ (uchar)keys[0];
-    (*state)[4] = SINV[(size_t)(wb1 >> 24)] ^ (uchar)(keys[1] >> 24);
-    (*state)[5] = SINV[(size_t)(wb0 >> 16) & 0xFF] ^ (uchar)(keys[1] >> 16);
-    (*state)[6] = SINV[(size_t)(wb3 >> 8) & 0xFF] ^ (uchar)(keys[1] >> 8);
-    (*state)[7] = SINV[(size_t)wb2 & 0xFF] ^ (uchar)keys[1];
-    (*state)[8] = SINV[(size_t)(wb2 >> 24)] ^ (uchar)(keys[2] >> 24);
-    (*state)[9] = SINV[(size_t)(wb1 >> 16) & 0xFF] ^ (uchar)(keys[2] >> 16);
-    (*state)[10] = SINV[(size_t)(wb0 >> 8) & 0xFF] ^ (uchar)(keys[2] >> 8);
-    (*state)[11] = SINV[(size_t)wb3 & 0xFF] ^ (uchar)keys[2];
-    (*state)[12] = SINV[(size_t)(wb3 >> 24)] ^ (uchar)(keys[3] >> 24);
-    (*state)[13] = SINV[(size_t)(wb2 >> 16) & 0xFF] ^ (uchar)(keys[3] >> 16);
-    (*state)[14] = SINV[(size_t)(wb1 >> 8) & 0xFF] ^ (uchar)(keys[3] >> 8);
-    (*state)[15] = SINV[(size_t)wb0 & 0xFF] ^ (uchar)keys[3];
+    (*state)[0] = tables->sinv[(size_t)(wb0 >> 24)] ^ (uchar)(keys[0] >> 24);
+    (*state)[1] = tables->sinv[(size_t)(wb3 >> 16) & 0xFF] ^ (uchar)(keys[0] >> 16);
+    (*state)[2] = tables->sinv[(size_t)(wb2 >> 8) & 0xFF] ^ (uchar)(keys[0] >> 8);
+    (*state)[3] = tables->sinv[(size_t)wb1 & 0xFF] ^ (uchar)keys[0];
+    (*state)[4] = tables->sinv[(size_t)(wb1 >> 24)] ^ (uchar)(keys[1] >> 24);
+    (*state)[5] = tables->sinv[(size_t)(wb0 >> 16) & 0xFF] ^ (uchar)(keys[1] >> 16);
+    (*state)[6] = tables->sinv[(size_t)(wb3 >> 8) & 0xFF] ^ (uchar)(keys[1] >> 8);
+    (*state)[7] = tables->sinv[(size_t)wb2 & 0xFF] ^ (uchar)keys[1];
+    (*state)[8] = tables->sinv[(size_t)(wb2 >> 24)] ^ (uchar)(keys[2] >> 24);
+    (*state)[9] = tables->sinv[(size_t)(wb1 >> 16) & 0xFF] ^ (uchar)(keys[2] >> 16);
+    (*state)[10] = tables->sinv[(size_t)(wb0 >> 8) & 0xFF] ^ (uchar)(keys[2] >> 8);
+    (*state)[11] = tables->sinv[(size_t)wb3 & 0xFF] ^ (uchar)keys[2];
+    (*state)[12] = tables->sinv[(size_t)(wb3 >> 24)] ^ (uchar)(keys[3] >> 24);
+    (*state)[13] = tables->sinv[(size_t)(wb2 >> 16) & 0xFF] ^ (uchar)(keys[3] >> 16);
+    (*state)[14] = tables->sinv[(size_t)(wb1 >> 8) & 0xFF] ^ (uchar)(keys[3] >> 8);
+    (*state)[15] = tables->sinv[(size_t)wb0 & 0xFF] ^ (uchar)keys[3];
 }
 
+// Bitsliced AES operates on a single block represented as 8 planes of 16 bits, bit `b` of plane
+// `j` is bit `j` of byte `b` of the block, which allows to compute S-Box without any lookups
+inline ushort8 bitslice_block(const uchar16 block) {
+    uchar bytes[16];
+    vstore16(block, 0, bytes);
+
+    ushort8 planes = (ushort8)(0);
+    for (uint b = 0; b < 16; ++b) {
+        ushort8 bits = ((ushort8)(bytes[b]) >> (ushort8)(0, 1, 2, 3, 4, 5, 6, 7)) & (ushort8)(1);
+        planes |= bits << (ushort8)(b);
+    }
+
+    return planes;
+}
+
+inline uchar16 unbitslice_block(const ushort8 planes) {
+    uchar bytes[16];
+    for (uint b = 0; b < 16; ++b) {
+        ushort8 bits = ((planes >> (ushort8)(b)) & (ushort8)(1)) << (ushort8)(0, 1, 2, 3, 4, 5, 6, 7);
+        bytes[b] = (uchar)(bits.s0 | bits.s1 | bits.s2 | bits.s3 | bits.s4 | bits.s5 | bits.s6 | bits.s7);
+    }
+
+    return vload16(0, bytes);
+}
+
+// Round keys are stored as big-endian words, the same way as for table-based implementation
+inline ushort8 bitslice_round_key(__constant const uint* keys) {
+    uchar bytes[16];
+    for (uint b = 0; b < 16; ++b) {
+        bytes[b] = (uchar)(keys[b / 4] >> (24 - 8 * (b % 4)));
+    }
+
+    return bitslice_block(vload16(0, bytes));
+}
+
+// S-Box circuit by Joan Boyar and René Peralta, "A depth-16 circuit for the AES S-box"
+inline ushort8 sub_bytes_bitsliced(const ushort8 planes) {
+    ushort U0 = planes.s7;
+    ushort U1 = planes.s6;
+    ushort U2 = planes.s5;
+    ushort U3 = planes.s4;
+    ushort U4 = planes.s3;
+    ushort U5 = planes.s2;
+    ushort U6 = planes.s1;
+    ushort U7 = planes.s0;
+
+    ushort T1 = U0 ^ U3;
+    ushort T2 = U0 ^ U5;
+    ushort T3 = U0 ^ U6;
+    ushort T4 = U3 ^ U5;
+    ushort T5 = U4 ^ U6;
+    ushort T6 = T1 ^ T5;
+    ushort T7 = U1 ^ U2;
+    ushort T8 = U7 ^ T6;
+    ushort T9 = U7 ^ T7;
+    ushort T10 = T6 ^ T7;
+    ushort T11 = U1 ^ U5;
+    ushort T12 = U2 ^ U5;
+    ushort T13 = T3 ^ T4;
+    ushort T14 = T6 ^ T11;
+    ushort T15 = T5 ^ T11;
+    ushort T16 = T5 ^ T12;
+    ushort T17 = T9 ^ T16;
+    ushort T18 = U3 ^ U7;
+    ushort T19 = T7 ^ T18;
+    ushort T20 = T1 ^ T19;
+    ushort T21 = U6 ^ U7;
+    ushort T22 = T7 ^ T21;
+    ushort T23 = T2 ^ T22;
+    ushort T24 = T2 ^ T10;
+    ushort T25 = T20 ^ T17;
+    ushort T26 = T3 ^ T16;
+    ushort T27 = T1 ^ T12;
+
+    ushort M1 = T13 & T6;
+    ushort M2 = T23 & T8;
+    ushort M3 = T14 ^ M1;
+    ushort M4 = T19 & U7;
+    ushort M5 = M4 ^ M1;
+    ushort M6 = T3 & T16;
+    ushort M7 = T22 & T9;
+    ushort M8 = T26 ^ M6;
+    ushort M9 = T20 & T17;
+    ushort M10 = M9 ^ M6;
+    ushort M11 = T1 & T15;
+    ushort M12 = T4 & T27;
+    ushort M13 = M12 ^ M11;
+    ushort M14 = T2 & T10;
+    ushort M15 = M14 ^ M11;
+    ushort M16 = M3 ^ M2;
+    ushort M17 = M5 ^ T24;
+    ushort M18 = M8 ^ M7;
+    ushort M19 = M10 ^ M15;
+    ushort M20 = M16 ^ M13;
+    ushort M21 = M17 ^ M15;
+    ushort M22 = M18 ^ M13;
+    ushort M23 = M19 ^ T25;
+    ushort M24 = M22 ^ M23;
+    ushort M25 = M22 & M20;
+    ushort M26 = M21 ^ M25;
+    ushort M27 = M20 ^ M21;
+    ushort M28 = M23 ^ M25;
+    ushort M29 = M28 & M27;
+    ushort M30 = M26 & M24;
+    ushort M31 = M20 & M23;
+    ushort M32 = M27 & M31;
+    ushort M33 = M27 ^ M25;
+    ushort M34 = M21 & M22;
+    ushort M35 = M24 & M34;
+    ushort M36 = M24 ^ M25;
+    ushort M37 = M21 ^ M29;
+    ushort M38 = M32 ^ M33;
+    ushort M39 = M23 ^ M30;
+    ushort M40 = M35 ^ M36;
+    ushort M41 = M38 ^ M40;
+    ushort M42 = M37 ^ M39;
+    ushort M43 = M37 ^ M38;
+    ushort M44 = M39 ^ M40;
+    ushort M45 = M42 ^ M41;
+    ushort M46 = M44 & T6;
+    ushort M47 = M40 & T8;
+    ushort M48 = M39 & U7;
+    ushort M49 = M43 & T16;
+    ushort M50 = M38 & T9;
+    ushort M51 = M37 & T17;
+    ushort M52 = M42 & T15;
+    ushort M53 = M45 & T27;
+    ushort M54 = M41 & T10;
+    ushort M55 = M44 & T13;
+    ushort M56 = M40 & T23;
+    ushort M57 = M39 & T19;
+    ushort M58 = M43 & T3;
+    ushort M59 = M38 & T22;
+    ushort M60 = M37 & T20;
+    ushort M61 = M42 & T1;
+    ushort M62 = M45 & T4;
+    ushort M63 = M41 & T2;
+
+    ushort L0 = M61 ^ M62;
+    ushort L1 = M50 ^ M56;
+    ushort L2 = M46 ^ M48;
+    ushort L3 = M47 ^ M55;
+    ushort L4 = M54 ^ M58;
+    ushort L5 = M49 ^ M61;
+    ushort L6 = M62 ^ L5;
+    ushort L7 = M46 ^ L3;
+    ushort L8 = M51 ^ M59;
+    ushort L9 = M52 ^ M53;
+    ushort L10 = M53 ^ L4;
+    ushort L11 = M60 ^ L2;
+    ushort L12 = M48 ^ M51;
+    ushort L13 = M50 ^ L0;
+    ushort L14 = M52 ^ M61;
+    ushort L15 = M55 ^ L1;
+    ushort L16 = M56 ^ L0;
+    ushort L17 = M57 ^ L1;
+    ushort L18 = M58 ^ L8;
+    ushort L19 = M63 ^ L4;
+    ushort L20 = L0 ^ L1;
+    ushort L21 = L1 ^ L7;
+    ushort L22 = L3 ^ L12;
+    ushort L23 = L18 ^ L2;
+    ushort L24 = L15 ^ L9;
+    ushort L25 = L6 ^ L10;
+    ushort L26 = L7 ^ L9;
+    ushort L27 = L8 ^ L10;
+    ushort L28 = L11 ^ L14;
+    ushort L29 = L11 ^ L17;
+
+    return (ushort8)(
+        (ushort)~(L6 ^ L23),
+        (ushort)~(L13 ^ L27),
+        L25 ^ L29,
+        L20 ^ L22,
+        L6 ^ L21,
+        (ushort)~(L19 ^ L28),
+        (ushort)~(L16 ^ L26),
+        L6 ^ L24
+    );
+}
+
+// Inverse affine transformation of S-Box without the constant
+inline ushort8 inv_affine_bitsliced(const ushort8 planes) {
+    return planes.s23456701 ^ planes.s56701234 ^ planes.s70123456;
+}
+
+// Inverse S-Box is expressed through S-Box as `A⁻¹(S(A⁻¹(x) ^ 0x05)) ^ 0x05`, where `A⁻¹` is
+// inverse affine transformation without the constant
+inline ushort8 inv_sub_bytes_bitsliced(const ushort8 planes) {
+    const ushort8 constant = (ushort8)(0xFFFF, 0, 0xFFFF, 0, 0, 0, 0, 0);
+
+    return inv_affine_bitsliced(sub_bytes_bitsliced(inv_affine_bitsliced(planes) ^ constant))
+        ^ constant;
+}
+
+inline ushort8 shift_rows_bitsliced(const ushort8 planes) {
+    return (planes & (ushort8)(0x1111))
+        | (rotate(planes, (ushort8)(12)) & (ushort8)(0x2222))
+        | (rotate(planes, (ushort8)(8)) & (ushort8)(0x4444))
+        | (rotate(planes, (ushort8)(4)) & (ushort8)(0x8888));
+}
+
+inline ushort8 inv_shift_rows_bitsliced(const ushort8 planes) {
+    return (planes & (ushort8)(0x1111))
+        | (rotate(planes, (ushort8)(4)) & (ushort8)(0x2222))
+        | (rotate(planes, (ushort8)(8)) & (ushort8)(0x4444))
+        | (rotate(planes, (ushort8)(12)) & (ushort8)(0x8888));
+}
+
+// Rotates rows within each column, such that row `r` gets value of row `r + 1`
+inline ushort8 rotate_rows_1(const ushort8 planes) {
+    return ((planes >> (ushort8)(1)) & (ushort8)(0x7777)) | ((planes << (ushort8)(3)) & (ushort8)(0x8888));
+}
+
+// Rotates rows within each column, such that row `r` gets value of row `r + 2`
+inline ushort8 rotate_rows_2(const ushort8 planes) {
+    return ((planes >> (ushort8)(2)) & (ushort8)(0x3333)) | ((planes << (ushort8)(2)) & (ushort8)(0xCCCC));
+}
+
+// Multiplication by `x` in GF(2^8)
+inline ushort8 xtime_bitsliced(const ushort8 planes) {
+    return (ushort8)(
+        planes.s7,
+        planes.s0 ^ planes.s7,
+        planes.s1,
+        planes.s2 ^ planes.s7,
+        planes.s3 ^ planes.s7,
+        planes.s4,
+        planes.s5,
+        planes.s6
+    );
+}
+
+inline ushort8 mix_columns_bitsliced(const ushort8 planes) {
+    ushort8 rotated_1 = rotate_rows_1(planes);
+    ushort8 rotated_2 = rotate_rows_2(planes);
+    ushort8 rotated_3 = rotate_rows_1(rotated_2);
+
+    return xtime_bitsliced(planes ^ rotated_1) ^ rotated_1 ^ rotated_2 ^ rotated_3;
+}
+
+inline ushort8 inv_mix_columns_bitsliced(const ushort8 planes) {
+    ushort8 correction = xtime_bitsliced(xtime_bitsliced(planes ^ rotate_rows_2(planes)));
+
+    return mix_columns_bitsliced(planes ^ correction);
+}
+
+inline uchar16 aes_128_enc_bitsliced(
+	const uchar16 block,
+	const ushort8* keys,
+	const uint aes_iterations
+) {
+    ushort8 state = bitslice_block(block);
+
+    for (uint i = 0; i < aes_iterations; ++i) {
+        state ^= keys[0];
+        for (uint round = 1; round < 10; ++round) {
+            state = mix_columns_bitsliced(shift_rows_bitsliced(sub_bytes_bitsliced(state))) ^ keys[round];
+        }
+        state = shift_rows_bitsliced(sub_bytes_bitsliced(state)) ^ keys[10];
+    }
+
+    return unbitslice_block(state);
+}
+
+// Uses equivalent inverse cipher, such that the same decryption round keys can be used as for
+// table-based implementation
+inline uchar16 aes_128_dec_bitsliced(
+	const uchar16 block,
+	const ushort8* keys,
+	const uint aes_iterations
+) {
+    ushort8 state = bitslice_block(block);
+
+    for (uint i = 0; i < aes_iterations; ++i) {
+        state ^= keys[10];
+        for (uint round = 9; round > 0; --round) {
+            state = inv_mix_columns_bitsliced(inv_shift_rows_bitsliced(inv_sub_bytes_bitsliced(state))) ^ keys[round];
+        }
+        state = inv_shift_rows_bitsliced(inv_sub_bytes_bitsliced(state)) ^ keys[0];
+    }
+
+    return unbitslice_block(state);
+}
+
+#if defined(AES_STRATEGY_BITSLICED)
+typedef struct {
+    ushort8 round_keys[11];
+} aes_context_t;
+
+// Lookup tables are not used by bitsliced implementation
+inline void aes_context_init(
+	aes_context_t* context,
+	__constant const uint* keys,
+	TABLES_SPACE const aes_tables_t* tables
+) {
+    for (uint round = 0; round < 11; ++round) {
+        context->round_keys[round] = bitslice_round_key(&keys[round * 4]);
+    }
+}
+
+inline void aes_128_enc(__global uchar16* state, const aes_context_t* context, const uint aes_iterations) {
+    *state = aes_128_enc_bitsliced(*state, context->round_keys, aes_iterations);
+}
+
+inline void aes_128_dec(__global uchar16* state, const aes_context_t* context, const uint aes_iterations) {
+    *state = aes_128_dec_bitsliced(*state, context->round_keys, aes_iterations);
+}
+#else
+typedef struct {
+    __constant const uint* round_keys;
+    TABLES_SPACE const aes_tables_t* tables;
+} aes_context_t;
+
+inline void aes_context_init(
+	aes_context_t* context,
+	__constant const uint* keys,
+	TABLES_SPACE const aes_tables_t* tables
+) {
+    context->round_keys = keys;
+    context->tables = tables;
+}
+
+inline void aes_128_enc(__global uchar16* state, const aes_context_t* context, const uint aes_iterations) {
+    for (uint i = 0; i < aes_iterations; ++i) {
+        aes_128_enc_tables(state, context->round_keys, context->tables);
+    }
+}
+
+inline void aes_128_dec(__global uchar16* state, const aes_context_t* context, const uint aes_iterations) {
+    for (uint i = 0; i < aes_iterations; ++i) {
+        aes_128_dec_tables(state, context->round_keys, context->tables);
+    }
+}
+#endif
+
+#if defined(AES_STRATEGY_LOCAL_TABLES)
+// Copies lookup tables into local memory cooperatively by all work items of the work group
+inline void aes_tables_to_local(__local aes_tables_t* local_tables) {
+    __constant const uint* source = (__constant const uint*)&AES_TABLES;
+    __local uint* destination = (__local uint*)local_tables;
+
+    for (uint i = get_local_id(0); i < sizeof(aes_tables_t) / sizeof(uint); i += get_local_size(0)) {
+        destination[i] = source[i];
+    }
+
+    barrier(CLK_LOCAL_MEM_FENCE);
+}
+#endif
+
 __constant uint blocks_per_piece = 4096 / 16;
+__constant uint round_keys_length_128 = 44;
 
 inline uchar16 por_128_enc_inner(
 	__global uchar16* state,
 	const uchar16 iv,
-	__constant const uint* keys,
+	const aes_context_t* context,
 	const uint aes_iterations
 ) {
     // XOR the first block with IV
     state[0] ^= iv;
 
     // Apply Rijndael cipher to the first block necessary number or times
-    for (uint r = 0; r < aes_iterations; ++r) {
-        aes_128_enc(state, keys);
-    }
+    aes_128_enc(state, context, aes_iterations);
 
     for (uint block = 1; block < blocks_per_piece; ++block) {
         // XOR feedback into next current block
         state[block] ^= state[block - 1];
 
         // Apply Rijndael cipher to each block necessary number or times
-        for (uint r = 0; r < aes_iterations; ++r) {
-            aes_128_enc(&state[block], keys);
-        }
+        aes_128_enc(&state[block], context, aes_iterations);
     }
 
     return state[blocks_per_piece - 1];
@@ -577,35 +947,44 @@ __kernel void por_128_enc(
 	__global uchar16* state,
 	__global uchar16* iv,
 	__constant const uint* keys,
+	__global const uint* key_indices,
 	const uint aes_iterations,
 	const uint breadth_iterations
 ) {
     uint gid = get_global_id(0);
 
+#if defined(AES_STRATEGY_LOCAL_TABLES)
+    __local aes_tables_t local_tables;
+    aes_tables_to_local(&local_tables);
+    TABLES_SPACE const aes_tables_t* tables = &local_tables;
+#else
+    TABLES_SPACE const aes_tables_t* tables = &AES_TABLES;
+#endif
+
+    // Each piece can be encoded with its own set of round keys
+    aes_context_t context;
+    aes_context_init(&context, &keys[key_indices[gid] * round_keys_length_128], tables);
+
     for (uint i = 0; i < breadth_iterations; ++i) {
-        iv[gid] = por_128_enc_inner(&state[gid * blocks_per_piece], iv[gid], keys, aes_iterations);
+        iv[gid] = por_128_enc_inner(&state[gid * blocks_per_piece], iv[gid], &context, aes_iterations);
     }
 }
 
 inline void por_128_dec_inner(
 	__global uchar16* state,
-	__constant const uint* keys,
+	const aes_context_t* context,
 	const uint aes_iterations
 ) {
     for (uint block = blocks_per_piece - 1; block > 0; --block) {
         // Apply Rijndael decipher to each block necessary number or times
-        for (uint r = 0; r < aes_iterations; ++r) {
-            aes_128_dec(&state[block], keys);
-        }
+        aes_128_dec(&state[block], context, aes_iterations);
 
         // XOR feedback into next current block
         state[block] ^= state[block - 1];
     }
 
     // Apply Rijndael decipher to the first block necessary number or times
-    for (uint r = 0; r < aes_iterations; ++r) {
-        aes_128_dec(state, keys);
-    }
+    aes_128_dec(state, context, aes_iterations);
 
     // XOR the first block with last block, which is decoded at this point
     state[0] ^= state[blocks_per_piece - 1];
@@ -614,23 +993,19 @@ inline void por_128_dec_inner(
 inline void por_128_dec_inner_last(
 	__global uchar16* state,
 	const uchar16 iv,
-	__constant const uint* keys,
+	const aes_context_t* context,
 	const uint aes_iterations
 ) {
     for (uint block = blocks_per_piece - 1; block > 0; --block) {
         // Apply Rijndael decipher to each block necessary number or times
-        for (uint r = 0; r < aes_iterations; ++r) {
-            aes_128_dec(&state[block], keys);
-        }
+        aes_128_dec(&state[block], context, aes_iterations);
 
         // XOR feedback into next current block
         state[block] ^= state[block - 1];
     }
 
     // Apply Rijndael decipher to the first block necessary number or times
-    for (uint r = 0; r < aes_iterations; ++r) {
-        aes_128_dec(state, keys);
-    }
+    aes_128_dec(state, context, aes_iterations);
 
     // XOR the first block with IV
     state[0] ^= iv;
@@ -640,14 +1015,27 @@ __kernel void por_128_dec(
 	__global uchar16* state,
 	__global uchar16* iv,
 	__constant const uint* keys,
+	__global const uint* key_indices,
     const uint aes_iterations,
     const uint breadth_iterations
 ) {
     uint gid = get_global_id(0);
 
+#if defined(AES_STRATEGY_LOCAL_TABLES)
+    __local aes_tables_t local_tables;
+    aes_tables_to_local(&local_tables);
+    TABLES_SPACE const aes_tables_t* tables = &local_tables;
+#else
+    TABLES_SPACE const aes_tables_t* tables = &AES_TABLES;
+#endif
+
+    // Each piece can be decoded with its own set of round keys
+    aes_context_t context;
+    aes_context_init(&context, &keys[key_indices[gid] * round_keys_length_128], tables);
+
     for (uint i = 1; i < breadth_iterations; ++i) {
-        por_128_dec_inner(&state[gid * blocks_per_piece], keys, aes_iterations);
+        por_128_dec_inner(&state[gid * blocks_per_piece], &context, aes_iterations);
     }
 
-    por_128_dec_inner_last(&state[gid * blocks_per_piece], iv[gid], keys, aes_iterations);
+    por_128_dec_inner_last(&state[gid * blocks_per_piece], iv[gid], &context, aes_iterations);
 }
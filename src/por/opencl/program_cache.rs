@@ -0,0 +1,116 @@
+use ocl::core::DeviceInfo;
+use ocl::{Device, Platform, Result};
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// Makes names of temporary files unique within the process
+static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Builds a key that identifies compiled program binary, any change in platform, device, driver,
+/// build options or kernel source results in a different key
+pub fn cache_key(
+    platform: Platform,
+    device: Device,
+    source: &str,
+    build_options: &str,
+) -> Result<String> {
+    Ok(format!(
+        "platform: {}\nplatform version: {}\ndevice: {}\ndevice vendor: {}\ndevice version: {}\n\
+        driver version: {}\nbuild options: {}\nsource: {:016x}",
+        platform.name()?,
+        platform.version()?,
+        device.name()?,
+        device.vendor()?,
+        device.info(DeviceInfo::Version)?,
+        device.info(DeviceInfo::DriverVersion)?,
+        build_options,
+        fnv1a_64(source.as_bytes()),
+    ))
+}
+
+/// Returns cached program binary for given key if present in cache directory
+pub fn load(cache_dir: &Path, key: &str) -> Option<Vec<u8>> {
+    let contents = fs::read(cache_file(cache_dir, key)).ok()?;
+
+    // Key is stored alongside the binary to guard against file name collisions
+    if contents.len() < 4 {
+        return None;
+    }
+    let (key_length, contents) = contents.split_at(4);
+    let key_length = u32::from_le_bytes(key_length.try_into().unwrap()) as usize;
+    if contents.len() < key_length || &contents[..key_length] != key.as_bytes() {
+        return None;
+    }
+
+    Some(contents[key_length..].to_vec())
+}
+
+/// Stores program binary for given key in cache directory, replacing previous binary atomically
+pub fn store(cache_dir: &Path, key: &str, binary: &[u8]) -> io::Result<()> {
+    fs::create_dir_all(cache_dir)?;
+
+    let mut contents = Vec::with_capacity(4 + key.len() + binary.len());
+    contents.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    contents.extend_from_slice(key.as_bytes());
+    contents.extend_from_slice(binary);
+
+    let cache_file = cache_file(cache_dir, key);
+    // Unique name, so that processes and threads sharing cache directory don't write into the same
+    // temporary file
+    let tmp_file = cache_file.with_extension(format!(
+        "{}.{}.tmp",
+        process::id(),
+        TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+    fs::write(&tmp_file, contents)?;
+    fs::rename(tmp_file, cache_file)
+}
+
+fn cache_file(cache_dir: &Path, key: &str) -> PathBuf {
+    cache_dir.join(format!("{:016x}.bin", fnv1a_64(key.as_bytes())))
+}
+
+/// Simple hash function that is stable across Rust versions, which is not the case for
+/// `DefaultHasher`
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::env;
+
+    #[test]
+    fn test_store_load() {
+        let cache_dir = env::temp_dir().join(format!(
+            "rust-aes-proofs-program-cache-{:016x}",
+            rand::thread_rng().gen::<u64>(),
+        ));
+
+        assert_eq!(load(&cache_dir, "key"), None);
+
+        store(&cache_dir, "key", b"binary").unwrap();
+        assert_eq!(load(&cache_dir, "key"), Some(b"binary".to_vec()));
+        assert_eq!(load(&cache_dir, "another key"), None);
+
+        store(&cache_dir, "key", b"another binary").unwrap();
+        assert_eq!(load(&cache_dir, "key"), Some(b"another binary".to_vec()));
+        // Temporary files don't stay behind
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+        fs::write(cache_file(&cache_dir, "key"), b"corrupted").unwrap();
+        assert_eq!(load(&cache_dir, "key"), None);
+
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! Dependency cone of decoding a range of blocks inside a piece.
+//!
+//! Every encoding iteration over the width of the piece (breadth iteration) XORs each block with
+//! the previous encoded block before encrypting it, the first block uses IV on the first
+//! iteration and the last block of the previous iteration on subsequent iterations. As a result
+//! decoding block `i` of layer `n` (layer 0 is plaintext, layer `breadth_iterations` is the
+//! encoded piece) only needs blocks `i` and `i - 1` of layer `n + 1`, while block 0 additionally
+//! needs the last block of layer `n` itself.
+//!
+//! With `breadth_iterations = 1` a range of blocks is decoded from itself and one preceding
+//! block. Every additional breadth iteration extends the range by one block to the left and once
+//! the range reaches the beginning of the piece, a growing range at the end of the piece becomes
+//! necessary too, so the cone covers the whole piece after roughly `PIECE_SIZE / BLOCK_SIZE`
+//! breadth iterations.
+
+use crate::BLOCK_SIZE;
+use crate::PIECE_SIZE;
+use core::ops::Range;
+
+pub(crate) const BLOCKS: usize = PIECE_SIZE / BLOCK_SIZE;
+
+/// Blocks of one layer, at most two disjoint ranges
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlockRanges {
+    /// Range that contains requested blocks
+    pub head: Range<usize>,
+    /// Range at the end of the piece needed for feedback of the first block, empty if not needed
+    pub tail: Range<usize>,
+}
+
+impl BlockRanges {
+    /// Number of blocks
+    pub fn len(&self) -> usize {
+        self.head.len() + self.tail.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.head.contains(&index) || self.tail.contains(&index)
+    }
+
+    /// Adds the last block if the first block is included, since it is feedback of the first
+    /// block within the same layer
+    fn with_feedback(mut self) -> Self {
+        if self.head.start == 0 && !self.head.is_empty() && !self.contains(BLOCKS - 1) {
+            if self.head.end == BLOCKS - 1 {
+                self.head.end = BLOCKS;
+            } else {
+                self.tail = (BLOCKS - 1)..BLOCKS;
+            }
+        }
+        self
+    }
+
+    /// Blocks of the next layer needed to decode these blocks
+    fn dependencies(&self) -> Self {
+        let extend = |range: &Range<usize>| {
+            if range.is_empty() {
+                BLOCKS..BLOCKS
+            } else {
+                range.start.saturating_sub(1)..range.end
+            }
+        };
+        let head = extend(&self.head);
+        let tail = extend(&self.tail);
+        if !tail.is_empty() && head.end >= tail.start {
+            Self {
+                head: head.start..tail.end,
+                tail: BLOCKS..BLOCKS,
+            }
+        } else {
+            Self { head, tail }
+        }
+    }
+}
+
+/// Blocks of every layer needed to decode a range of blocks of a piece
+#[derive(Debug, Clone)]
+pub struct DependencyCone {
+    block_range: Range<usize>,
+    breadth_iterations: usize,
+}
+
+impl DependencyCone {
+    /// Panics if block range is outside of the piece or `breadth_iterations` is 0
+    pub fn new(block_range: Range<usize>, breadth_iterations: usize) -> Self {
+        assert!(block_range.start <= block_range.end && block_range.end <= BLOCKS);
+        assert!(breadth_iterations > 0);
+
+        Self {
+            block_range,
+            breadth_iterations,
+        }
+    }
+
+    /// Blocks of layer `layer` that need to be known (layer 0 is plaintext and layer
+    /// `breadth_iterations` is the encoded piece)
+    pub fn layer(&self, layer: usize) -> BlockRanges {
+        assert!(layer <= self.breadth_iterations);
+
+        let mut ranges = BlockRanges {
+            head: self.block_range.clone(),
+            tail: BLOCKS..BLOCKS,
+        };
+        for current in 1..=layer {
+            ranges = ranges.dependencies();
+            // Layer 0 is decoded using IV and encoded piece is only read
+            if current < self.breadth_iterations {
+                ranges = ranges.with_feedback();
+            }
+        }
+        ranges
+    }
+
+    /// Blocks of the encoded piece that need to be read
+    pub fn encoded_blocks(&self) -> BlockRanges {
+        self.layer(self.breadth_iterations)
+    }
+
+    /// Total number of block decodings across all breadth iterations, compare with
+    /// `breadth_iterations * PIECE_SIZE / BLOCK_SIZE` for full piece decoding
+    pub fn decoded_blocks(&self) -> usize {
+        (0..self.breadth_iterations)
+            .map(|layer| self.layer(layer).len())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::aes_ni::AesNi;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::aes_ni::AesNiKeys;
+    use crate::por::software_bit_slicing::SoftwareBitSlicing;
+    use crate::por::software_bit_slicing::SoftwareBitSlicingKeys;
+    use crate::por::software_fix_slicing::SoftwareFixSlicing;
+    use crate::por::software_fix_slicing::SoftwareFixSlicingKeys;
+    use crate::por::software_lut::SoftwareLuT;
+    use crate::por::software_lut::SoftwareLuTKeys;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::vaes::VAes;
+    #[cfg(target_arch = "x86_64")]
+    use crate::por::vaes::VAesKeys;
+    use crate::round_keys::RoundKeys;
+    use crate::Piece;
+
+    const AES_ITERATIONS: usize = 16;
+
+    fn ranges(head: Range<usize>, tail: Range<usize>) -> BlockRanges {
+        BlockRanges { head, tail }
+    }
+
+    #[test]
+    fn test_cone() {
+        let cone = DependencyCone::new(10..20, 1);
+        assert_eq!(cone.layer(0), ranges(10..20, BLOCKS..BLOCKS));
+        assert_eq!(cone.encoded_blocks(), ranges(9..20, BLOCKS..BLOCKS));
+        assert_eq!(cone.decoded_blocks(), 10);
+
+        let cone = DependencyCone::new(0..5, 1);
+        assert_eq!(cone.encoded_blocks(), ranges(0..5, BLOCKS..BLOCKS));
+
+        let cone = DependencyCone::new(0..5, 3);
+        assert_eq!(cone.layer(1), ranges(0..5, 255..256));
+        assert_eq!(cone.layer(2), ranges(0..5, 254..256));
+        assert_eq!(cone.encoded_blocks(), ranges(0..5, 253..256));
+        assert_eq!(cone.decoded_blocks(), 5 + 6 + 7);
+
+        let cone = DependencyCone::new(3..4, 3);
+        assert_eq!(cone.layer(2), ranges(1..4, BLOCKS..BLOCKS));
+        assert_eq!(cone.encoded_blocks(), ranges(0..4, BLOCKS..BLOCKS));
+
+        // Ranges merge and eventually cover the whole piece
+        let cone = DependencyCone::new(0..250, 4);
+        assert_eq!(cone.encoded_blocks(), ranges(0..250, 252..256));
+        assert_eq!(cone.decoded_blocks(), 250 + 251 + 252 + 253);
+        let cone = DependencyCone::new(0..252, 4);
+        assert_eq!(cone.layer(3), ranges(0..252, 253..256));
+        assert_eq!(cone.encoded_blocks(), ranges(0..256, BLOCKS..BLOCKS));
+        let cone = DependencyCone::new(0..BLOCKS, 10);
+        assert_eq!(cone.decoded_blocks(), BLOCKS * 10);
+
+        assert!(DependencyCone::new(7..7, 5).encoded_blocks().is_empty());
+    }
+
+    /// Every backend decodes requested blocks correctly while blocks outside of the cone are
+    /// garbage
+    #[test]
+    fn test_backends() {
+        let round_keys = RoundKeys::new(&ID);
+        let software_lut = SoftwareLuT::new();
+        let lut_keys = SoftwareLuTKeys::from_round_keys(&round_keys);
+
+        for &breadth_iterations in [1, 2, 3, 10].iter() {
+            let mut encoded = INPUT;
+            software_lut.encode(
+                &mut encoded,
+                &lut_keys,
+                IV,
+                AES_ITERATIONS,
+                breadth_iterations,
+            );
+
+            for block_range in [0..1, 0..5, 1..2, 37..42, 5..250, 250..256, 255..256, 0..256] {
+                let cone = DependencyCone::new(block_range.clone(), breadth_iterations);
+                let encoded_blocks = cone.encoded_blocks();
+                let mut piece = encoded;
+                for (index, block) in piece.chunks_exact_mut(BLOCK_SIZE).enumerate() {
+                    if !encoded_blocks.contains(index) {
+                        block.fill(0xAA);
+                    }
+                }
+                let bytes = (block_range.start * BLOCK_SIZE)..(block_range.end * BLOCK_SIZE);
+                let check = |piece: &Piece, backend: &str| {
+                    assert_eq!(
+                        piece[bytes.clone()],
+                        INPUT[bytes.clone()],
+                        "{} {} {:?}",
+                        backend,
+                        breadth_iterations,
+                        block_range,
+                    );
+                };
+
+                {
+                    let mut piece = piece;
+                    software_lut.decode_range(
+                        &mut piece,
+                        &lut_keys,
+                        &IV,
+                        block_range.clone(),
+                        AES_ITERATIONS,
+                        breadth_iterations,
+                    );
+                    check(&piece, "SoftwareLuT");
+                }
+
+                {
+                    let mut piece = piece;
+                    SoftwareFixSlicing::new().decode_range(
+                        &mut piece,
+                        &SoftwareFixSlicingKeys::from_round_keys(&round_keys),
+                        &IV,
+                        block_range.clone(),
+                        AES_ITERATIONS,
+                        breadth_iterations,
+                    );
+                    check(&piece, "SoftwareFixSlicing");
+                }
+
+                {
+                    let mut pieces = [piece; 8];
+                    SoftwareBitSlicing::new().decode_range(
+                        &mut pieces,
+                        &SoftwareBitSlicingKeys::from_round_keys(&round_keys),
+                        [&IV; 8],
+                        block_range.clone(),
+                        AES_ITERATIONS,
+                        breadth_iterations,
+                    );
+                    for piece in pieces.iter() {
+                        check(piece, "SoftwareBitSlicing");
+                    }
+                }
+
+                #[cfg(target_arch = "x86_64")]
+                {
+                    if let Ok(aes_ni) = AesNi::new() {
+                        let mut piece = piece;
+                        aes_ni.decode_range(
+                            &mut piece,
+                            &AesNiKeys::from_round_keys(&round_keys),
+                            &IV,
+                            block_range.clone(),
+                            AES_ITERATIONS,
+                            breadth_iterations,
+                        );
+                        check(&piece, "AesNi");
+                    }
+
+                    if let Ok(vaes) = VAes::new() {
+                        let mut piece = piece;
+                        vaes.decode_range(
+                            &mut piece,
+                            &VAesKeys::from_round_keys(&round_keys),
+                            &IV,
+                            block_range.clone(),
+                            AES_ITERATIONS,
+                            breadth_iterations,
+                        );
+                        check(&piece, "VAes");
+                    }
+                }
+            }
+        }
+    }
+}
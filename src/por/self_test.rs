@@ -0,0 +1,299 @@
+//! Known-answer self-test with reduced number of iterations, shared by all backends
+
+pub(super) use crate::por::test_data::ID;
+pub(super) use crate::por::test_data::INPUT;
+pub(super) use crate::por::test_data::IV;
+use crate::utils::SelfTestFailed;
+use crate::Piece;
+
+pub(super) const AES_ITERATIONS: usize = 16;
+pub(super) const BREADTH_ITERATIONS: usize = 2;
+
+/// `INPUT` encoded with `ID`, `IV` and iterations above
+const ENCODING: Piece = [
+    0x30, 0x66, 0x6e, 0x3c, 0x03, 0x57, 0x87, 0x2d, 0x7f, 0x9a, 0xa2, 0xf3, 0xda, 0x83, 0xf8, 0x0c,
+    0x66, 0x37, 0x23, 0x1e, 0x45, 0x1e, 0x02, 0x2d, 0x92, 0x89, 0xbb, 0x54, 0xe3, 0x22, 0x32, 0x63,
+    0x1f, 0x06, 0xbe, 0xb3, 0xd7, 0xcf, 0xac, 0x6d, 0xfa, 0x3f, 0x15, 0x22, 0x85, 0x08, 0x9d, 0x2d,
+    0x56, 0x27, 0xd7, 0x8e, 0xd3, 0x99, 0xa2, 0xbf, 0xc0, 0x71, 0x66, 0x6c, 0xdd, 0x46, 0x00, 0x87,
+    0x99, 0x9f, 0x1e, 0xab, 0x33, 0xe5, 0x78, 0xdc, 0x54, 0x58, 0x82, 0xb8, 0x61, 0x54, 0x9b, 0xc3,
+    0xb2, 0x6d, 0x20, 0xfb, 0x78, 0x65, 0xcd, 0x94, 0xc1, 0x3b, 0xcc, 0x4d, 0xb4, 0x13, 0x00, 0xf1,
+    0x69, 0x76, 0xf1, 0x8c, 0x99, 0x75, 0x1e, 0xbd, 0xcf, 0x16, 0x58, 0x3a, 0x96, 0x08, 0xe7, 0xdf,
+    0xe4, 0x5e, 0xe9, 0xb0, 0x6d, 0x64, 0x0c, 0x8e, 0xb0, 0x5f, 0x0d, 0xc7, 0x68, 0xbb, 0x72, 0xd6,
+    0x59, 0x32, 0x96, 0x17, 0x45, 0x9b, 0x06, 0x6a, 0x75, 0x8f, 0xf6, 0x13, 0xb1, 0xda, 0xcf, 0x02,
+    0x67, 0x58, 0x8f, 0x13, 0x83, 0x6a, 0x7d, 0x9f, 0xc9, 0x2a, 0x81, 0x99, 0xb7, 0x7b, 0x31, 0x23,
+    0xdc, 0x9f, 0x32, 0x03, 0x8e, 0xda, 0xdc, 0xd5, 0x37, 0x6e, 0xfb, 0x47, 0x5d, 0x52, 0x39, 0x38,
+    0xe1, 0xae, 0xcd, 0xa1, 0x50, 0x3f, 0xae, 0xd7, 0x4b, 0x1f, 0x3c, 0x8c, 0x3d, 0x0e, 0x6e, 0x22,
+    0xfc, 0xa1, 0x08, 0x74, 0x53, 0x06, 0xb1, 0x9a, 0xf4, 0xde, 0x2c, 0xb3, 0xc2, 0x93, 0xcb, 0x26,
+    0xdc, 0xdd, 0xb4, 0xf6, 0xeb, 0x33, 0xa4, 0xeb, 0x89, 0x8a, 0x19, 0x8f, 0x98, 0x46, 0x9e, 0x69,
+    0x9d, 0xe3, 0x60, 0x4d, 0x99, 0xb4, 0xc8, 0x92, 0xf6, 0x89, 0x27, 0xaa, 0x8a, 0x4d, 0x28, 0x86,
+    0x4b, 0xea, 0x04, 0x45, 0xd3, 0xd5, 0xaf, 0x85, 0x08, 0x87, 0x8b, 0xec, 0x64, 0xb9, 0x2e, 0xd4,
+    0xc3, 0x24, 0x6f, 0x6a, 0xa8, 0xad, 0x3f, 0xdb, 0x93, 0x83, 0x43, 0xf1, 0x05, 0x31, 0x3d, 0xd9,
+    0xd4, 0x47, 0x9c, 0xd9, 0x62, 0xc9, 0x32, 0xa3, 0x21, 0x15, 0xd4, 0xb2, 0x15, 0xff, 0x0e, 0xda,
+    0x3c, 0x69, 0x09, 0xe0, 0x4b, 0x90, 0x7f, 0xb2, 0x53, 0xd9, 0x65, 0xc9, 0x29, 0x89, 0x86, 0x2e,
+    0xe7, 0x9d, 0xc3, 0xf7, 0x0f, 0x10, 0xee, 0xdf, 0xcb, 0xf3, 0xc5, 0x10, 0xda, 0x40, 0x8d, 0x77,
+    0x4f, 0xe5, 0x4f, 0xb4, 0xb6, 0xc0, 0x5a, 0xe0, 0x89, 0xed, 0x7a, 0xb9, 0xfd, 0x7f, 0x86, 0xe4,
+    0x50, 0x23, 0x89, 0xfe, 0xa9, 0x28, 0x99, 0xd1, 0x68, 0x0a, 0x01, 0x95, 0x92, 0x61, 0x1b, 0x19,
+    0xa0, 0x6b, 0xb0, 0xb2, 0xed, 0x4a, 0x1d, 0x42, 0x54, 0x04, 0xab, 0xea, 0x8f, 0x9d, 0x65, 0x08,
+    0xb3, 0x5d, 0x6d, 0xbc, 0x2b, 0xd2, 0x6f, 0x5d, 0xd6, 0x0d, 0x5f, 0x65, 0xab, 0xf4, 0xda, 0x0f,
+    0x70, 0xc5, 0x43, 0x06, 0xb2, 0x84, 0xa1, 0x4a, 0x3c, 0xef, 0x89, 0xe4, 0x5e, 0xd4, 0x65, 0x07,
+    0x23, 0xd6, 0xf2, 0x80, 0x2d, 0xd5, 0x4a, 0x9f, 0xf0, 0x8b, 0x61, 0xdb, 0xd4, 0x54, 0x98, 0x9f,
+    0x0d, 0xdb, 0x39, 0x89, 0x4d, 0x63, 0xcc, 0x19, 0xf7, 0x0f, 0x00, 0x29, 0xfe, 0xf5, 0x47, 0x16,
+    0x89, 0xb5, 0x93, 0x23, 0x25, 0x66, 0x90, 0x12, 0xef, 0x51, 0xb3, 0x4c, 0xf6, 0xdb, 0xce, 0xb2,
+    0x4d, 0x8e, 0x89, 0x88, 0x01, 0x22, 0x5a, 0x63, 0x68, 0x54, 0x6b, 0x61, 0x82, 0xeb, 0xd3, 0x6e,
+    0x47, 0x20, 0x44, 0x06, 0x7b, 0x5b, 0x4b, 0x51, 0x7b, 0x20, 0x0e, 0x94, 0xdb, 0x43, 0xc0, 0x41,
+    0x7c, 0x21, 0xb5, 0xf3, 0x1a, 0xac, 0x63, 0x6e, 0x5a, 0xba, 0x86, 0xb3, 0x3e, 0xe5, 0x34, 0xf3,
+    0x6b, 0x99, 0xa5, 0x73, 0x2a, 0xeb, 0x11, 0x91, 0xa5, 0xbe, 0x40, 0x36, 0x70, 0xa4, 0xb7, 0xc5,
+    0x82, 0x18, 0x27, 0x16, 0xec, 0x3b, 0xaf, 0xa8, 0xa3, 0x01, 0x3b, 0x6e, 0x86, 0xde, 0x90, 0x2d,
+    0x99, 0x90, 0x4b, 0x28, 0x7f, 0x41, 0x4e, 0x81, 0x4c, 0x71, 0x14, 0x78, 0x50, 0x83, 0x50, 0xc3,
+    0xf9, 0xd2, 0xda, 0xbd, 0xac, 0x52, 0x67, 0xcc, 0x89, 0xfd, 0x27, 0x36, 0x67, 0xf0, 0xe7, 0xf4,
+    0xcd, 0x43, 0x7c, 0x29, 0xd0, 0x87, 0xe4, 0x9a, 0x23, 0x17, 0xb3, 0x13, 0x8a, 0xb3, 0xa9, 0xa0,
+    0x8b, 0x33, 0xd0, 0x3b, 0xfc, 0x8a, 0xa7, 0x7b, 0x71, 0xb9, 0xde, 0xcb, 0xdf, 0xad, 0x48, 0x12,
+    0x7c, 0x9a, 0x36, 0xb6, 0x19, 0xf3, 0xe2, 0xa2, 0x1a, 0xf9, 0x69, 0x1c, 0xe6, 0x85, 0xd6, 0xa6,
+    0xc6, 0xdf, 0xf9, 0x9f, 0x23, 0x23, 0xb4, 0xd7, 0x92, 0xc0, 0x60, 0xf8, 0x4f, 0xa9, 0x9e, 0x06,
+    0x41, 0x07, 0xbd, 0x35, 0x9b, 0x95, 0x60, 0x08, 0x1c, 0x6e, 0xcb, 0xcd, 0x44, 0x46, 0xaf, 0xfc,
+    0x3e, 0x9f, 0xb4, 0xfd, 0x07, 0x7f, 0xb8, 0xae, 0x97, 0x1f, 0xfe, 0xbe, 0x07, 0x05, 0x6d, 0x85,
+    0xe2, 0x45, 0x3a, 0xd5, 0xaa, 0x9d, 0x27, 0x1a, 0x5d, 0x88, 0x98, 0x4d, 0x5e, 0x54, 0x2c, 0xa4,
+    0x48, 0x19, 0xef, 0x4d, 0x4a, 0x86, 0x3e, 0xac, 0xe4, 0x62, 0x52, 0xa3, 0xf7, 0xa0, 0x64, 0xae,
+    0x04, 0xb9, 0x28, 0x0b, 0xb5, 0x45, 0x3a, 0xd1, 0x4e, 0x15, 0xd6, 0x0f, 0x61, 0xf5, 0xfc, 0x8e,
+    0x1d, 0x5b, 0xa7, 0xb6, 0xf1, 0x6d, 0x94, 0xf8, 0xb0, 0xc6, 0x1a, 0x93, 0x25, 0x2a, 0x61, 0x79,
+    0xa2, 0x0e, 0xb2, 0xbd, 0x62, 0x21, 0x73, 0xbf, 0x06, 0x49, 0xb9, 0x1b, 0x9b, 0x9f, 0x31, 0x17,
+    0x51, 0x23, 0xa1, 0x62, 0x3d, 0x3e, 0x10, 0xba, 0x0d, 0xe7, 0xab, 0x2d, 0xdd, 0x84, 0x4f, 0x89,
+    0x1a, 0x41, 0x6f, 0x10, 0x7c, 0xf3, 0x9a, 0x75, 0xa9, 0xf1, 0x32, 0xfd, 0x69, 0xf7, 0xeb, 0x41,
+    0xa7, 0xbf, 0x35, 0xc6, 0x37, 0x63, 0x38, 0x7a, 0xa2, 0x06, 0x4b, 0xa3, 0x4e, 0x3f, 0x46, 0x72,
+    0xca, 0x75, 0xd6, 0x7b, 0xc0, 0x05, 0x54, 0xae, 0x6e, 0x45, 0x9b, 0x15, 0x35, 0x78, 0xf4, 0x75,
+    0xe2, 0x90, 0xe2, 0x6d, 0x87, 0x15, 0xd7, 0x0c, 0xf5, 0xa5, 0x12, 0xf5, 0x97, 0x86, 0xb7, 0x8b,
+    0x18, 0x4d, 0x7a, 0x46, 0xff, 0xe0, 0x78, 0x2c, 0x4f, 0x94, 0xa4, 0x7c, 0x47, 0xd2, 0x71, 0x62,
+    0x5e, 0x31, 0x06, 0xd7, 0x6d, 0x21, 0x1f, 0xa3, 0x46, 0x9a, 0x0c, 0xcb, 0x79, 0x89, 0x7c, 0xa8,
+    0x43, 0x6b, 0xd9, 0x7f, 0x93, 0x98, 0x87, 0x82, 0xb4, 0xef, 0x06, 0xf7, 0xe7, 0x99, 0xce, 0xff,
+    0xac, 0x2e, 0x35, 0x7d, 0xf4, 0x97, 0xf3, 0xec, 0x7b, 0xb1, 0x65, 0xd1, 0x48, 0xdd, 0x9c, 0x77,
+    0x02, 0xaa, 0x81, 0x29, 0x0c, 0x08, 0xd8, 0xd4, 0x5f, 0xb2, 0x26, 0x40, 0xd5, 0xfe, 0x06, 0xb0,
+    0x17, 0xc0, 0x68, 0x95, 0x6a, 0xa1, 0x05, 0x10, 0xc6, 0x95, 0x05, 0xdd, 0xe4, 0xff, 0xc5, 0xcc,
+    0x83, 0x17, 0x07, 0x64, 0xb7, 0xae, 0x73, 0x3c, 0x89, 0x52, 0xb5, 0x0b, 0xda, 0x16, 0xbe, 0x21,
+    0xa9, 0x82, 0x12, 0xd1, 0x07, 0xbe, 0xc3, 0xb1, 0x6b, 0x54, 0xed, 0x80, 0x77, 0xa2, 0xec, 0x84,
+    0xdd, 0x58, 0x6e, 0x21, 0x31, 0x46, 0x2f, 0xe8, 0x3e, 0x96, 0xee, 0x00, 0x09, 0x27, 0x3c, 0xca,
+    0x19, 0xde, 0x32, 0x6a, 0xf3, 0xfc, 0x07, 0xb2, 0xee, 0xbf, 0xcd, 0x09, 0x4d, 0xa4, 0xfa, 0xb2,
+    0xc6, 0x6e, 0x66, 0xe7, 0xc4, 0x77, 0xc1, 0x47, 0xdf, 0xcf, 0x52, 0xb3, 0x25, 0x76, 0xb2, 0x37,
+    0x80, 0x43, 0xf7, 0xe8, 0x7e, 0x80, 0x42, 0x03, 0x2a, 0xc1, 0xc0, 0xe2, 0xeb, 0x4a, 0xba, 0x48,
+    0xfe, 0x69, 0x2e, 0x1f, 0x9a, 0xef, 0x83, 0x68, 0x57, 0x2b, 0x68, 0xb9, 0xe5, 0x15, 0x76, 0xe7,
+    0x14, 0x6b, 0x37, 0xec, 0xea, 0x1a, 0xb4, 0x4f, 0x63, 0x87, 0xb3, 0xed, 0xb6, 0xb9, 0x66, 0xcc,
+    0x2e, 0x05, 0xc6, 0xb4, 0xca, 0x92, 0xf2, 0x39, 0x82, 0xdb, 0x3c, 0xcc, 0x1c, 0x97, 0x7d, 0x73,
+    0xd9, 0x2c, 0xac, 0x08, 0xfe, 0x92, 0xf6, 0x0e, 0x39, 0xe0, 0xb5, 0x84, 0x0d, 0x0c, 0x85, 0x8f,
+    0xdd, 0x65, 0x80, 0x24, 0x2c, 0x0d, 0x5b, 0xd3, 0x79, 0x0a, 0x66, 0x6c, 0xcd, 0x4f, 0x14, 0x52,
+    0x36, 0x9a, 0xe7, 0x4c, 0xa3, 0x39, 0x9d, 0x83, 0x4f, 0x3c, 0xbb, 0x10, 0x7a, 0xd6, 0x65, 0xe7,
+    0xc4, 0xef, 0xcd, 0xd1, 0x8f, 0xac, 0x62, 0x2e, 0xb1, 0x23, 0x13, 0x78, 0x58, 0xbf, 0x94, 0x5a,
+    0x9f, 0x9f, 0x88, 0xe9, 0xd6, 0xf1, 0x9a, 0x0e, 0xfb, 0x95, 0x9c, 0x68, 0x9e, 0x36, 0x01, 0xbc,
+    0x7f, 0xa5, 0x9b, 0x43, 0x67, 0xbe, 0x3a, 0x86, 0x90, 0x53, 0x91, 0x8f, 0x29, 0x82, 0x1e, 0x52,
+    0x13, 0xf5, 0x9f, 0xfc, 0x90, 0xe0, 0xf4, 0x4d, 0xc9, 0xc0, 0x6b, 0xda, 0x6a, 0xd5, 0x50, 0xe2,
+    0x15, 0x2f, 0x4e, 0xc0, 0xe0, 0x17, 0xdc, 0xb0, 0x12, 0xfc, 0x7e, 0x1c, 0x0d, 0x71, 0x4f, 0x78,
+    0x72, 0x0f, 0xb6, 0x64, 0xce, 0xe2, 0xea, 0xad, 0x31, 0x2d, 0x0c, 0x67, 0xc4, 0xe9, 0xdf, 0xbf,
+    0xcd, 0x1d, 0x88, 0x14, 0xc4, 0xe9, 0x85, 0x07, 0x18, 0xc8, 0x89, 0xb9, 0xc2, 0x0c, 0x42, 0x7d,
+    0x39, 0xc0, 0xda, 0x89, 0xe7, 0x94, 0xf8, 0x14, 0xc7, 0xfc, 0x8d, 0xd7, 0x16, 0x61, 0x6d, 0x1e,
+    0xe0, 0x54, 0x64, 0x5b, 0xce, 0x72, 0x82, 0x19, 0x03, 0xcd, 0xc9, 0xd1, 0x3e, 0x48, 0xd8, 0xfd,
+    0x9c, 0xa5, 0x11, 0x2d, 0x89, 0x97, 0xe5, 0xdd, 0x17, 0x22, 0x8a, 0x2d, 0xf3, 0x89, 0x9d, 0x46,
+    0x35, 0x4a, 0x2f, 0x71, 0x2b, 0x70, 0x08, 0x0b, 0xe8, 0xdc, 0xc3, 0xe2, 0x50, 0x52, 0x6a, 0x75,
+    0x04, 0x07, 0xf6, 0x04, 0xb3, 0xec, 0xfc, 0xa7, 0x8e, 0xa2, 0x1d, 0xd7, 0xac, 0x9b, 0xf4, 0x34,
+    0x0c, 0x2c, 0xf1, 0xc5, 0x23, 0x20, 0x79, 0x89, 0x12, 0x09, 0x4e, 0x31, 0x60, 0x06, 0xbf, 0xda,
+    0x83, 0xb2, 0x63, 0x08, 0x2e, 0xc1, 0x32, 0x0d, 0xa3, 0x1d, 0xa5, 0x80, 0xe8, 0xf9, 0x76, 0xb2,
+    0x18, 0xba, 0x12, 0x92, 0xd1, 0x00, 0xee, 0xca, 0x77, 0xd4, 0xb1, 0xe2, 0xb8, 0x6c, 0xf2, 0x35,
+    0x68, 0x96, 0x49, 0xb3, 0xcc, 0x4e, 0xbd, 0xbf, 0xfd, 0x7b, 0xed, 0xbc, 0xab, 0x68, 0x5c, 0x35,
+    0xf3, 0x52, 0x83, 0x94, 0xfb, 0xd6, 0x9a, 0x50, 0xdf, 0x9b, 0xd7, 0x17, 0x13, 0x1d, 0x42, 0xe4,
+    0xbf, 0xaf, 0x05, 0xc1, 0x6e, 0xcf, 0x07, 0x1f, 0xdd, 0x55, 0xc2, 0x63, 0xde, 0x2a, 0xf2, 0xbb,
+    0x86, 0x96, 0x84, 0x1e, 0x98, 0xf3, 0xa4, 0x21, 0x25, 0x59, 0x0e, 0x0f, 0x01, 0x1c, 0x99, 0xd6,
+    0x4c, 0x37, 0xaf, 0xd4, 0xf5, 0x72, 0xb8, 0x97, 0xa4, 0x63, 0x33, 0xef, 0x39, 0x9e, 0x98, 0x80,
+    0x5e, 0xec, 0x9f, 0x4d, 0x35, 0x93, 0x77, 0xa2, 0x11, 0x15, 0x03, 0x2a, 0xfd, 0xd0, 0x99, 0xfa,
+    0x97, 0x15, 0xa6, 0x1e, 0xae, 0x10, 0xb5, 0x95, 0x33, 0x43, 0x52, 0x9c, 0x43, 0xaf, 0xc2, 0x2f,
+    0x7d, 0x38, 0x31, 0x3f, 0x08, 0xab, 0xa2, 0x5d, 0x81, 0xe9, 0x5f, 0x6a, 0xd2, 0x89, 0x44, 0xb9,
+    0xba, 0x87, 0x89, 0xc1, 0x09, 0x2e, 0xfd, 0xed, 0x16, 0x33, 0xa3, 0x71, 0x84, 0x23, 0xb0, 0x69,
+    0xba, 0x98, 0x3b, 0x0a, 0x6c, 0x22, 0x8e, 0x52, 0x3b, 0xca, 0x10, 0x37, 0x3a, 0x95, 0x7b, 0x9d,
+    0xa0, 0x40, 0xd0, 0xe6, 0x47, 0x2d, 0x44, 0x5b, 0xd9, 0xe9, 0x4a, 0x2e, 0x3c, 0x42, 0xf7, 0xc0,
+    0xe5, 0xae, 0x32, 0xf0, 0xa1, 0x8e, 0x14, 0x71, 0xbd, 0xe3, 0xe1, 0x2a, 0x43, 0x7f, 0xde, 0x7f,
+    0x7c, 0xd0, 0xb5, 0x69, 0xae, 0xea, 0x9b, 0xe9, 0x12, 0x84, 0x80, 0x89, 0xa2, 0x76, 0xa6, 0x43,
+    0xad, 0xcb, 0x43, 0xec, 0xa8, 0x21, 0x91, 0x55, 0xdc, 0x00, 0x0d, 0xe2, 0x39, 0x7b, 0x9f, 0x8e,
+    0xdc, 0x83, 0x86, 0x55, 0x00, 0xd5, 0x0f, 0x5c, 0x3e, 0x5a, 0x0d, 0xcd, 0x50, 0xaa, 0x8b, 0xe8,
+    0x65, 0x7b, 0x1a, 0x18, 0x09, 0xd9, 0x44, 0x9f, 0x93, 0x04, 0xbf, 0x83, 0xbb, 0x10, 0x93, 0xd7,
+    0x5b, 0xd6, 0x1c, 0x1f, 0x2c, 0xcd, 0xfa, 0x58, 0x3a, 0x8c, 0x66, 0x8f, 0x4e, 0x79, 0xc3, 0x72,
+    0x81, 0x40, 0x4f, 0x0d, 0x21, 0xb5, 0x83, 0x6c, 0x96, 0x9c, 0xc1, 0x51, 0x56, 0xb0, 0x78, 0x92,
+    0x8e, 0xc4, 0x1b, 0x49, 0xd5, 0xc2, 0xb4, 0x1d, 0x5a, 0x29, 0x63, 0xe0, 0x75, 0x61, 0x1a, 0x76,
+    0x2b, 0x21, 0xd5, 0xc5, 0x5a, 0x8e, 0x6c, 0x06, 0xf3, 0x74, 0xfc, 0x62, 0x80, 0x83, 0x69, 0x53,
+    0xf0, 0xa7, 0x60, 0xf6, 0x51, 0x29, 0x3a, 0x05, 0xbe, 0x31, 0xab, 0x97, 0x68, 0xe6, 0x19, 0x3b,
+    0x12, 0x53, 0x20, 0x56, 0x8d, 0xbe, 0x65, 0x70, 0x07, 0x86, 0x86, 0x21, 0x3f, 0x38, 0x87, 0xe8,
+    0x08, 0xba, 0x03, 0x9d, 0xf9, 0xef, 0xee, 0x47, 0x8e, 0x95, 0x09, 0x0d, 0xbc, 0x35, 0xc3, 0x23,
+    0xa6, 0xd8, 0xad, 0xca, 0x66, 0x20, 0xa3, 0x93, 0xab, 0xb0, 0x2f, 0xc8, 0x21, 0x5a, 0xbe, 0x3d,
+    0x37, 0x3f, 0x0b, 0x0c, 0xc8, 0x5f, 0x19, 0xfd, 0xb8, 0xa6, 0x01, 0xba, 0xee, 0x9d, 0x01, 0x8c,
+    0xbe, 0xac, 0x62, 0xcc, 0x89, 0xd6, 0xf3, 0xdd, 0x8e, 0x4f, 0x2c, 0x72, 0x24, 0x42, 0xdb, 0x21,
+    0xf5, 0x65, 0x12, 0xd0, 0xcd, 0x17, 0x4f, 0x24, 0x5f, 0x71, 0x03, 0x9b, 0xe6, 0xab, 0x54, 0x01,
+    0x80, 0x1d, 0x3d, 0x6e, 0x9b, 0x01, 0x2a, 0xd3, 0x28, 0xd0, 0xa4, 0xac, 0x24, 0x2b, 0x54, 0x6f,
+    0xf2, 0xd8, 0x1f, 0x95, 0x38, 0x96, 0xc8, 0x6d, 0xef, 0xf1, 0xd4, 0xe5, 0x66, 0x07, 0x1c, 0x62,
+    0xb4, 0x5f, 0x89, 0x2f, 0xfa, 0xef, 0xaa, 0x2e, 0xa6, 0xc5, 0x76, 0xe6, 0x9a, 0x11, 0x24, 0xf3,
+    0xbc, 0x33, 0x01, 0x68, 0x45, 0xba, 0x76, 0x98, 0x08, 0x5c, 0xd9, 0xf0, 0x64, 0x72, 0x38, 0xd1,
+    0x5c, 0xfe, 0xa7, 0x67, 0x74, 0x07, 0x3a, 0xd2, 0x81, 0x2e, 0x8f, 0x53, 0x50, 0x3b, 0x6e, 0xef,
+    0x85, 0xab, 0x5b, 0x3f, 0xee, 0x6f, 0x56, 0x96, 0xd0, 0x0e, 0xb2, 0x25, 0x48, 0xc5, 0xc9, 0x28,
+    0x5e, 0x3e, 0x85, 0xe4, 0x79, 0x67, 0x1f, 0xfc, 0x18, 0xdf, 0xde, 0xec, 0x68, 0x6d, 0x1b, 0x49,
+    0x4d, 0x0f, 0xd7, 0x4a, 0x14, 0x0b, 0x1a, 0x2f, 0xb2, 0x59, 0xad, 0x69, 0x88, 0x29, 0x06, 0x30,
+    0xe7, 0x48, 0xd7, 0x4c, 0xd2, 0x12, 0x17, 0x15, 0xea, 0xbc, 0x08, 0x56, 0x14, 0xd7, 0x25, 0x59,
+    0xf9, 0x85, 0xe1, 0xfe, 0xe4, 0xff, 0x84, 0x71, 0x8c, 0x6a, 0xee, 0xc7, 0xe2, 0xf8, 0xc7, 0x29,
+    0x5b, 0xac, 0x98, 0x56, 0x04, 0xa6, 0x06, 0x93, 0x8d, 0xa6, 0x84, 0x21, 0x28, 0xc7, 0x04, 0x64,
+    0xcb, 0x85, 0xe2, 0x90, 0x38, 0x5d, 0xa8, 0xf1, 0x09, 0xdd, 0x71, 0x03, 0x82, 0x64, 0xae, 0xda,
+    0x21, 0xbf, 0x5a, 0x3b, 0xc0, 0x08, 0x42, 0xda, 0xa4, 0xae, 0x81, 0xc7, 0xb8, 0x83, 0x80, 0x5a,
+    0x74, 0x4f, 0x38, 0xa8, 0x31, 0x08, 0x17, 0xc3, 0xd7, 0x88, 0x5b, 0x31, 0x90, 0x5f, 0x19, 0xd5,
+    0x6f, 0x52, 0x47, 0xff, 0x2b, 0xcd, 0x2d, 0xda, 0x62, 0x7c, 0x9f, 0x6c, 0x5d, 0x56, 0xe4, 0xa0,
+    0x21, 0x77, 0xc1, 0x79, 0x54, 0xa4, 0x47, 0x45, 0x1d, 0x3a, 0x1e, 0xb6, 0xa2, 0xd9, 0x51, 0xa9,
+    0x0b, 0x6d, 0xfd, 0x72, 0x09, 0x1d, 0x8d, 0x70, 0x43, 0x32, 0xd9, 0x89, 0xb3, 0x08, 0xfa, 0xae,
+    0xde, 0xda, 0x8b, 0x2e, 0x24, 0x5d, 0x1f, 0x08, 0x17, 0xc4, 0x17, 0xeb, 0x16, 0x04, 0x22, 0x0a,
+    0x4e, 0x42, 0xd8, 0xff, 0xa0, 0xe9, 0x5d, 0x3b, 0x7b, 0x27, 0x5f, 0xbb, 0x92, 0xb0, 0x31, 0xb7,
+    0x45, 0xc6, 0x74, 0xf1, 0x26, 0x8b, 0xdc, 0xc2, 0x05, 0x8b, 0x1d, 0x66, 0xf7, 0xda, 0x17, 0xce,
+    0x2c, 0xf0, 0x01, 0xf0, 0x2d, 0xd2, 0xb8, 0xec, 0x28, 0x07, 0x2c, 0xb9, 0xa9, 0xae, 0x17, 0x3e,
+    0xb9, 0xce, 0x56, 0x00, 0x5e, 0x70, 0xbe, 0x50, 0x11, 0x3e, 0x0d, 0x7d, 0x88, 0xc7, 0x72, 0x3d,
+    0xef, 0x4b, 0x56, 0x4b, 0x28, 0x43, 0xc4, 0x5c, 0x4e, 0x8b, 0x5b, 0xa2, 0x30, 0xcc, 0x9a, 0x8e,
+    0x85, 0xa2, 0x27, 0xe4, 0x28, 0xe2, 0xb2, 0x6c, 0x12, 0x0b, 0xc3, 0xea, 0xdf, 0x0a, 0x50, 0xa8,
+    0x4e, 0xa8, 0xf2, 0xa0, 0xf1, 0x98, 0xd7, 0x65, 0xa0, 0x71, 0x52, 0x42, 0xe5, 0xdf, 0x0b, 0xba,
+    0x67, 0x0d, 0x14, 0x86, 0x91, 0x59, 0xa6, 0xff, 0x75, 0x13, 0x57, 0x16, 0x53, 0x22, 0x35, 0x59,
+    0x77, 0xf8, 0x3a, 0xe1, 0x69, 0x7f, 0xe1, 0xa1, 0xc1, 0xd5, 0x3f, 0x0e, 0xbd, 0xe0, 0xfd, 0x11,
+    0x3a, 0xd5, 0xa1, 0xdd, 0x9e, 0xf4, 0x2d, 0x6b, 0xf9, 0x08, 0x3a, 0xc0, 0x65, 0xaa, 0x37, 0x05,
+    0xa7, 0xcd, 0x25, 0xd1, 0x9d, 0x6a, 0x5b, 0xc6, 0x30, 0xfe, 0x16, 0x08, 0x10, 0xd9, 0xb4, 0x36,
+    0xc8, 0x56, 0x41, 0x89, 0x1a, 0x77, 0x66, 0xc1, 0xf3, 0xc9, 0x69, 0xc6, 0x79, 0x8e, 0xae, 0x25,
+    0xe4, 0x72, 0x76, 0x0d, 0x4f, 0xa5, 0x98, 0xb1, 0xf3, 0xb6, 0x45, 0xa7, 0x33, 0x83, 0x42, 0x21,
+    0x2b, 0x6f, 0x9e, 0x9f, 0xb4, 0xe1, 0x46, 0xff, 0x37, 0x34, 0xe5, 0x65, 0xb4, 0x61, 0x80, 0x07,
+    0xe8, 0x4e, 0x38, 0xb3, 0x76, 0x84, 0xc9, 0x1a, 0x99, 0x6b, 0xb7, 0x8a, 0x21, 0x5f, 0xe4, 0x5d,
+    0xab, 0x4b, 0x4b, 0xfc, 0x40, 0xea, 0x80, 0xe1, 0x12, 0x22, 0xfe, 0x56, 0xd8, 0x02, 0x85, 0x45,
+    0x6c, 0x26, 0xab, 0xb3, 0x05, 0x71, 0xd4, 0xb1, 0x06, 0xd7, 0x8f, 0x37, 0xbe, 0xdc, 0xa3, 0xf9,
+    0x01, 0x02, 0x4e, 0x44, 0x09, 0x37, 0xe0, 0xd5, 0x66, 0x6b, 0x5a, 0xb7, 0xac, 0x09, 0xfb, 0x8d,
+    0x75, 0xef, 0x01, 0x90, 0xf9, 0x90, 0x5e, 0x57, 0xc1, 0x65, 0xc2, 0xd8, 0xaf, 0x83, 0xab, 0x68,
+    0xd6, 0x01, 0x68, 0x30, 0x65, 0x7b, 0x21, 0xeb, 0x06, 0xab, 0x0a, 0xa5, 0x45, 0x6f, 0xc0, 0xfe,
+    0x23, 0xfd, 0x6a, 0xa8, 0x75, 0x92, 0x7f, 0xf8, 0x6a, 0x74, 0x57, 0x9d, 0x59, 0x48, 0x31, 0xe4,
+    0xee, 0x51, 0x20, 0x26, 0x15, 0x70, 0x89, 0x4f, 0xfd, 0xf8, 0x22, 0x15, 0x78, 0x78, 0xd3, 0x6b,
+    0x24, 0x08, 0x99, 0x11, 0xc2, 0x4d, 0x12, 0xe4, 0xc9, 0xe2, 0xc3, 0x00, 0x3d, 0x3c, 0xee, 0x9f,
+    0x73, 0x37, 0x95, 0x1e, 0xec, 0x95, 0x42, 0xa7, 0xa1, 0x9d, 0xf4, 0x25, 0x68, 0x4d, 0xa0, 0x1b,
+    0x85, 0x53, 0xea, 0xdf, 0x04, 0x62, 0xc2, 0x31, 0x26, 0x80, 0xc2, 0x79, 0x69, 0x76, 0x8c, 0xf9,
+    0x43, 0x42, 0xb1, 0xb5, 0x43, 0xc3, 0xbf, 0xd7, 0x8b, 0xd0, 0xac, 0x56, 0xe8, 0x1b, 0xd6, 0xf6,
+    0x8a, 0x27, 0x03, 0xf9, 0xc1, 0x09, 0xe5, 0x4a, 0x1d, 0xe2, 0x30, 0x3f, 0x7c, 0x6b, 0xe9, 0xd6,
+    0x7f, 0x9a, 0x38, 0xf0, 0x32, 0x20, 0x1c, 0xbf, 0x62, 0x6d, 0xf6, 0xf0, 0xce, 0xbe, 0xc6, 0xd8,
+    0x21, 0x5d, 0x0e, 0x4c, 0xd2, 0x21, 0x89, 0xb8, 0x7a, 0x1d, 0xf9, 0x1e, 0x9e, 0x23, 0xa8, 0xd8,
+    0xbb, 0x7f, 0x0a, 0xcd, 0x38, 0xf2, 0x69, 0xfd, 0xdf, 0x55, 0x87, 0x56, 0xea, 0xd0, 0xec, 0xdd,
+    0x71, 0x40, 0xe8, 0x02, 0x6a, 0x61, 0x63, 0xb9, 0x5f, 0xf6, 0x2d, 0xcb, 0xa1, 0x24, 0xb5, 0x1c,
+    0x42, 0x45, 0x1c, 0x9c, 0xdd, 0x29, 0x69, 0xdf, 0xa1, 0x46, 0x04, 0xa1, 0xb6, 0x5d, 0x49, 0x90,
+    0x19, 0xb7, 0x15, 0xbe, 0x0a, 0x1c, 0x27, 0x3b, 0xad, 0xb0, 0x9f, 0xb6, 0x5f, 0xf4, 0xd2, 0x0c,
+    0x76, 0x05, 0x86, 0x09, 0xab, 0x18, 0xcd, 0xe3, 0x39, 0xda, 0x8f, 0xc6, 0xfe, 0x67, 0xbf, 0x8b,
+    0xb6, 0x71, 0x9b, 0x29, 0xaa, 0x6b, 0xdc, 0x39, 0x59, 0xd1, 0x8e, 0x64, 0x51, 0x13, 0xbc, 0xb0,
+    0x2f, 0x17, 0x63, 0x35, 0x2d, 0x4a, 0x0f, 0x27, 0x5c, 0x89, 0xda, 0xc0, 0x05, 0xf1, 0xf2, 0xbb,
+    0xee, 0x43, 0xe3, 0x18, 0xcb, 0x9d, 0xcd, 0x23, 0x83, 0xf4, 0x6d, 0x6d, 0xb6, 0x04, 0xdb, 0x3f,
+    0x79, 0x53, 0x47, 0x4d, 0x51, 0xcf, 0x25, 0x51, 0xf6, 0x09, 0xf4, 0x8e, 0x62, 0x79, 0x53, 0xba,
+    0x8d, 0xfe, 0x3f, 0x73, 0x36, 0x97, 0x62, 0x16, 0x2d, 0xcd, 0x66, 0xd9, 0x57, 0x34, 0x62, 0xd6,
+    0x55, 0x0d, 0xbf, 0x9e, 0x26, 0xf1, 0x27, 0x7a, 0xf6, 0xdf, 0x41, 0x07, 0x59, 0x80, 0xbe, 0xdf,
+    0xe7, 0xe3, 0xfe, 0x3d, 0x41, 0x92, 0x9a, 0xde, 0xb9, 0x8b, 0xdb, 0x77, 0xbd, 0xe2, 0x8c, 0xf5,
+    0x31, 0x3a, 0x74, 0x7d, 0xb1, 0x68, 0x4a, 0x96, 0xe0, 0xa5, 0x65, 0xdb, 0x57, 0xe8, 0xad, 0x87,
+    0x0f, 0xec, 0xcd, 0xc4, 0x72, 0x40, 0x77, 0x92, 0x3a, 0xc8, 0xf7, 0xda, 0x1b, 0x30, 0x73, 0xa2,
+    0xf6, 0x72, 0x97, 0x14, 0x09, 0xb6, 0x33, 0x17, 0x18, 0x51, 0x71, 0x0e, 0xbc, 0xe1, 0xb1, 0xef,
+    0x04, 0x92, 0x41, 0xcb, 0x0a, 0xa3, 0x8a, 0x69, 0xd8, 0xf7, 0x49, 0x10, 0x0d, 0x1f, 0x1c, 0xf5,
+    0xde, 0xf7, 0x1a, 0x46, 0x10, 0x65, 0xe5, 0x86, 0x43, 0x44, 0x8e, 0x9f, 0x4e, 0x00, 0xa7, 0xd7,
+    0x22, 0xc3, 0x20, 0x60, 0x2d, 0x9b, 0x09, 0x89, 0x6d, 0x52, 0x9b, 0x0f, 0x5a, 0xf2, 0x24, 0xb4,
+    0xf9, 0x32, 0xa2, 0x63, 0xd3, 0xf6, 0x6c, 0xf9, 0xa0, 0xd4, 0xb8, 0x4d, 0x51, 0xb9, 0xe2, 0x0e,
+    0xbd, 0xec, 0x78, 0x93, 0xc5, 0xaf, 0xec, 0x9b, 0xbc, 0xde, 0x85, 0xe1, 0xb7, 0x01, 0x5b, 0xa4,
+    0xe3, 0x0f, 0x23, 0x0d, 0xd3, 0x1f, 0x74, 0x5b, 0xc1, 0x8d, 0xf6, 0x67, 0xd7, 0x60, 0xb9, 0x16,
+    0x97, 0x08, 0xca, 0x3f, 0x77, 0x43, 0xc8, 0x28, 0x2a, 0x3d, 0x80, 0x54, 0x83, 0x47, 0x23, 0xf7,
+    0x3a, 0x0d, 0x02, 0x67, 0x51, 0x5f, 0x4c, 0xea, 0x2e, 0x32, 0xcf, 0xec, 0xdc, 0xd3, 0x52, 0xa7,
+    0x05, 0xa2, 0xc7, 0xdc, 0xfc, 0x35, 0x16, 0xed, 0x63, 0x69, 0x09, 0xc8, 0x66, 0xc3, 0xb8, 0x22,
+    0x63, 0x12, 0x5e, 0x51, 0xa9, 0x58, 0x0a, 0xec, 0xb2, 0xd6, 0x95, 0x15, 0xe3, 0x22, 0x82, 0x9b,
+    0x32, 0x76, 0x24, 0xb8, 0x80, 0x0d, 0x4b, 0xdd, 0x58, 0xb6, 0x26, 0x9f, 0x96, 0xfe, 0xe8, 0xdb,
+    0xa3, 0xe6, 0x25, 0x02, 0x44, 0x48, 0xac, 0x14, 0xda, 0xf9, 0x54, 0x4a, 0xb0, 0x05, 0xa2, 0xee,
+    0x58, 0x5f, 0xa6, 0x8f, 0xaa, 0x32, 0xe2, 0xbb, 0xea, 0x31, 0xba, 0x61, 0x4b, 0xa9, 0x11, 0xbd,
+    0xf8, 0x26, 0xb1, 0x3d, 0x9e, 0x30, 0xe3, 0xd0, 0x89, 0xee, 0x57, 0x4f, 0x4a, 0x91, 0xe7, 0xcd,
+    0x82, 0x09, 0x4c, 0xde, 0x33, 0xfa, 0xc6, 0x57, 0x6a, 0x01, 0xa5, 0x08, 0x52, 0x0c, 0xb1, 0x58,
+    0xed, 0x84, 0x24, 0x15, 0x6d, 0x86, 0xf3, 0x03, 0x42, 0x8d, 0xfd, 0x4e, 0x0b, 0x39, 0xaa, 0x09,
+    0x01, 0x9d, 0xf5, 0xa4, 0x40, 0x23, 0x38, 0xb4, 0xd4, 0xb1, 0x60, 0xe2, 0xb8, 0xa0, 0x5f, 0x8c,
+    0xab, 0x94, 0x43, 0x0e, 0x73, 0xe9, 0x1c, 0x74, 0x36, 0x04, 0xa9, 0x35, 0xf9, 0xe1, 0x06, 0x99,
+    0x5b, 0x63, 0x68, 0x11, 0x75, 0x6b, 0xa6, 0x6d, 0x7b, 0x32, 0x57, 0x0d, 0x63, 0x2b, 0x12, 0x6e,
+    0x79, 0x92, 0x01, 0x48, 0x90, 0x07, 0x31, 0xfe, 0x2f, 0x57, 0x04, 0xb8, 0xcf, 0xae, 0x21, 0x7e,
+    0x40, 0x8a, 0x44, 0xf9, 0x2f, 0x91, 0x90, 0x1d, 0x93, 0x93, 0x53, 0xae, 0xda, 0x05, 0x7a, 0x45,
+    0x42, 0x98, 0x00, 0x79, 0x9d, 0x31, 0x17, 0x27, 0x72, 0x22, 0x22, 0x74, 0x98, 0xb7, 0x8c, 0xf0,
+    0x80, 0x11, 0x09, 0x0e, 0x21, 0xf7, 0xd1, 0x93, 0xd4, 0x26, 0x9e, 0xe6, 0x79, 0x55, 0x52, 0xb3,
+    0xb4, 0x7c, 0x5e, 0x3f, 0xd3, 0xb0, 0x63, 0xce, 0x52, 0xbf, 0xca, 0x2a, 0x3e, 0xe3, 0x74, 0xac,
+    0xf9, 0x69, 0x53, 0xfe, 0x15, 0xa6, 0xcc, 0x8b, 0xc0, 0x0e, 0x24, 0xba, 0x3a, 0x37, 0x32, 0x2e,
+    0x1f, 0xe0, 0x2e, 0x2e, 0x03, 0xe6, 0x30, 0x89, 0x88, 0x8f, 0x23, 0x3b, 0xef, 0x7d, 0x8f, 0x39,
+    0x35, 0x39, 0x7e, 0x3c, 0xeb, 0xcb, 0xb7, 0x1b, 0x1f, 0xa7, 0x36, 0xb6, 0x39, 0x25, 0xa6, 0x7a,
+    0xda, 0x3c, 0xe6, 0xa7, 0x12, 0xcb, 0x3c, 0xa1, 0xc6, 0x03, 0xbc, 0xdd, 0x5d, 0x25, 0x04, 0xcd,
+    0xd5, 0x2b, 0x26, 0xa9, 0xa6, 0xd8, 0x9c, 0x71, 0xcd, 0x5b, 0x02, 0xa9, 0xec, 0x96, 0x1d, 0x92,
+    0xec, 0x64, 0xbe, 0x69, 0x79, 0xf3, 0x9b, 0xcf, 0x01, 0x70, 0xd1, 0x44, 0x5e, 0x84, 0x28, 0x72,
+    0xb2, 0x3e, 0x26, 0x36, 0x5a, 0x11, 0x02, 0x43, 0xfe, 0xdc, 0x63, 0x98, 0x72, 0xd6, 0x23, 0xe6,
+    0xdb, 0x8d, 0x5e, 0x1c, 0xcd, 0x9a, 0x79, 0x8a, 0x0e, 0x80, 0xcd, 0x40, 0xe9, 0x56, 0x6e, 0x9d,
+    0xac, 0x05, 0xf5, 0xa0, 0xa3, 0x20, 0x2c, 0xb7, 0xc7, 0x4f, 0x2b, 0xe3, 0xe4, 0x18, 0x3c, 0xd7,
+    0x11, 0x46, 0xa8, 0x3e, 0xae, 0x6f, 0xbb, 0x40, 0xed, 0x28, 0xf6, 0x13, 0x15, 0x4b, 0x33, 0x4e,
+    0xb9, 0xd1, 0xc3, 0x60, 0x2c, 0x3b, 0x02, 0xa0, 0x52, 0x54, 0x26, 0x4b, 0xfb, 0xc1, 0x33, 0x55,
+    0x29, 0x3e, 0x8f, 0xe4, 0x67, 0xb7, 0x87, 0x63, 0x1e, 0x36, 0x36, 0xae, 0x3f, 0x5a, 0x82, 0x6d,
+    0x58, 0xb2, 0xbc, 0x93, 0xd1, 0xf3, 0xab, 0x9a, 0xab, 0x20, 0x95, 0xd3, 0x99, 0x1f, 0x2e, 0xb9,
+    0x1e, 0x78, 0xa3, 0x1a, 0x87, 0x0d, 0x94, 0x7f, 0xf0, 0xb7, 0x69, 0xcb, 0x10, 0xe6, 0xa0, 0xc8,
+    0x50, 0x1b, 0xdb, 0xe8, 0x89, 0x81, 0x40, 0x81, 0xd4, 0xb5, 0xb5, 0xec, 0x2b, 0x8a, 0xb4, 0x2b,
+    0x8f, 0x3d, 0xb6, 0x17, 0x7c, 0x9d, 0xc1, 0xe1, 0x41, 0x40, 0x08, 0x77, 0x27, 0xde, 0x6b, 0xcc,
+    0x22, 0xef, 0x97, 0x8c, 0x97, 0xc2, 0xd8, 0xcb, 0xed, 0x91, 0xdb, 0x42, 0xa5, 0x5f, 0xa6, 0x71,
+    0x9e, 0x2e, 0xdc, 0x34, 0x1f, 0xec, 0x58, 0x6a, 0xd4, 0x3f, 0xdd, 0xa8, 0x9a, 0xca, 0x26, 0x3e,
+    0x63, 0xab, 0xd6, 0x12, 0xb8, 0xec, 0x1b, 0x18, 0x04, 0xe5, 0xfc, 0x3c, 0x0a, 0x16, 0x2b, 0xc0,
+    0x01, 0x3e, 0xbd, 0x06, 0x33, 0x6f, 0xc6, 0x34, 0xfa, 0xcd, 0x71, 0x40, 0x82, 0x50, 0xcc, 0x3d,
+    0x79, 0x93, 0xa8, 0x90, 0xc5, 0x1e, 0x69, 0xf0, 0x85, 0x66, 0x11, 0xcd, 0x3c, 0xa7, 0x16, 0x6b,
+    0xbc, 0x61, 0x69, 0xe3, 0xfb, 0x85, 0x69, 0xff, 0x71, 0xc3, 0x14, 0x46, 0x76, 0xdd, 0xa2, 0x66,
+    0x7d, 0xc2, 0x62, 0x33, 0x6e, 0xb2, 0x2e, 0x95, 0xd3, 0x55, 0x09, 0x43, 0xe3, 0x4b, 0x39, 0xd1,
+    0x05, 0x70, 0x26, 0xb9, 0x0b, 0x30, 0x27, 0x6e, 0xaf, 0x21, 0xf7, 0x37, 0xe3, 0xda, 0x49, 0x12,
+    0xac, 0x66, 0x8a, 0xf4, 0xdd, 0x73, 0x14, 0xf4, 0xdc, 0xf1, 0x0a, 0x51, 0x26, 0xdc, 0x73, 0x29,
+    0xc9, 0x16, 0xf6, 0x4e, 0xd3, 0xbc, 0xcf, 0x4f, 0x72, 0x51, 0xfe, 0xe7, 0xac, 0x30, 0x21, 0x8c,
+    0x92, 0xb5, 0xd8, 0x2f, 0x5c, 0x1d, 0x8e, 0x8c, 0xe8, 0x88, 0xe3, 0x54, 0x0c, 0x57, 0x60, 0x27,
+    0xbb, 0x88, 0x05, 0x14, 0xc9, 0x4a, 0x75, 0x72, 0x28, 0xf6, 0xa1, 0xaf, 0x6f, 0x92, 0x53, 0x2d,
+    0x2e, 0xfa, 0x32, 0x2e, 0xa1, 0xdb, 0x72, 0xb7, 0xaa, 0xed, 0x9d, 0xd9, 0x62, 0x6a, 0x8b, 0x16,
+    0x19, 0xc7, 0x30, 0x6d, 0x58, 0x42, 0x73, 0x58, 0xeb, 0x80, 0x17, 0x4c, 0x5b, 0xb0, 0xf8, 0x85,
+    0x9e, 0x3c, 0x21, 0x0f, 0x7e, 0x56, 0x44, 0xe2, 0x3e, 0xd4, 0x7d, 0xb8, 0x45, 0x47, 0xca, 0x05,
+    0x25, 0x25, 0x47, 0xf1, 0x4d, 0xe9, 0x0a, 0x55, 0xe3, 0x90, 0x94, 0x2f, 0x90, 0x47, 0x2e, 0x07,
+    0x77, 0x3b, 0xe8, 0x70, 0x1d, 0x19, 0x8e, 0xc4, 0xfb, 0xd4, 0x23, 0x8c, 0x5d, 0x94, 0xfc, 0x91,
+    0x0e, 0x81, 0x67, 0xec, 0xb3, 0xe8, 0xbd, 0x4a, 0xc9, 0xd6, 0xa6, 0x09, 0xd4, 0x35, 0xfe, 0x80,
+    0x2e, 0xf2, 0xee, 0xcf, 0xa7, 0xe2, 0x7b, 0xf4, 0x77, 0x8b, 0xed, 0x77, 0x4d, 0xd5, 0x99, 0x14,
+    0x91, 0xfc, 0xea, 0x24, 0xcf, 0x0f, 0x00, 0x86, 0xfe, 0x75, 0x28, 0xc5, 0xd8, 0x65, 0x9f, 0x11,
+    0x3e, 0xe9, 0x21, 0x6a, 0x6f, 0x8f, 0x56, 0x6a, 0x50, 0xb3, 0x7e, 0xb4, 0xf8, 0x56, 0x74, 0xd1,
+    0x6f, 0x85, 0xdf, 0xd5, 0x28, 0xd6, 0x69, 0x02, 0xc9, 0x2c, 0x55, 0x70, 0x6a, 0x47, 0xe0, 0xe8,
+    0x59, 0xc4, 0xf1, 0x6d, 0x9b, 0x84, 0x84, 0xac, 0x17, 0xec, 0x0d, 0xe0, 0x7d, 0x7d, 0x38, 0x49,
+    0xb0, 0x1d, 0x2e, 0x61, 0x22, 0xf9, 0xcc, 0x93, 0xa5, 0x9a, 0x8a, 0x1b, 0xda, 0x3a, 0x37, 0x45,
+    0xac, 0xc8, 0x67, 0x96, 0x64, 0xda, 0xcb, 0x08, 0x09, 0x90, 0xc7, 0x37, 0x61, 0x26, 0x22, 0xe3,
+    0xa9, 0x8d, 0xc7, 0x6e, 0x70, 0x9c, 0xd9, 0x0b, 0x63, 0x27, 0xb1, 0x56, 0x42, 0x5f, 0xc1, 0x3c,
+    0x3d, 0x04, 0xd1, 0x1a, 0x79, 0x35, 0x76, 0x19, 0xd2, 0x32, 0x75, 0x0b, 0xe6, 0x36, 0x8e, 0x99,
+    0xa6, 0x37, 0x4e, 0xc4, 0x0b, 0xdd, 0x17, 0xbf, 0x35, 0xb2, 0x51, 0x51, 0xd8, 0xc2, 0x86, 0x25,
+    0xc2, 0xd0, 0x86, 0x77, 0x8b, 0xa9, 0x53, 0x24, 0xe6, 0xe7, 0x92, 0x82, 0x41, 0xb8, 0xeb, 0x1e,
+    0x97, 0x31, 0x92, 0x55, 0x80, 0x46, 0xfd, 0x19, 0x99, 0xed, 0xa5, 0x48, 0x08, 0x03, 0xf9, 0x94,
+    0x10, 0xce, 0x16, 0x30, 0xc0, 0x5b, 0xc5, 0xa9, 0xbc, 0x0e, 0xd0, 0x01, 0x76, 0xa3, 0xe1, 0x2b,
+    0x95, 0x64, 0x3b, 0x5a, 0x21, 0x89, 0xa6, 0xa7, 0x89, 0xa7, 0xbc, 0x07, 0x4b, 0x9e, 0x8e, 0x99,
+    0x56, 0x07, 0x42, 0x3d, 0x3f, 0xa3, 0x5a, 0x02, 0x5c, 0x6c, 0xc8, 0x80, 0x8e, 0x59, 0x56, 0x2b,
+    0x02, 0xe4, 0x5f, 0xb6, 0x44, 0x8c, 0xec, 0x4a, 0x42, 0xf2, 0x6e, 0xdc, 0xee, 0x91, 0x32, 0xf2,
+    0x8c, 0xed, 0xcd, 0x09, 0x86, 0xe5, 0x23, 0x16, 0x3f, 0x21, 0x19, 0xd0, 0xa0, 0x42, 0xa3, 0x09,
+    0x0a, 0x76, 0x54, 0xaa, 0x5f, 0x93, 0xbe, 0xfb, 0x98, 0x08, 0x4c, 0xc4, 0xc0, 0x04, 0x58, 0x55,
+    0xcd, 0xce, 0xe9, 0x51, 0x59, 0xc8, 0xae, 0xa3, 0x33, 0x77, 0x07, 0xb9, 0xa0, 0x64, 0x83, 0x49,
+    0x62, 0x32, 0x78, 0x70, 0x2e, 0xe2, 0xee, 0xae, 0x2c, 0x20, 0xef, 0x8e, 0x83, 0x59, 0x92, 0x88,
+    0x1e, 0x91, 0xe0, 0x6d, 0x3f, 0x62, 0x81, 0x88, 0xad, 0xbe, 0xc2, 0xbe, 0x97, 0x38, 0x1b, 0xf5,
+    0x6e, 0xf3, 0x27, 0x79, 0x8c, 0xc1, 0x53, 0x2e, 0x4b, 0x96, 0xdc, 0xcb, 0x85, 0x2a, 0xee, 0xaf,
+    0xa9, 0x6d, 0x9d, 0x47, 0x65, 0x26, 0x2e, 0x49, 0x12, 0x06, 0x2a, 0x51, 0x20, 0x34, 0x24, 0x2e,
+    0x4e, 0x4a, 0x4a, 0xaf, 0x81, 0x4a, 0x3f, 0x3d, 0xf4, 0x34, 0x7a, 0xab, 0x5d, 0x32, 0x0f, 0xd5,
+    0x1f, 0xfb, 0x9f, 0xb9, 0x4d, 0x44, 0xec, 0x32, 0x5e, 0xb0, 0x80, 0xd7, 0xd1, 0x66, 0x68, 0x38,
+];
+
+pub(super) fn check_encodings(pieces: &[Piece]) -> Result<(), SelfTestFailed> {
+    check(pieces, &ENCODING)
+}
+
+pub(super) fn check_decodings(pieces: &[Piece]) -> Result<(), SelfTestFailed> {
+    check(pieces, &INPUT)
+}
+
+fn check(pieces: &[Piece], expected: &Piece) -> Result<(), SelfTestFailed> {
+    if pieces.iter().all(|piece| piece == expected) {
+        Ok(())
+    } else {
+        Err(SelfTestFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!(check_encodings(&[ENCODING, ENCODING]), Ok(()));
+        assert_eq!(check_encodings(&[ENCODING, INPUT]), Err(SelfTestFailed));
+        assert_eq!(check_decodings(&[INPUT]), Ok(()));
+        assert_eq!(check_decodings(&[ENCODING]), Err(SelfTestFailed));
+    }
+}
@@ -1,4 +1,11 @@
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleRootBuilder;
+use crate::por::iv::IvDerivation;
+use crate::por::self_test;
 use crate::por::utils;
+use crate::round_keys::RoundKeys;
+use crate::utils::SelfTestFailed;
 use crate::Block;
 use crate::Piece;
 use crate::BLOCK_SIZE;
@@ -7,8 +14,9 @@ use aes_soft::block_cipher_trait::generic_array::typenum::{U16, U8};
 use aes_soft::block_cipher_trait::generic_array::GenericArray;
 use aes_soft::block_cipher_trait::BlockCipher;
 use aes_soft::Aes128;
-use std::io::Write;
-use std::mem;
+use core::mem;
+use core::ops::Range;
+use zeroize::Zeroize;
 
 pub type Block128x8 = GenericArray<GenericArray<u8, U16>, U8>;
 
@@ -21,8 +29,28 @@ impl SoftwareBitSlicingKeys {
         let cipher = Aes128::new(GenericArray::from_slice(id));
         Self { cipher }
     }
+
+    /// `aes-soft` doesn't accept expanded keys, so they are expanded again from the id
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        Self::new(round_keys.id())
+    }
+}
+
+impl Drop for SoftwareBitSlicingKeys {
+    fn drop(&mut self) {
+        // Safety: `Aes128` is plain data (bit-sliced round keys) without pointers or drop glue, so
+        // it is fine to overwrite its bytes right before it is dropped
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(
+                &mut self.cipher as *mut Aes128 as *mut u8,
+                mem::size_of::<Aes128>(),
+            )
+        };
+        bytes.zeroize();
+    }
 }
 
+#[derive(Default)]
 pub struct SoftwareBitSlicing;
 
 impl SoftwareBitSlicing {
@@ -30,6 +58,30 @@ impl SoftwareBitSlicing {
         Self {}
     }
 
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = SoftwareBitSlicingKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+
+        let mut pieces = [self_test::INPUT; 8];
+        self.encode(
+            &mut pieces,
+            &keys,
+            [self_test::IV; 8],
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_encodings(&pieces)?;
+
+        self.decode(
+            &mut pieces,
+            &keys,
+            [&self_test::IV; 8],
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_decodings(&pieces)
+    }
+
     /// Proof of replication encoding purely in software (using bit slicing approach)
     pub fn encode(
         &self,
@@ -40,10 +92,47 @@ impl SoftwareBitSlicing {
         breadth_iterations: usize,
     ) {
         for _ in 0..breadth_iterations {
-            ivs = encode_internal(pieces, &keys.cipher, ivs, aes_iterations);
+            ivs = encode_internal(pieces, &keys.cipher, ivs, aes_iterations, None);
         }
     }
 
+    /// Same as `encode()`, also returns roots of Merkle trees over blocks of encoded pieces (see
+    /// `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so pieces
+    /// don't need to be read again
+    pub fn seal(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &SoftwareBitSlicingKeys,
+        ivs: [Block; 8],
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> [Hash; 8] {
+        utils::seal(hasher, ivs, breadth_iterations, |ivs, builders| {
+            encode_internal(pieces, &keys.cipher, ivs, aes_iterations, builders)
+        })
+    }
+
+    /// Same as `encode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_indexed(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &SoftwareBitSlicingKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode(
+            pieces,
+            keys,
+            iv_derivation.piece_ivs(first_index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
     /// Proof of replication decoding purely in software (using bit slicing approach)
     pub fn decode(
         &self,
@@ -59,6 +148,67 @@ impl SoftwareBitSlicing {
 
         decode_internal(pieces, &keys.cipher, Some(ivs), aes_iterations);
     }
+
+    /// Same as `decode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn decode_indexed(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &SoftwareBitSlicingKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let ivs = iv_derivation.piece_ivs::<8>(first_index);
+        self.decode(
+            pieces,
+            keys,
+            ivs.each_ref(),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Decodes only blocks in `block_range` of every piece and blocks they depend on (see
+    /// `DependencyCone`), the rest of the pieces is left in unspecified state
+    pub fn decode_range(
+        &self,
+        pieces: &mut [Piece; 8],
+        keys: &SoftwareBitSlicingKeys,
+        ivs: [&Block; 8],
+        block_range: Range<usize>,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut blocks_generic_array = Block128x8::default();
+
+        utils::decode_range(block_range, breadth_iterations, |blocks, feedback| {
+            for index in blocks.clone().rev() {
+                let feedback = if index == blocks.start {
+                    feedback
+                } else {
+                    utils::Feedback::Block(index - 1)
+                };
+                let mut feedbacks = [Block::default(); 8];
+                for ((feedback_block, piece), iv) in
+                    feedbacks.iter_mut().zip(pieces.iter()).zip(ivs.iter())
+                {
+                    *feedback_block = feedback.resolve(piece, iv);
+                }
+
+                decode_8_blocks_internal(
+                    &keys.cipher,
+                    pieces
+                        .each_mut()
+                        .map(|piece| &mut piece[index * BLOCK_SIZE..][..BLOCK_SIZE]),
+                    feedbacks.each_ref(),
+                    aes_iterations,
+                    &mut blocks_generic_array,
+                );
+            }
+        });
+    }
 }
 
 fn encode_internal(
@@ -66,6 +216,7 @@ fn encode_internal(
     cipher: &Aes128,
     mut ivs: [Block; 8],
     aes_iterations: usize,
+    mut builders: Option<&mut [MerkleRootBuilder; 8]>,
 ) -> [Block; 8] {
     let [piece0, piece1, piece2, piece3, piece4, piece5, piece6, piece7] = pieces;
 
@@ -108,8 +259,13 @@ fn encode_internal(
             ivs.iter_mut()
                 .zip(blocks.iter())
                 .for_each(|(feedback, block)| {
-                    feedback.as_mut().write_all(block).unwrap();
+                    feedback.copy_from_slice(block);
                 });
+            if let Some(builders) = &mut builders {
+                for (builder, block) in builders.iter_mut().zip(blocks.iter()) {
+                    builder.push(block);
+                }
+            }
         });
 
     ivs
@@ -232,6 +388,7 @@ fn swap_blocks(generic_blocks: &mut Block128x8, slice_blocks: &mut [&mut [u8]; 8
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle::MerkleTree;
     use crate::por::test_data::CORRECT_ENCODING;
     use crate::por::test_data::CORRECT_ENCODING_BREADTH_10;
     use crate::por::test_data::ID;
@@ -338,4 +495,25 @@ mod tests {
             assert_eq!(decoding.to_vec(), input.to_vec());
         }
     }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(SoftwareBitSlicing::new().self_test(), Ok(()));
+    }
+
+    /// Sealing produces the same encoding as `encode()` and the same root as hashing it afterwards
+    #[test]
+    fn test_seal() {
+        let keys = SoftwareBitSlicingKeys::new(&ID);
+        let por = SoftwareBitSlicing::new();
+        let hasher = AesHash::new();
+        let root = MerkleTree::from_piece_blocks(hasher, &CORRECT_ENCODING_BREADTH_10).root();
+
+        let mut encodings = [INPUT; 8];
+        let roots = por.seal(&mut encodings, &keys, [IV; 8], &hasher, 256, 10);
+        for (encoding, piece_root) in encodings.iter().zip(roots.iter()) {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+            assert_eq!(*piece_root, root);
+        }
+    }
 }
@@ -0,0 +1,594 @@
+use crate::aes_low_level::fixslice;
+use crate::aes_low_level::fixslice::State;
+use crate::aes_low_level::fixslice::LANES;
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleRootBuilder;
+use crate::por::iv::IvDerivation;
+use crate::por::self_test;
+use crate::por::utils;
+use crate::round_keys::RoundKeys;
+use crate::utils::SelfTestFailed;
+use crate::Block;
+use crate::Piece;
+use crate::BLOCK_SIZE;
+use crate::PIECE_SIZE;
+use core::ops::Range;
+use zeroize::Zeroize;
+
+/// Round keys of either AES-128 or AES-256, only the first `rounds + 1` of them are used
+pub struct SoftwareFixSlicingKeys {
+    keys_enc: [State; 15],
+    keys_dec: [State; 15],
+    rounds: usize,
+}
+
+impl SoftwareFixSlicingKeys {
+    /// AES-128 keys, key expansion is constant-time as well
+    pub fn new(id: &Block) -> Self {
+        let (mut round_keys_enc, mut round_keys_dec) = fixslice::expand_keys_128(id);
+        let keys = Self::from_keys(
+            &fixslice::enc_keys(&round_keys_enc),
+            &fixslice::dec_keys(&round_keys_dec),
+        );
+        round_keys_enc.zeroize();
+        round_keys_dec.zeroize();
+
+        keys
+    }
+
+    /// AES-256 keys, encoding with them is not compatible with other backends that only support
+    /// AES-128
+    pub fn new_256(key: &[u8; 32]) -> Self {
+        let (mut round_keys_enc, mut round_keys_dec) = fixslice::expand_keys_256(key);
+        let keys = Self::from_keys(
+            &fixslice::enc_keys(&round_keys_enc),
+            &fixslice::dec_keys(&round_keys_dec),
+        );
+        round_keys_enc.zeroize();
+        round_keys_dec.zeroize();
+
+        keys
+    }
+
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        Self::from_keys(
+            &fixslice::enc_keys(round_keys.enc()),
+            &fixslice::dec_keys(round_keys.dec()),
+        )
+    }
+
+    fn from_keys<const N: usize>(keys_enc: &[State; N], keys_dec: &[State; N]) -> Self {
+        let mut keys = Self {
+            keys_enc: [[0u64; 8]; 15],
+            keys_dec: [[0u64; 8]; 15],
+            rounds: N - 1,
+        };
+        keys.keys_enc[..N].copy_from_slice(keys_enc);
+        keys.keys_dec[..N].copy_from_slice(keys_dec);
+
+        keys
+    }
+
+    fn enc(&self) -> &[State] {
+        &self.keys_enc[..=self.rounds]
+    }
+
+    fn dec(&self) -> &[State] {
+        &self.keys_dec[..=self.rounds]
+    }
+}
+
+impl Drop for SoftwareFixSlicingKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+#[derive(Default)]
+pub struct SoftwareFixSlicing;
+
+impl SoftwareFixSlicing {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = SoftwareFixSlicingKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+
+        let mut piece = self_test::INPUT;
+        self.encode(
+            &mut piece,
+            &keys,
+            self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        let mut pieces = [self_test::INPUT; 4];
+        self.encode_x4(
+            &mut pieces,
+            &keys,
+            [self_test::IV; 4],
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_encodings(&[piece])?;
+        self_test::check_encodings(&pieces)?;
+
+        self.decode(
+            &mut piece,
+            &keys,
+            &self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_decodings(&[piece])
+    }
+
+    /// Proof of replication encoding purely in software (using constant-time fixsliced AES)
+    pub fn encode(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareFixSlicingKeys,
+        iv: Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut ivs = [iv];
+        for _ in 0..breadth_iterations {
+            ivs = encode_internal(
+                core::array::from_mut(piece),
+                keys.enc(),
+                ivs,
+                aes_iterations,
+                None,
+            );
+        }
+    }
+
+    /// Same as `encode()`, also returns root of Merkle tree over blocks of the encoded piece
+    /// (see `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so the
+    /// piece doesn't need to be read again
+    pub fn seal(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareFixSlicingKeys,
+        iv: Block,
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Hash {
+        let [root] = utils::seal(hasher, [iv], breadth_iterations, |ivs, builders| {
+            encode_internal(
+                core::array::from_mut(piece),
+                keys.enc(),
+                ivs,
+                aes_iterations,
+                builders,
+            )
+        });
+        root
+    }
+
+    /// Same as `encode()`, IV is derived from piece index
+    pub fn encode_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareFixSlicingKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode(
+            piece,
+            keys,
+            iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Proof of replication encoding of 4 pieces at once (one piece per lane, each with its own
+    /// IV) purely in software (using constant-time fixsliced AES)
+    pub fn encode_x4(
+        &self,
+        pieces: &mut [Piece; 4],
+        keys: &SoftwareFixSlicingKeys,
+        mut ivs: [Block; 4],
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        for _ in 0..breadth_iterations {
+            ivs = encode_internal(pieces, keys.enc(), ivs, aes_iterations, None);
+        }
+    }
+
+    /// Same as `encode_x4()`, also returns roots of Merkle trees over blocks of encoded pieces (see
+    /// `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so pieces
+    /// don't need to be read again
+    pub fn seal_x4(
+        &self,
+        pieces: &mut [Piece; 4],
+        keys: &SoftwareFixSlicingKeys,
+        ivs: [Block; 4],
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> [Hash; 4] {
+        utils::seal(hasher, ivs, breadth_iterations, |ivs, builders| {
+            encode_internal(pieces, keys.enc(), ivs, aes_iterations, builders)
+        })
+    }
+
+    /// Same as `encode_x4()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_x4_indexed(
+        &self,
+        pieces: &mut [Piece; 4],
+        keys: &SoftwareFixSlicingKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode_x4(
+            pieces,
+            keys,
+            iv_derivation.piece_ivs(first_index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Proof of replication decoding purely in software (using constant-time fixsliced AES, 4
+    /// blocks of the piece at a time)
+    pub fn decode(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareFixSlicingKeys,
+        iv: &Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        for _ in 1..breadth_iterations {
+            decode_internal(piece, keys.dec(), None, aes_iterations);
+        }
+
+        decode_internal(piece, keys.dec(), Some(iv), aes_iterations);
+    }
+
+    /// Same as `decode()`, IV is derived from piece index
+    pub fn decode_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareFixSlicingKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.decode(
+            piece,
+            keys,
+            &iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Decodes only blocks in `block_range` and blocks they depend on (see `DependencyCone`),
+    /// the rest of the piece is left in unspecified state
+    pub fn decode_range(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareFixSlicingKeys,
+        iv: &Block,
+        block_range: Range<usize>,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        utils::decode_range(block_range, breadth_iterations, |blocks, feedback| {
+            utils::decode_blocks_batched::<LANES, _>(
+                piece,
+                blocks,
+                feedback,
+                iv,
+                |blocks, feedback| {
+                    decode_blocks_internal(keys.dec(), blocks, feedback, aes_iterations);
+                },
+            );
+        });
+    }
+}
+
+/// Encodes up to `LANES` pieces, unused lanes are filled with zeroes
+fn encode_internal<const N: usize>(
+    pieces: &mut [Piece; N],
+    keys: &[State],
+    mut ivs: [Block; N],
+    aes_iterations: usize,
+    mut builders: Option<&mut [MerkleRootBuilder; N]>,
+) -> [Block; N] {
+    for offset in (0..PIECE_SIZE).step_by(BLOCK_SIZE) {
+        let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+        for ((block, piece), iv) in blocks.iter_mut().zip(pieces.iter()).zip(ivs.iter()) {
+            block
+                .iter_mut()
+                .zip(&piece[offset..(offset + BLOCK_SIZE)])
+                .zip(iv)
+                .for_each(|((block_byte, piece_byte), feedback_byte)| {
+                    *block_byte = piece_byte ^ feedback_byte;
+                });
+        }
+
+        let mut state = fixslice::bitslice(&blocks);
+        for _ in 0..aes_iterations {
+            fixslice::encrypt(keys, &mut state);
+        }
+        let blocks = fixslice::un_bitslice(&state);
+
+        for ((piece, iv), block) in pieces.iter_mut().zip(ivs.iter_mut()).zip(blocks.iter()) {
+            piece[offset..(offset + BLOCK_SIZE)].copy_from_slice(block);
+            *iv = *block;
+        }
+        if let Some(builders) = &mut builders {
+            for (builder, block) in builders.iter_mut().zip(blocks.iter()) {
+                builder.push(block);
+            }
+        }
+    }
+
+    ivs
+}
+
+fn decode_internal(piece: &mut Piece, keys: &[State], iv: Option<&Block>, aes_iterations: usize) {
+    for i in (1..(PIECE_SIZE / BLOCK_SIZE / LANES)).rev() {
+        let (blocks, feedback) = utils::piece_to_blocks_and_feedback(piece, i, LANES);
+
+        decode_blocks_internal(keys, blocks, feedback, aes_iterations);
+    }
+
+    let (first_blocks, feedback) = utils::piece_to_first_blocks_and_feedback(piece, iv, LANES);
+    decode_blocks_internal(keys, first_blocks, feedback, aes_iterations);
+}
+
+/// Decodes `LANES` consecutive blocks at once
+fn decode_blocks_internal(
+    keys: &[State],
+    blocks: &mut [u8],
+    feedback: &Block,
+    aes_iterations: usize,
+) {
+    let mut encoded = [[0u8; BLOCK_SIZE]; LANES];
+    for (encoded, block) in encoded.iter_mut().zip(blocks.chunks_exact(BLOCK_SIZE)) {
+        encoded.copy_from_slice(block);
+    }
+
+    let mut state = fixslice::bitslice(&encoded);
+    for _ in 0..aes_iterations {
+        fixslice::decrypt(keys, &mut state);
+    }
+    let decoded = fixslice::un_bitslice(&state);
+
+    let feedbacks = core::iter::once(feedback).chain(encoded.iter());
+    for ((block, decoded), feedback) in blocks
+        .chunks_exact_mut(BLOCK_SIZE)
+        .zip(decoded.iter())
+        .zip(feedbacks)
+    {
+        block
+            .iter_mut()
+            .zip(decoded.iter().zip(feedback.iter()))
+            .for_each(|(block_byte, (decoded_byte, feedback_byte))| {
+                *block_byte = decoded_byte ^ feedback_byte;
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::MerkleTree;
+    use crate::por::software_lut::SoftwareLuT;
+    use crate::por::software_lut::SoftwareLuTKeys;
+    use crate::por::test_data::CORRECT_ENCODING;
+    use crate::por::test_data::CORRECT_ENCODING_BREADTH_10;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use core::convert::TryInto;
+    use rand::Rng;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    fn test() {
+        let aes_iterations = 256;
+
+        let keys = SoftwareFixSlicingKeys::new(&ID);
+        let por = SoftwareFixSlicing::new();
+
+        let mut encoding = INPUT;
+        por.encode(&mut encoding, &keys, IV, aes_iterations, 1);
+
+        assert_eq!(encoding.to_vec(), CORRECT_ENCODING.to_vec());
+
+        let mut encodings = [INPUT; 4];
+        por.encode_x4(&mut encodings, &keys, [IV; 4], aes_iterations, 1);
+
+        for encoding in encodings.iter() {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING.to_vec());
+        }
+
+        let mut decoding = CORRECT_ENCODING;
+        por.decode(&mut decoding, &keys, &IV, aes_iterations, 1);
+
+        assert_eq!(decoding.to_vec(), INPUT.to_vec());
+    }
+
+    #[test]
+    fn test_breadth_10() {
+        let aes_iterations = 256;
+
+        let keys = SoftwareFixSlicingKeys::from_round_keys(&RoundKeys::new(&ID));
+        let por = SoftwareFixSlicing::new();
+
+        let mut encoding = INPUT;
+        por.encode(&mut encoding, &keys, IV, aes_iterations, 10);
+
+        assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+
+        let mut decoding = CORRECT_ENCODING_BREADTH_10;
+        por.decode(&mut decoding, &keys, &IV, aes_iterations, 10);
+
+        assert_eq!(decoding.to_vec(), INPUT.to_vec());
+    }
+
+    #[test]
+    fn test_x4_random() {
+        let aes_iterations = 16;
+
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill(&mut id[..]);
+
+        let mut inputs = [[0u8; PIECE_SIZE]; 4];
+        let mut ivs = [[0u8; 16]; 4];
+        for (input, iv) in inputs.iter_mut().zip(ivs.iter_mut()) {
+            rand::thread_rng().fill(&mut input[..]);
+            rand::thread_rng().fill(&mut iv[..]);
+        }
+
+        let keys = SoftwareFixSlicingKeys::new(&id);
+        let por = SoftwareFixSlicing::new();
+        let keys_lut = SoftwareLuTKeys::new(&id);
+        let por_lut = SoftwareLuT::new();
+
+        let mut encodings = inputs;
+        por.encode_x4(&mut encodings, &keys, ivs, aes_iterations, 2);
+
+        for ((encoding, input), iv) in encodings.iter().zip(inputs.iter()).zip(ivs.iter()) {
+            let mut expected = *input;
+            por_lut.encode(&mut expected, &keys_lut, *iv, aes_iterations, 2);
+            assert_eq!(encoding.to_vec(), expected.to_vec());
+
+            let mut single = *input;
+            por.encode(&mut single, &keys, *iv, aes_iterations, 2);
+            assert_eq!(encoding.to_vec(), single.to_vec());
+
+            let mut decoding = *encoding;
+            por.decode(&mut decoding, &keys, iv, aes_iterations, 2);
+            assert_eq!(decoding.to_vec(), input.to_vec());
+        }
+    }
+
+    /// Sanity check that neither key nor data affect how long encoding and decoding take. Samples
+    /// of all cases are interleaved and their medians are compared with a generous ratio, so that
+    /// noise from other tests running in parallel doesn't make it fail
+    #[test]
+    fn test_timing() {
+        const SAMPLES: usize = 51;
+        let aes_iterations = 4;
+
+        let mut random_id = [0u8; 16];
+        rand::thread_rng().fill(&mut random_id[..]);
+        let mut random_input = [0u8; PIECE_SIZE];
+        rand::thread_rng().fill(&mut random_input[..]);
+
+        let por = SoftwareFixSlicing::new();
+        let cases = [
+            (SoftwareFixSlicingKeys::new(&[0u8; 16]), [0u8; PIECE_SIZE]),
+            (SoftwareFixSlicingKeys::new(&[0xff; 16]), [0xff; PIECE_SIZE]),
+            (SoftwareFixSlicingKeys::new(&random_id), random_input),
+        ];
+
+        let mut durations = [[Duration::default(); SAMPLES]; 3];
+        for sample in 0..SAMPLES {
+            for ((keys, input), durations) in cases.iter().zip(durations.iter_mut()) {
+                let mut piece = *input;
+                let start = Instant::now();
+                por.encode(&mut piece, keys, [0u8; 16], aes_iterations, 1);
+                por.decode(&mut piece, keys, &[0u8; 16], aes_iterations, 1);
+                durations[sample] = start.elapsed();
+            }
+        }
+
+        let medians = durations.map(|mut durations| {
+            durations.sort_unstable();
+            durations[SAMPLES / 2]
+        });
+        let fastest = medians.iter().min().unwrap().as_secs_f64();
+        let slowest = medians.iter().max().unwrap().as_secs_f64();
+        assert!(
+            slowest / fastest < 2.0,
+            "Timing depends on input: {:?}",
+            medians,
+        );
+    }
+
+    #[test]
+    fn test_aes_256() {
+        // FIPS-197 Appendix C.3
+        let key: [u8; 32] = core::array::from_fn(|byte| byte as u8);
+        let plaintext: Block = core::array::from_fn(|byte| (byte * 0x11) as u8);
+        let ciphertext: Block = [
+            0x8e, 0xa2, 0xb7, 0xca, 0x51, 0x67, 0x45, 0xbf, 0xea, 0xfc, 0x49, 0x90, 0x4b, 0x49,
+            0x60, 0x89,
+        ];
+
+        let keys = SoftwareFixSlicingKeys::new_256(&key);
+        let por = SoftwareFixSlicing::new();
+
+        // With zero IV and single AES iteration the first block is just encrypted
+        let mut input = INPUT;
+        input[..BLOCK_SIZE].copy_from_slice(&plaintext);
+        let mut encoding = input;
+        por.encode(&mut encoding, &keys, [0u8; 16], 1, 1);
+        assert_eq!(encoding[..BLOCK_SIZE], ciphertext);
+
+        let mut encodings = [input; 4];
+        por.encode_x4(&mut encodings, &keys, [IV; 4], 16, 2);
+        let mut decoding = encodings[0];
+        por.decode(&mut decoding, &keys, &IV, 16, 2);
+        assert_eq!(decoding.to_vec(), input.to_vec());
+
+        // AES-256 encoding differs from AES-128 encoding with the first half of the key
+        let mut encoding_128 = input;
+        por.encode(
+            &mut encoding_128,
+            &SoftwareFixSlicingKeys::new(key[..16].try_into().unwrap()),
+            IV,
+            16,
+            2,
+        );
+        assert_ne!(encoding_128.to_vec(), encodings[0].to_vec());
+    }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(SoftwareFixSlicing::new().self_test(), Ok(()));
+    }
+
+    /// Sealing produces the same encoding as `encode()` and the same root as hashing it afterwards
+    #[test]
+    fn test_seal() {
+        let keys = SoftwareFixSlicingKeys::new(&ID);
+        let por = SoftwareFixSlicing::new();
+        let hasher = AesHash::new();
+        let root = MerkleTree::from_piece_blocks(hasher, &CORRECT_ENCODING_BREADTH_10).root();
+
+        let mut encoding = INPUT;
+        let piece_root = por.seal(&mut encoding, &keys, IV, &hasher, 256, 10);
+        assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+        assert_eq!(piece_root, root);
+
+        let mut encodings = [INPUT; 4];
+        let roots = por.seal_x4(&mut encodings, &keys, [IV; 4], &hasher, 256, 10);
+        for (encoding, piece_root) in encodings.iter().zip(roots.iter()) {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+            assert_eq!(*piece_root, root);
+        }
+    }
+}
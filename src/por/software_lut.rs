@@ -1,10 +1,18 @@
+use crate::aes_low_level::software;
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleRootBuilder;
+use crate::por::iv::IvDerivation;
+use crate::por::self_test;
 use crate::por::utils;
+use crate::round_keys::RoundKeys;
+use crate::utils::SelfTestFailed;
 use crate::Block;
 use crate::Piece;
 use crate::BLOCK_SIZE;
 use crate::PIECE_SIZE;
-use aes_frast::aes_core;
-use std::io::Write;
+use core::ops::Range;
+use zeroize::Zeroize;
 
 pub struct SoftwareLuTKeys {
     keys_enc: [u32; 44],
@@ -14,14 +22,29 @@ pub struct SoftwareLuTKeys {
 impl SoftwareLuTKeys {
     pub fn new(id: &Block) -> Self {
         let mut keys_enc: [u32; 44] = [0u32; 44];
-        aes_core::setkey_enc_k128(id, &mut keys_enc);
+        software::setkey_enc_k128(id, &mut keys_enc);
         let mut keys_dec: [u32; 44] = [0u32; 44];
-        aes_core::setkey_dec_k128(id, &mut keys_dec);
+        software::setkey_dec_k128(id, &mut keys_dec);
+
+        Self { keys_enc, keys_dec }
+    }
+
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let keys_enc = software::round_keys_to_words(round_keys.enc());
+        let keys_dec = software::round_keys_to_words(round_keys.dec());
 
         Self { keys_enc, keys_dec }
     }
 }
 
+impl Drop for SoftwareLuTKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+#[derive(Default)]
 pub struct SoftwareLuT;
 
 impl SoftwareLuT {
@@ -29,6 +52,30 @@ impl SoftwareLuT {
         Self {}
     }
 
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = SoftwareLuTKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+
+        let mut piece = self_test::INPUT;
+        self.encode(
+            &mut piece,
+            &keys,
+            self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_encodings(&[piece])?;
+
+        self.decode(
+            &mut piece,
+            &keys,
+            &self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_decodings(&[piece])
+    }
+
     /// Proof of replication encoding purely in software (using look-up table approach)
     pub fn encode(
         &self,
@@ -39,10 +86,53 @@ impl SoftwareLuT {
         breadth_iterations: usize,
     ) {
         for _ in 0..breadth_iterations {
-            iv = encode_internal(piece, &keys.keys_enc, iv, aes_iterations);
+            iv = encode_internal(piece, &keys.keys_enc, iv, aes_iterations, None);
         }
     }
 
+    /// Same as `encode()`, IV is derived from piece index
+    pub fn encode_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareLuTKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode(
+            piece,
+            keys,
+            iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Same as `encode()`, also returns root of Merkle tree over blocks of the encoded piece
+    /// (see `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so the
+    /// piece doesn't need to be read again
+    pub fn seal(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareLuTKeys,
+        iv: Block,
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Hash {
+        let [root] = utils::seal(hasher, [iv], breadth_iterations, |[iv], builders| {
+            [encode_internal(
+                piece,
+                &keys.keys_enc,
+                iv,
+                aes_iterations,
+                builders.map(|[builder]| builder),
+            )]
+        });
+        root
+    }
+
     /// Proof of replication decoding purely in software (using look-up table approach)
     pub fn decode(
         &self,
@@ -58,6 +148,45 @@ impl SoftwareLuT {
 
         decode_internal(piece, &keys.keys_dec, Some(iv), aes_iterations);
     }
+
+    /// Same as `decode()`, IV is derived from piece index
+    pub fn decode_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareLuTKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.decode(
+            piece,
+            keys,
+            &iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Decodes only blocks in `block_range` and blocks they depend on (see `DependencyCone`),
+    /// the rest of the piece is left in unspecified state
+    pub fn decode_range(
+        &self,
+        piece: &mut Piece,
+        keys: &SoftwareLuTKeys,
+        iv: &Block,
+        block_range: Range<usize>,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        let mut tmp: Block = [0u8; 16];
+        utils::decode_range(block_range, breadth_iterations, |blocks, feedback| {
+            utils::decode_blocks_batched::<1, _>(piece, blocks, feedback, iv, |block, feedback| {
+                decode_block_internal(&keys.keys_dec, block, feedback, aes_iterations, &mut tmp);
+            });
+        });
+        tmp.zeroize();
+    }
 }
 
 fn encode_internal(
@@ -65,6 +194,7 @@ fn encode_internal(
     keys: &[u32; 44],
     mut iv: Block,
     aes_iterations: usize,
+    mut builder: Option<&mut MerkleRootBuilder>,
 ) -> Block {
     piece.chunks_exact_mut(BLOCK_SIZE).for_each(|block| {
         block
@@ -75,9 +205,13 @@ fn encode_internal(
             });
 
         for _ in 0..aes_iterations {
-            // TODO: This needs to be in-place in aes_frast crate
-            aes_core::block_enc_k128(&block, &mut iv, keys);
-            block.as_mut().write_all(&iv).unwrap();
+            // TODO: This needs to be in-place
+            software::block_enc_k128(block, &mut iv, keys);
+            block.copy_from_slice(&iv);
+        }
+
+        if let Some(builder) = &mut builder {
+            builder.push(block);
         }
     });
 
@@ -94,7 +228,10 @@ fn decode_internal(piece: &mut Piece, keys: &[u32; 44], iv: Option<&Block>, aes_
     }
 
     let (first_block, feedback) = utils::piece_to_first_blocks_and_feedback(piece, iv, 1);
-    decode_block_internal(keys, first_block, &feedback, aes_iterations, &mut tmp);
+    decode_block_internal(keys, first_block, feedback, aes_iterations, &mut tmp);
+
+    // Cleared once per piece rather than per block to keep the hot loop intact
+    tmp.zeroize();
 }
 
 fn decode_block_internal(
@@ -105,9 +242,9 @@ fn decode_block_internal(
     tmp: &mut Block,
 ) {
     for _ in 0..aes_iterations {
-        // TODO: This needs to be in-place in aes_frast crate
-        aes_core::block_dec_k128(&block, tmp, keys);
-        block.as_mut().write_all(tmp).unwrap();
+        // TODO: This needs to be in-place
+        software::block_dec_k128(block, tmp, keys);
+        block.copy_from_slice(tmp);
     }
 
     block
@@ -121,12 +258,14 @@ fn decode_block_internal(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle::MerkleTree;
     use crate::por::test_data::CORRECT_ENCODING;
     use crate::por::test_data::CORRECT_ENCODING_BREADTH_10;
     use crate::por::test_data::ID;
     use crate::por::test_data::INPUT;
     use crate::por::test_data::IV;
     use crate::PIECE_SIZE;
+    use core::mem::ManuallyDrop;
     use rand::Rng;
 
     #[test]
@@ -214,4 +353,32 @@ mod tests {
 
         assert_eq!(decoding.to_vec(), input.to_vec());
     }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        let mut keys = ManuallyDrop::new(SoftwareLuTKeys::new(&ID));
+        unsafe { ManuallyDrop::drop(&mut keys) };
+
+        assert_eq!(keys.keys_enc, [0u32; 44]);
+        assert_eq!(keys.keys_dec, [0u32; 44]);
+    }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(SoftwareLuT::new().self_test(), Ok(()));
+    }
+
+    /// Sealing produces the same encoding as `encode()` and the same root as hashing it afterwards
+    #[test]
+    fn test_seal() {
+        let keys = SoftwareLuTKeys::new(&ID);
+        let por = SoftwareLuT::new();
+        let hasher = AesHash::new();
+        let root = MerkleTree::from_piece_blocks(hasher, &CORRECT_ENCODING_BREADTH_10).root();
+
+        let mut encoding = INPUT;
+        let piece_root = por.seal(&mut encoding, &keys, IV, &hasher, 256, 10);
+        assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+        assert_eq!(piece_root, root);
+    }
 }
@@ -0,0 +1,463 @@
+//! Streaming proof of replication encoding of arbitrary byte streams.
+//!
+//! Input is split into pieces, the last piece is padded with `0x80` byte followed by zeroes
+//! (ISO/IEC 7816-4 padding), padding is always present, so input that is a multiple of piece size
+//! gets an extra piece of padding. This way original length is recorded in the replica itself and
+//! decoding restores exact original bytes.
+
+use crate::por::dispatcher::Dispatcher;
+use crate::round_keys::RoundKeys;
+use crate::Block;
+use crate::Piece;
+use crate::PIECE_SIZE;
+use std::io;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+
+/// Number of pieces encoded/decoded at once by default
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// The first byte of padding, the rest of padding are zeroes
+const PADDING_START: u8 = 0x80;
+
+/// IV of piece `index` is `iv + index` (as big-endian 128-bit integer)
+pub fn piece_iv(iv: &Block, index: u64) -> Block {
+    u128::from_be_bytes(*iv)
+        .wrapping_add(u128::from(index))
+        .to_be_bytes()
+}
+
+/// Result of encoding/decoding a stream
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct StreamSummary {
+    /// Size of original (not padded) data in bytes
+    pub size: u64,
+    /// Number of pieces in replica, including padding
+    pub pieces: u64,
+}
+
+struct Stream {
+    dispatcher: Dispatcher,
+    round_keys: RoundKeys,
+    iv: Block,
+    aes_iterations: usize,
+    breadth_iterations: usize,
+    batch_size: usize,
+}
+
+impl Stream {
+    fn ivs(&self, first_index: u64, count: usize) -> Vec<Block> {
+        (first_index..)
+            .take(count)
+            .map(|index| piece_iv(&self.iv, index))
+            .collect()
+    }
+}
+
+/// Encodes any `Read` source into replica written to `Write` sink
+pub struct StreamEncoder {
+    stream: Stream,
+}
+
+impl StreamEncoder {
+    /// Piece `i` of replica is encoded with IV `piece_iv(iv, i)`
+    pub fn new(
+        dispatcher: Dispatcher,
+        round_keys: RoundKeys,
+        iv: Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Self {
+        Self {
+            stream: Stream {
+                dispatcher,
+                round_keys,
+                iv,
+                aes_iterations,
+                breadth_iterations,
+                batch_size: DEFAULT_BATCH_SIZE,
+            },
+        }
+    }
+
+    /// Number of pieces encoded at once, larger batches use more memory, but make better use of
+    /// parallelism
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0);
+        self.stream.batch_size = batch_size;
+        self
+    }
+
+    /// Dispatcher used for encoding
+    pub fn dispatcher(&self) -> &Dispatcher {
+        &self.stream.dispatcher
+    }
+
+    /// Encodes the whole `input` until EOF, padding the last piece
+    pub fn encode<R: Read, W: Write>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+    ) -> io::Result<StreamSummary> {
+        let stream = &mut self.stream;
+        let mut pieces = vec![[0u8; PIECE_SIZE]; stream.batch_size];
+        let mut summary = StreamSummary { size: 0, pieces: 0 };
+
+        loop {
+            let size = read_pieces(&mut input, &mut pieces)?;
+            let last_batch = size < pieces.len() * PIECE_SIZE;
+            let pieces = if last_batch {
+                pad(&mut pieces, size)
+            } else {
+                &mut pieces[..]
+            };
+
+            let ivs = stream.ivs(summary.pieces, pieces.len());
+            stream.dispatcher.encode(
+                pieces,
+                &stream.round_keys,
+                &ivs,
+                stream.aes_iterations,
+                stream.breadth_iterations,
+            );
+            write_pieces(&mut output, pieces)?;
+
+            summary.size += size as u64;
+            summary.pieces += pieces.len() as u64;
+
+            if last_batch {
+                output.flush()?;
+                return Ok(summary);
+            }
+        }
+    }
+}
+
+/// Decodes replica from any `Read` source, writing original bytes into `Write` sink
+pub struct StreamDecoder {
+    stream: Stream,
+}
+
+impl StreamDecoder {
+    /// Parameters must be the same as used for `StreamEncoder`
+    pub fn new(
+        dispatcher: Dispatcher,
+        round_keys: RoundKeys,
+        iv: Block,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> Self {
+        Self {
+            stream: Stream {
+                dispatcher,
+                round_keys,
+                iv,
+                aes_iterations,
+                breadth_iterations,
+                batch_size: DEFAULT_BATCH_SIZE,
+            },
+        }
+    }
+
+    /// Number of pieces decoded at once, larger batches use more memory, but make better use of
+    /// parallelism
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0);
+        self.stream.batch_size = batch_size;
+        self
+    }
+
+    /// Dispatcher used for decoding
+    pub fn dispatcher(&self) -> &Dispatcher {
+        &self.stream.dispatcher
+    }
+
+    /// Decodes the whole `input` until EOF and removes padding, returns
+    /// `ErrorKind::InvalidData` error if replica size is not a positive multiple of piece size or
+    /// padding is invalid. Padding check is not an integrity check, wrong parameters or corrupted
+    /// replica are only detected when they affect decoded padding.
+    pub fn decode<R: Read, W: Write>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+    ) -> io::Result<StreamSummary> {
+        let stream = &mut self.stream;
+        let mut pieces = vec![[0u8; PIECE_SIZE]; stream.batch_size];
+        // The last decoded piece is only written once it is known whether it contains padding
+        let mut last_piece: Option<Piece> = None;
+        let mut summary = StreamSummary { size: 0, pieces: 0 };
+
+        loop {
+            let size = read_pieces(&mut input, &mut pieces)?;
+            if size % PIECE_SIZE != 0 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Replica size is not a multiple of piece size",
+                ));
+            }
+            if size == 0 {
+                break;
+            }
+            let pieces = &mut pieces[..(size / PIECE_SIZE)];
+
+            let ivs = stream.ivs(summary.pieces, pieces.len());
+            stream.dispatcher.decode(
+                pieces,
+                &stream.round_keys,
+                &ivs,
+                stream.aes_iterations,
+                stream.breadth_iterations,
+            );
+
+            if let Some(piece) = last_piece.take() {
+                output.write_all(&piece)?;
+            }
+            let (last, pieces) = pieces.split_last().unwrap();
+            write_pieces(&mut output, pieces)?;
+            last_piece.replace(*last);
+
+            summary.size += size as u64;
+            summary.pieces += (pieces.len() + 1) as u64;
+        }
+
+        let last_piece =
+            last_piece.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Replica is empty"))?;
+        let padding_start = padding_start(&last_piece).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                "Invalid padding, replica is corrupted or decoding parameters are wrong",
+            )
+        })?;
+        output.write_all(&last_piece[..padding_start])?;
+        output.flush()?;
+
+        summary.size -= (PIECE_SIZE - padding_start) as u64;
+        Ok(summary)
+    }
+}
+
+/// Pads data of `size` bytes in `pieces` and returns pieces that contain data and padding
+fn pad(pieces: &mut [Piece], size: usize) -> &mut [Piece] {
+    let pieces = &mut pieces[..=(size / PIECE_SIZE)];
+    let last_piece = pieces.last_mut().unwrap();
+    let offset = size % PIECE_SIZE;
+    last_piece[offset] = PADDING_START;
+    last_piece[(offset + 1)..].iter_mut().for_each(|byte| {
+        *byte = 0;
+    });
+
+    pieces
+}
+
+/// Offset of padding in decoded piece, `None` if padding is invalid
+fn padding_start(piece: &Piece) -> Option<usize> {
+    let offset = piece.iter().rposition(|&byte| byte != 0)?;
+    if piece[offset] == PADDING_START {
+        Some(offset)
+    } else {
+        None
+    }
+}
+
+/// Fills pieces with data from `input` until EOF or until all pieces are full, returns number of
+/// bytes read
+fn read_pieces<R: Read>(input: &mut R, pieces: &mut [Piece]) -> io::Result<usize> {
+    let mut size = 0;
+    for piece in pieces.iter_mut() {
+        let mut piece_size = 0;
+        while piece_size < PIECE_SIZE {
+            match input.read(&mut piece[piece_size..]) {
+                Ok(0) => {
+                    return Ok(size + piece_size);
+                }
+                Ok(read) => {
+                    piece_size += read;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    return Err(error);
+                }
+            }
+        }
+        size += PIECE_SIZE;
+    }
+
+    Ok(size)
+}
+
+fn write_pieces<W: Write>(output: &mut W, pieces: &[Piece]) -> io::Result<()> {
+    for piece in pieces {
+        output.write_all(piece)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::por::dispatcher::Backend;
+    use crate::por::test_data::CORRECT_ENCODING;
+    use crate::por::test_data::ID;
+    use crate::por::test_data::INPUT;
+    use crate::por::test_data::IV;
+    use crate::BLOCK_SIZE;
+    use rand::Rng;
+
+    const AES_ITERATIONS: usize = 16;
+    const BREADTH_ITERATIONS: usize = 2;
+
+    fn encoder(batch_size: usize) -> StreamEncoder {
+        StreamEncoder::new(
+            Dispatcher::new(),
+            RoundKeys::new(&ID),
+            IV,
+            AES_ITERATIONS,
+            BREADTH_ITERATIONS,
+        )
+        .with_batch_size(batch_size)
+    }
+
+    fn decoder(batch_size: usize) -> StreamDecoder {
+        StreamDecoder::new(
+            Dispatcher::new(),
+            RoundKeys::new(&ID),
+            IV,
+            AES_ITERATIONS,
+            BREADTH_ITERATIONS,
+        )
+        .with_batch_size(batch_size)
+    }
+
+    /// Reader that returns at most 1000 bytes at a time
+    struct ShortReads<'a>(&'a [u8]);
+
+    impl Read for ShortReads<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let size = buf.len().min(self.0.len()).min(1000);
+            buf[..size].copy_from_slice(&self.0[..size]);
+            self.0 = &self.0[size..];
+            Ok(size)
+        }
+    }
+
+    #[test]
+    fn test_piece_iv() {
+        let iv = [0xff; BLOCK_SIZE];
+        assert_eq!(piece_iv(&iv, 0), iv);
+        assert_eq!(piece_iv(&iv, 1), [0u8; BLOCK_SIZE]);
+
+        let mut expected = [0u8; BLOCK_SIZE];
+        expected[BLOCK_SIZE - 1] = 1;
+        expected[BLOCK_SIZE - 2] = 1;
+        assert_eq!(piece_iv(&[0u8; BLOCK_SIZE], 257), expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut data = vec![0u8; PIECE_SIZE * 5];
+        rand::thread_rng().fill(&mut data[..]);
+
+        for &size in [
+            0,
+            1,
+            PIECE_SIZE - 1,
+            PIECE_SIZE,
+            PIECE_SIZE + 1,
+            PIECE_SIZE * 3,
+            PIECE_SIZE * 5 - 7,
+        ]
+        .iter()
+        {
+            for &batch_size in [1, 3, DEFAULT_BATCH_SIZE].iter() {
+                let data = &data[..size];
+                let mut replica = Vec::new();
+                let summary = encoder(batch_size)
+                    .encode(ShortReads(data), &mut replica)
+                    .unwrap();
+                let pieces = (size / PIECE_SIZE + 1) as u64;
+                assert_eq!(
+                    summary,
+                    StreamSummary {
+                        size: size as u64,
+                        pieces,
+                    },
+                );
+                assert_eq!(replica.len() as u64, pieces * PIECE_SIZE as u64);
+
+                let mut decoded = Vec::new();
+                let summary = decoder(batch_size)
+                    .decode(ShortReads(&replica), &mut decoded)
+                    .unwrap();
+                assert_eq!(
+                    summary,
+                    StreamSummary {
+                        size: size as u64,
+                        pieces,
+                    },
+                );
+                assert_eq!(decoded, data, "size {} batch {}", size, batch_size);
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_as_dispatcher() {
+        let mut dispatcher = Dispatcher::with_backend(Backend::SoftwareFixSlicing).unwrap();
+        let mut replica = Vec::new();
+        StreamEncoder::new(
+            Dispatcher::with_backend(Backend::SoftwareLuT).unwrap(),
+            RoundKeys::new(&ID),
+            IV,
+            256,
+            1,
+        )
+        .encode(&INPUT[..], &mut replica)
+        .unwrap();
+
+        assert_eq!(replica[..PIECE_SIZE].to_vec(), CORRECT_ENCODING.to_vec());
+
+        let mut padding = [[0u8; PIECE_SIZE]];
+        padding[0][0] = PADDING_START;
+        dispatcher.encode(
+            &mut padding,
+            &RoundKeys::new(&ID),
+            &[piece_iv(&IV, 1)],
+            256,
+            1,
+        );
+        assert_eq!(replica[PIECE_SIZE..].to_vec(), padding[0].to_vec());
+    }
+
+    #[test]
+    fn test_invalid_replica() {
+        let mut decoded = Vec::new();
+        let error = decoder(1).decode(&[][..], &mut decoded).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let error = decoder(1)
+            .decode(&[0u8; PIECE_SIZE + 1][..], &mut decoded)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let mut replica = Vec::new();
+        encoder(1).encode(&[1, 2, 3][..], &mut replica).unwrap();
+
+        // Wrong IV
+        let error = StreamDecoder::new(
+            Dispatcher::new(),
+            RoundKeys::new(&ID),
+            piece_iv(&IV, 1),
+            AES_ITERATIONS,
+            BREADTH_ITERATIONS,
+        )
+        .decode(&replica[..], &mut decoded)
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        decoded.clear();
+        decoder(1).decode(&replica[..], &mut decoded).unwrap();
+        assert_eq!(decoded, vec![1, 2, 3]);
+    }
+}
@@ -1,17 +1,24 @@
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleRootBuilder;
+use crate::por::range::DependencyCone;
+use crate::por::range::BLOCKS;
 use crate::Block;
 use crate::Piece;
 use crate::BLOCK_SIZE;
-use std::convert::TryInto;
+use core::convert::TryInto;
+use core::ops::Range;
+use zeroize::Zeroize;
 
 /// Returns (blocks, feedback) tuple given block index in a piece
-/// Handles cases when `number_of_blocks` is not a multiple of `piece.len()` gracefully (by adding
-/// offset from the beginning of the piece)
+/// Handles cases when number of blocks in a piece is not a multiple of `number_of_blocks` gracefully
+/// (by adding offset of remaining blocks from the beginning of the piece)
 pub fn piece_to_blocks_and_feedback(
     piece: &mut Piece,
     index: usize,
     number_of_blocks: usize,
 ) -> (&mut [u8], &Block) {
-    let offset = piece.len() % number_of_blocks;
+    let offset = (piece.len() / BLOCK_SIZE % number_of_blocks) * BLOCK_SIZE;
     let (ends_with_feedback, starts_with_block) =
         piece.split_at_mut(offset + index * BLOCK_SIZE * number_of_blocks);
 
@@ -41,3 +48,114 @@ pub fn piece_to_first_blocks_and_feedback<'a>(
 
     (first_blocks, iv)
 }
+
+/// Source of feedback of the first block in a range of blocks
+#[derive(Debug, Copy, Clone)]
+pub enum Feedback {
+    Iv,
+    /// Block of the piece at this index
+    Block(usize),
+}
+
+impl Feedback {
+    pub fn resolve(self, piece: &Piece, iv: &Block) -> Block {
+        match self {
+            Feedback::Iv => *iv,
+            Feedback::Block(index) => piece[index * BLOCK_SIZE..][..BLOCK_SIZE]
+                .try_into()
+                .unwrap(),
+        }
+    }
+}
+
+/// Calls `decode_blocks` for every range of blocks that needs to be decoded (in place) in order to
+/// get plaintext of `block_range`, layer by layer starting from the encoded piece, see
+/// `DependencyCone`
+pub fn decode_range<F>(block_range: Range<usize>, breadth_iterations: usize, mut decode_blocks: F)
+where
+    F: FnMut(Range<usize>, Feedback),
+{
+    let cone = DependencyCone::new(block_range, breadth_iterations);
+    for layer in (0..breadth_iterations).rev() {
+        let ranges = cone.layer(layer);
+        // Tail goes first, since the last block is feedback of the first one
+        if !ranges.tail.is_empty() {
+            let feedback = Feedback::Block(ranges.tail.start - 1);
+            decode_blocks(ranges.tail, feedback);
+        }
+        if !ranges.head.is_empty() {
+            let feedback = match ranges.head.start {
+                0 if layer == 0 => Feedback::Iv,
+                0 => Feedback::Block(BLOCKS - 1),
+                start => Feedback::Block(start - 1),
+            };
+            decode_blocks(ranges.head, feedback);
+        }
+    }
+}
+
+/// Decodes `blocks` of the piece with `decode_batch` that decodes `N` consecutive blocks given
+/// feedback of the first of them. Batches are processed from the end and the first one is padded
+/// with zero blocks if necessary, feedback of the first block is resolved last, so it can be one of
+/// decoded blocks
+pub fn decode_blocks_batched<const N: usize, F>(
+    piece: &mut Piece,
+    blocks: Range<usize>,
+    feedback: Feedback,
+    iv: &Block,
+    mut decode_batch: F,
+) where
+    F: FnMut(&mut [u8], &Block),
+{
+    let first_batch_end = blocks.start + blocks.len() % N;
+    for batch_start in (first_batch_end..blocks.end).step_by(N).rev() {
+        let feedback = if batch_start == blocks.start {
+            feedback.resolve(piece, iv)
+        } else {
+            Feedback::Block(batch_start - 1).resolve(piece, iv)
+        };
+        decode_batch(
+            &mut piece[batch_start * BLOCK_SIZE..][..N * BLOCK_SIZE],
+            &feedback,
+        );
+    }
+
+    if first_batch_end > blocks.start {
+        let blocks = blocks.start * BLOCK_SIZE..first_batch_end * BLOCK_SIZE;
+        let mut padded = [[0u8; BLOCK_SIZE]; N];
+        let padded = padded.as_flattened_mut();
+        padded[..blocks.len()].copy_from_slice(&piece[blocks.clone()]);
+
+        decode_batch(padded, &feedback.resolve(piece, iv));
+
+        piece[blocks.clone()].copy_from_slice(&padded[..blocks.len()]);
+        padded.zeroize();
+    }
+}
+
+/// Calls `encode_iteration` for every breadth iteration with IVs of pieces, the last iteration
+/// also gets Merkle root builders that it must feed with every encoded block of every piece.
+/// Returns roots of Merkle trees over blocks of encoded pieces, panics if `breadth_iterations`
+/// is 0
+pub fn seal<const N: usize, F>(
+    hasher: &AesHash,
+    mut ivs: [Block; N],
+    breadth_iterations: usize,
+    mut encode_iteration: F,
+) -> [Hash; N]
+where
+    F: FnMut([Block; N], Option<&mut [MerkleRootBuilder; N]>) -> [Block; N],
+{
+    assert!(
+        breadth_iterations > 0,
+        "Sealing needs at least one breadth iteration"
+    );
+
+    for _ in 1..breadth_iterations {
+        ivs = encode_iteration(ivs, None);
+    }
+    let mut builders = core::array::from_fn(|_| MerkleRootBuilder::new(*hasher));
+    encode_iteration(ivs, Some(&mut builders));
+
+    builders.map(|builder| builder.root().expect("Pieces are never empty"))
+}
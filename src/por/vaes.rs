@@ -1,12 +1,22 @@
 use crate::aes_low_level::aes_ni;
 use crate::aes_low_level::aes_ni::ExpandedKeys;
 use crate::aes_low_level::vaes;
+use crate::hash::AesHash;
+use crate::hash::Hash;
+use crate::merkle::MerkleRootBuilder;
+use crate::por::iv::IvDerivation;
+use crate::por::self_test;
 use crate::por::utils;
+use crate::round_keys::RoundKeys;
+use crate::utils::AesImplementation;
+use crate::utils::SelfTestFailed;
+use crate::utils::UnsupportedCpu;
 use crate::Block;
 use crate::Piece;
 use crate::BLOCK_SIZE;
 use crate::PIECE_SIZE;
-use std::io::Write;
+use core::ops::Range;
+use zeroize::Zeroize;
 
 // TODO: This should use keys expanded using AES-NI
 pub struct VAesKeys {
@@ -15,17 +25,63 @@ pub struct VAesKeys {
 }
 
 impl VAesKeys {
-    pub fn new(id: &Block) -> Self {
-        let (keys_enc, keys_dec) = aes_ni::expand(id);
+    /// Returns an error if AES-NI is not supported by this CPU
+    pub fn new(id: &Block) -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::AesNi)?;
+        // Safety: checked above that AES-NI is available
+        let (keys_enc, keys_dec) = unsafe { aes_ni::expand(id) };
+        Ok(Self { keys_enc, keys_dec })
+    }
+
+    /// Only loads round keys into registers, doesn't require AES-NI
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let keys_enc = aes_ni::expanded_keys_from_round_keys(round_keys.enc());
+        let keys_dec = aes_ni::expanded_keys_from_round_keys(round_keys.dec());
         Self { keys_enc, keys_dec }
     }
 }
 
-pub struct VAes;
+impl Drop for VAesKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+pub struct VAes {
+    // Can only be constructed after checking that VAES is available
+    _private: (),
+}
 
 impl VAes {
-    pub fn new() -> Self {
-        Self {}
+    /// Returns an error if VAES is not supported by this CPU
+    pub fn new() -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::VAes)?;
+        Ok(Self { _private: () })
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = VAesKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+
+        let mut pieces = [self_test::INPUT; 12];
+        self.encode(
+            &mut pieces,
+            &keys,
+            [self_test::IV; 12],
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_encodings(&pieces)?;
+
+        self.decode(
+            &mut pieces[0],
+            &keys,
+            &self_test::IV,
+            self_test::AES_ITERATIONS,
+            self_test::BREADTH_ITERATIONS,
+        );
+        self_test::check_decodings(&pieces[..1])
     }
 
     /// Pipelined proof of replication encoding with VAES
@@ -38,10 +94,47 @@ impl VAes {
         breadth_iterations: usize,
     ) {
         for _ in 0..breadth_iterations {
-            ivs = encode_internal(pieces, &keys.keys_enc, ivs, aes_iterations);
+            ivs = encode_internal(pieces, &keys.keys_enc, ivs, aes_iterations, None);
         }
     }
 
+    /// Same as `encode()`, also returns roots of Merkle trees over blocks of encoded pieces (see
+    /// `MerkleTree::from_piece_blocks()`) computed during the last breadth iteration, so pieces
+    /// don't need to be read again
+    pub fn seal(
+        &self,
+        pieces: &mut [Piece; 12],
+        keys: &VAesKeys,
+        ivs: [Block; 12],
+        hasher: &AesHash,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) -> [Hash; 12] {
+        utils::seal(hasher, ivs, breadth_iterations, |ivs, builders| {
+            encode_internal(pieces, &keys.keys_enc, ivs, aes_iterations, builders)
+        })
+    }
+
+    /// Same as `encode()`, IVs are derived from indices of consecutive pieces starting with
+    /// `first_index`
+    pub fn encode_indexed(
+        &self,
+        pieces: &mut [Piece; 12],
+        keys: &VAesKeys,
+        iv_derivation: &IvDerivation,
+        first_index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.encode(
+            pieces,
+            keys,
+            iv_derivation.piece_ivs(first_index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
     /// Pipelined proof of replication decoding with VAES
     pub fn decode(
         &self,
@@ -57,6 +150,49 @@ impl VAes {
 
         decode_internal(piece, &keys.keys_dec, Some(iv), aes_iterations);
     }
+
+    /// Same as `decode()`, IV is derived from piece index
+    pub fn decode_indexed(
+        &self,
+        piece: &mut Piece,
+        keys: &VAesKeys,
+        iv_derivation: &IvDerivation,
+        index: u64,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        self.decode(
+            piece,
+            keys,
+            &iv_derivation.piece_iv(index),
+            aes_iterations,
+            breadth_iterations,
+        );
+    }
+
+    /// Decodes only blocks in `block_range` and blocks they depend on (see `DependencyCone`),
+    /// the rest of the piece is left in unspecified state
+    pub fn decode_range(
+        &self,
+        piece: &mut Piece,
+        keys: &VAesKeys,
+        iv: &Block,
+        block_range: Range<usize>,
+        aes_iterations: usize,
+        breadth_iterations: usize,
+    ) {
+        utils::decode_range(block_range, breadth_iterations, |blocks, feedback| {
+            utils::decode_blocks_batched::<12, _>(
+                piece,
+                blocks,
+                feedback,
+                iv,
+                |blocks, feedback| {
+                    decode_12_blocks_internal(&keys.keys_dec, blocks, feedback, aes_iterations);
+                },
+            );
+        });
+    }
 }
 
 /// Returns iv for the next round
@@ -65,6 +201,7 @@ fn encode_internal(
     keys: &ExpandedKeys,
     mut ivs: [Block; 12],
     aes_iterations: usize,
+    mut builders: Option<&mut [MerkleRootBuilder; 12]>,
 ) -> [Block; 12] {
     let [piece0, piece1, piece2, piece3, piece4, piece5, piece6, piece7, piece8, piece9, piece10, piece11] =
         pieces;
@@ -109,8 +246,13 @@ fn encode_internal(
             vaes::por_encode_pipelined_x12_low_level(keys, &mut blocks, &ivs, aes_iterations);
 
             ivs.iter_mut().zip(blocks.iter()).for_each(|(iv, block)| {
-                iv.as_mut().write_all(block).unwrap();
+                iv.copy_from_slice(block);
             });
+            if let Some(builders) = &mut builders {
+                for (builder, block) in builders.iter_mut().zip(blocks.iter()) {
+                    builder.push(block);
+                }
+            }
         });
 
     ivs
@@ -123,7 +265,7 @@ fn decode_internal(
     aes_iterations: usize,
 ) {
     // We have `4096 / 16 / 12 = 21` iterations and 4 blocks extra
-    for i in (0..(PIECE_SIZE / BLOCK_SIZE / 4)).rev() {
+    for i in (0..(PIECE_SIZE / BLOCK_SIZE / 12)).rev() {
         let (blocks, feedback) = utils::piece_to_blocks_and_feedback(piece, i, 12);
         decode_12_blocks_internal(keys, blocks, feedback, aes_iterations);
     }
@@ -140,11 +282,8 @@ fn decode_12_blocks_internal(
     aes_iterations: usize,
 ) {
     let mut feedbacks = [0u8; BLOCK_SIZE * 12];
-    feedbacks.as_mut().write_all(feedback).unwrap();
-    feedbacks[BLOCK_SIZE..]
-        .as_mut()
-        .write_all(&blocks[..(blocks.len() - BLOCK_SIZE)])
-        .unwrap();
+    feedbacks[..BLOCK_SIZE].copy_from_slice(feedback);
+    feedbacks[BLOCK_SIZE..].copy_from_slice(&blocks[..(blocks.len() - BLOCK_SIZE)]);
 
     vaes::por_decode_pipelined_x12_low_level(keys, blocks, &feedbacks, aes_iterations);
 }
@@ -156,11 +295,8 @@ fn decode_4_blocks_internal(
     aes_iterations: usize,
 ) {
     let mut feedbacks = [0u8; BLOCK_SIZE * 4];
-    feedbacks.as_mut().write_all(feedback).unwrap();
-    feedbacks[BLOCK_SIZE..]
-        .as_mut()
-        .write_all(&blocks[..(blocks.len() - BLOCK_SIZE)])
-        .unwrap();
+    feedbacks[..BLOCK_SIZE].copy_from_slice(feedback);
+    feedbacks[BLOCK_SIZE..].copy_from_slice(&blocks[..(blocks.len() - BLOCK_SIZE)]);
 
     vaes::por_decode_x4_low_level(keys, blocks, &feedbacks, aes_iterations);
 }
@@ -168,6 +304,7 @@ fn decode_4_blocks_internal(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle::MerkleTree;
     use crate::por::test_data::CORRECT_ENCODING;
     use crate::por::test_data::CORRECT_ENCODING_BREADTH_10;
     use crate::por::test_data::ID;
@@ -186,8 +323,8 @@ mod tests {
         }
         let aes_iterations = 256;
 
-        let keys = VAesKeys::new(&ID);
-        let por = VAes::new();
+        let keys = VAesKeys::new(&ID).unwrap();
+        let por = VAes::new().unwrap();
 
         let mut encodings = [INPUT; 12];
         por.encode(&mut encodings, &keys, [IV; 12], aes_iterations, 1);
@@ -210,8 +347,8 @@ mod tests {
         }
         let aes_iterations = 256;
 
-        let keys = VAesKeys::new(&ID);
-        let por = VAes::new();
+        let keys = VAesKeys::new(&ID).unwrap();
+        let por = VAes::new().unwrap();
 
         let mut encodings = [INPUT; 12];
         por.encode(&mut encodings, &keys, [IV; 12], aes_iterations, 10);
@@ -243,8 +380,8 @@ mod tests {
         let mut iv = [0u8; 16];
         rand::thread_rng().fill(&mut iv[..]);
 
-        let keys = VAesKeys::new(&id);
-        let por = VAes::new();
+        let keys = VAesKeys::new(&id).unwrap();
+        let por = VAes::new().unwrap();
 
         let mut encodings = [input; 12];
         por.encode(&mut encodings, &keys, [iv; 12], aes_iterations, 1);
@@ -274,8 +411,8 @@ mod tests {
         let mut iv = [0u8; 16];
         rand::thread_rng().fill(&mut iv[..]);
 
-        let keys = VAesKeys::new(&id);
-        let por = VAes::new();
+        let keys = VAesKeys::new(&id).unwrap();
+        let por = VAes::new().unwrap();
 
         let mut encodings = [input; 12];
         por.encode(&mut encodings, &keys, [iv; 12], aes_iterations, 10);
@@ -287,4 +424,29 @@ mod tests {
             assert_eq!(decoding.to_vec(), input.to_vec());
         }
     }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(VAes::new().unwrap().self_test(), Ok(()));
+    }
+
+    /// Sealing produces the same encoding as `encode()` and the same root as hashing it afterwards
+    #[test]
+    fn test_seal() {
+        if !utils::aes_implementations_available().contains(&AesImplementation::VAes) {
+            println!("VAES support not available, skipping test");
+            return;
+        }
+        let keys = VAesKeys::new(&ID).unwrap();
+        let por = VAes::new().unwrap();
+        let hasher = AesHash::new();
+        let root = MerkleTree::from_piece_blocks(hasher, &CORRECT_ENCODING_BREADTH_10).root();
+
+        let mut encodings = [INPUT; 12];
+        let roots = por.seal(&mut encodings, &keys, [IV; 12], &hasher, 256, 10);
+        for (encoding, piece_root) in encodings.iter().zip(roots.iter()) {
+            assert_eq!(encoding.to_vec(), CORRECT_ENCODING_BREADTH_10.to_vec());
+            assert_eq!(*piece_root, root);
+        }
+    }
 }
@@ -0,0 +1,130 @@
+//! Spot verification of encoding against hardware faults.
+//!
+//! A bit flip during encoding (for instance on overclocked hardware) corrupts replica silently and
+//! permanently. Verification decodes some or all of the freshly encoded pieces with a software
+//! backend that doesn't share hardware AES units with accelerated backends and compares the result
+//! with the original plaintext, see `Dispatcher::encode_verified()`.
+
+use crate::hash::AesHash;
+use crate::por::dispatcher::Backend;
+use crate::Block;
+use crate::BLOCK_SIZE;
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::fmt;
+
+/// Domain of hashes used for sampling pieces from seed
+pub const DOMAIN_SAMPLE: u8 = 4;
+
+/// Which encoded pieces to verify
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Verification {
+    /// Every piece, needs a copy of the whole plaintext
+    All,
+    /// Pseudo-random sample of `count` distinct pieces, seed should be random so that faulty
+    /// pieces can't predict whether they are checked
+    Sample { count: usize, seed: Block },
+}
+
+impl Verification {
+    /// Sorted indices of pieces to verify out of `pieces`
+    pub fn sample(&self, pieces: usize) -> Vec<usize> {
+        match *self {
+            Verification::Sample { count, seed } if count < pieces => {
+                let hasher = AesHash::new();
+                let mut sample = BTreeSet::new();
+                let mut counter = 0u64;
+                while sample.len() < count {
+                    let mut input = [0u8; BLOCK_SIZE + 8];
+                    input[..BLOCK_SIZE].copy_from_slice(&seed);
+                    input[BLOCK_SIZE..].copy_from_slice(&counter.to_le_bytes());
+                    let hash = hasher.hash_in_domain(DOMAIN_SAMPLE, &input);
+
+                    let value = u64::from_le_bytes(hash[..8].try_into().unwrap());
+                    sample.insert((value % pieces as u64) as usize);
+                    counter += 1;
+                }
+                sample.into_iter().collect()
+            }
+            _ => (0..pieces).collect(),
+        }
+    }
+}
+
+/// Software backend that verifies pieces encoded with `backend`, always a different one
+pub(crate) fn verification_backend(backend: Backend) -> Backend {
+    if backend == Backend::SoftwareLuT {
+        Backend::SoftwareFixSlicing
+    } else {
+        Backend::SoftwareLuT
+    }
+}
+
+/// Some of verified pieces didn't decode back to plaintext
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerificationError {
+    /// Number of verified pieces
+    pub verified: usize,
+    /// Indices of pieces that didn't decode back to plaintext
+    pub mismatched: Vec<usize>,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} verified pieces didn't decode back to plaintext: {:?}",
+            self.mismatched.len(),
+            self.verified,
+            self.mismatched,
+        )
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        assert_eq!(Verification::All.sample(5), vec![0, 1, 2, 3, 4]);
+        assert!(Verification::All.sample(0).is_empty());
+
+        let verification = Verification::Sample {
+            count: 10,
+            seed: [1u8; BLOCK_SIZE],
+        };
+        let sample = verification.sample(100);
+        assert_eq!(sample.len(), 10);
+        assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(sample.iter().all(|&index| index < 100));
+        assert_eq!(sample, verification.sample(100));
+
+        let other_seed = Verification::Sample {
+            count: 10,
+            seed: [2u8; BLOCK_SIZE],
+        };
+        assert_ne!(sample, other_seed.sample(100));
+
+        // Sample as large as replica is the whole replica
+        assert_eq!(verification.sample(10), Verification::All.sample(10));
+        assert_eq!(verification.sample(3), Verification::All.sample(3));
+    }
+
+    #[test]
+    fn test_verification_backend() {
+        for &backend in [
+            Backend::OpenCL,
+            Backend::VAes,
+            Backend::AesNi,
+            Backend::SoftwareFixSlicing,
+            Backend::SoftwareLuT,
+        ]
+        .iter()
+        {
+            assert_ne!(verification_backend(backend), backend);
+        }
+    }
+}
@@ -3,11 +3,20 @@ use crate::aes128_load4;
 use crate::aes128_store;
 use crate::aes_low_level::aes_ni;
 use crate::aes_low_level::aes_ni::ExpandedKeys;
+use crate::pot::self_test;
 use crate::pot::MAX_VERIFIER_PARALLELISM;
 use crate::pot::MIN_VERIFIER_PARALLELISM;
+use crate::round_keys::RoundKeys;
+use crate::utils::AesImplementation;
+use crate::utils::SelfTestFailed;
+use crate::utils::UnsupportedCpu;
 use crate::Block;
 use crate::BLOCK_SIZE;
+use alloc::vec::Vec;
+use core::arch::x86_64::*;
+#[cfg(feature = "std")]
 use rayon::prelude::*;
+use zeroize::Zeroize;
 
 pub struct AesNiKeys {
     keys_enc: ExpandedKeys,
@@ -16,18 +25,63 @@ pub struct AesNiKeys {
 }
 
 impl AesNiKeys {
-    pub fn new(id: &Block) -> Self {
-        let (keys_enc, keys_dec) = aes_ni::expand(id);
+    /// Returns an error if AES-NI is not supported by this CPU
+    pub fn new(id: &Block) -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::AesNi)?;
+        // Safety: checked above that AES-NI is available
+        let (keys_enc, keys_dec) = unsafe { aes_ni::expand(id) };
+        Ok(Self { keys_enc, keys_dec })
+    }
+
+    /// Only loads round keys into registers, doesn't require AES-NI
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let keys_enc = aes_ni::expanded_keys_from_round_keys(round_keys.enc());
+        let keys_dec = aes_ni::expanded_keys_from_round_keys(round_keys.dec());
         Self { keys_enc, keys_dec }
     }
 }
 
-pub struct AesNi;
+impl Drop for AesNiKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+pub struct AesNi {
+    // Can only be constructed after checking that AES-NI is available
+    _private: (),
+}
 
 impl AesNi {
-    pub fn new() -> Self {
-        Self {}
+    /// Returns an error if AES-NI is not supported by this CPU
+    pub fn new() -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::AesNi)?;
+        Ok(Self { _private: () })
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = AesNiKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+        let proof = self.prove(
+            &self_test::SEED,
+            &keys,
+            self_test::AES_ITERATIONS,
+            self_test::VERIFIER_PARALLELISM,
+        );
+
+        self_test::check(&proof, |proof| {
+            self.verify(proof, &self_test::SEED, &keys, self_test::AES_ITERATIONS)
+        })?;
+        #[cfg(feature = "std")]
+        self_test::check(&proof, |proof| {
+            self.verify_parallel(proof, &self_test::SEED, &keys, self_test::AES_ITERATIONS)
+        })?;
+        self_test::check(&proof, |proof| {
+            self.verify_x8(proof, &self_test::SEED, &keys, self_test::AES_ITERATIONS)
+        })
     }
+
     /// Arbitrary length proof-of-time
     pub fn prove(
         &self,
@@ -36,7 +90,10 @@ impl AesNi {
         aes_iterations: usize,
         verifier_parallelism: usize,
     ) -> Vec<u8> {
-        assert!(aes_iterations % 12 == 0 && aes_iterations % MAX_VERIFIER_PARALLELISM == 0);
+        assert!(
+            aes_iterations.is_multiple_of(12)
+                && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        );
         assert!(
             verifier_parallelism == MIN_VERIFIER_PARALLELISM
                 || verifier_parallelism == 8
@@ -51,8 +108,9 @@ impl AesNi {
         let mut block_reg = unsafe { aes128_load!(block) };
 
         for _ in 0..verifier_parallelism {
-            block_reg = aes_ni::pot_prove_low_level(keys.keys_enc, block_reg, inner_iterations);
+            // Safety: `AesNi` can only be constructed when AES-NI is available
             unsafe {
+                block_reg = aes_ni::pot_prove_low_level(keys.keys_enc, block_reg, inner_iterations);
                 aes128_store!(block, block_reg);
             }
             result.extend_from_slice(&block);
@@ -69,8 +127,11 @@ impl AesNi {
         keys: &AesNiKeys,
         aes_iterations: usize,
     ) -> bool {
-        assert!(proof.len() % BLOCK_SIZE == 0);
-        assert!(aes_iterations % 12 == 0 && aes_iterations % MAX_VERIFIER_PARALLELISM == 0);
+        assert!(proof.len().is_multiple_of(BLOCK_SIZE));
+        assert!(
+            aes_iterations.is_multiple_of(12)
+                && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        );
         let verifier_parallelism = proof.len() / BLOCK_SIZE;
         assert!(
             verifier_parallelism == MIN_VERIFIER_PARALLELISM
@@ -83,28 +144,101 @@ impl AesNi {
 
         let mut previous = seed.as_ref();
 
-        proof
-            .chunks_exact(BLOCK_SIZE * 4)
-            .map(|blocks| -> bool {
-                let (block0, blocks) = blocks.split_at(BLOCK_SIZE);
-                let (block1, blocks) = blocks.split_at(BLOCK_SIZE);
-                let (block2, block3) = blocks.split_at(BLOCK_SIZE);
+        proof.chunks_exact(BLOCK_SIZE * 4).all(|blocks| -> bool {
+            let (block0, blocks) = blocks.split_at(BLOCK_SIZE);
+            let (block1, blocks) = blocks.split_at(BLOCK_SIZE);
+            let (block2, block3) = blocks.split_at(BLOCK_SIZE);
 
-                let expected_reg = unsafe { aes128_load4!(previous, block0, block1, block2) };
-                let blocks_reg = unsafe { aes128_load4!(block0, block1, block2, block3) };
-                previous = block3;
+            let expected_reg = unsafe { aes128_load4!(previous, block0, block1, block2) };
+            let blocks_reg = unsafe { aes128_load4!(block0, block1, block2, block3) };
+            previous = block3;
 
+            // Safety: `AesNi` can only be constructed when AES-NI is available
+            unsafe {
                 aes_ni::pot_verify_pipelined_x4_low_level(
                     keys.keys_dec,
                     expected_reg,
                     blocks_reg,
                     inner_iterations,
                 )
-            })
-            .fold(true, |a, b| a && b)
+            }
+        })
+    }
+
+    /// Arbitrary length proof-of-time verification using pipelined AES-NI with 8 blocks at a time,
+    /// remaining 4 blocks (if any) are verified with 4 blocks pipeline
+    pub fn verify_x8(
+        &self,
+        proof: &[u8],
+        seed: &Block,
+        keys: &AesNiKeys,
+        aes_iterations: usize,
+    ) -> bool {
+        assert!(proof.len().is_multiple_of(BLOCK_SIZE));
+        assert!(
+            aes_iterations.is_multiple_of(12)
+                && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        );
+        let verifier_parallelism = proof.len() / BLOCK_SIZE;
+        assert!(
+            verifier_parallelism == MIN_VERIFIER_PARALLELISM
+                || verifier_parallelism == 8
+                || verifier_parallelism == 12
+                || verifier_parallelism == MAX_VERIFIER_PARALLELISM
+        );
+
+        let inner_iterations = aes_iterations / verifier_parallelism;
+
+        let mut previous = seed.as_ref();
+
+        let mut iterator = proof.chunks_exact(BLOCK_SIZE * 8);
+        let remainder = iterator.remainder();
+        let result = iterator.all(|blocks| -> bool {
+            // Safety: `AesNi` can only be constructed when AES-NI is available
+            unsafe {
+                let mut expected_reg = [aes128_load!(previous); 8];
+                let mut blocks_reg = [_mm_setzero_si128(); 8];
+                for (i, block) in blocks.chunks_exact(BLOCK_SIZE).enumerate() {
+                    blocks_reg[i] = aes128_load!(block);
+                    if i < 7 {
+                        expected_reg[i + 1] = blocks_reg[i];
+                    }
+                }
+                previous = &blocks[(blocks.len() - BLOCK_SIZE)..];
+
+                aes_ni::pot_verify_pipelined_x8_low_level(
+                    keys.keys_dec,
+                    expected_reg,
+                    blocks_reg,
+                    inner_iterations,
+                )
+            }
+        });
+
+        if !result || remainder.is_empty() {
+            return result;
+        }
+
+        let (block0, blocks) = remainder.split_at(BLOCK_SIZE);
+        let (block1, blocks) = blocks.split_at(BLOCK_SIZE);
+        let (block2, block3) = blocks.split_at(BLOCK_SIZE);
+
+        // Safety: `AesNi` can only be constructed when AES-NI is available
+        unsafe {
+            let expected_reg = aes128_load4!(previous, block0, block1, block2);
+            let blocks_reg = aes128_load4!(block0, block1, block2, block3);
+
+            aes_ni::pot_verify_pipelined_x4_low_level(
+                keys.keys_dec,
+                expected_reg,
+                blocks_reg,
+                inner_iterations,
+            )
+        }
     }
 
     /// Arbitrary length proof-of-time verification using pipelined AES-NI in parallel
+    #[cfg(feature = "std")]
     pub fn verify_parallel(
         &self,
         proof: &[u8],
@@ -114,10 +248,10 @@ impl AesNi {
     ) -> bool {
         let pipelining_parallelism = 4;
 
-        assert!(proof.len() % BLOCK_SIZE == 0);
+        assert!(proof.len().is_multiple_of(BLOCK_SIZE));
         let verifier_parallelism = proof.len() / BLOCK_SIZE;
-        assert!(verifier_parallelism % pipelining_parallelism == 0);
-        assert!(aes_iterations % verifier_parallelism == 0);
+        assert!(verifier_parallelism.is_multiple_of(pipelining_parallelism));
+        assert!(aes_iterations.is_multiple_of(verifier_parallelism));
 
         let inner_iterations = aes_iterations / verifier_parallelism;
 
@@ -142,14 +276,15 @@ impl AesNi {
                 let expected_reg = unsafe { aes128_load4!(seed, block0, block1, block2) };
                 let blocks_reg = unsafe { aes128_load4!(block0, block1, block2, block3) };
 
-                let result = aes_ni::pot_verify_pipelined_x4_low_level(
-                    keys.keys_dec,
-                    expected_reg,
-                    blocks_reg,
-                    inner_iterations,
-                );
-
-                result
+                // Safety: `AesNi` can only be constructed when AES-NI is available
+                unsafe {
+                    aes_ni::pot_verify_pipelined_x4_low_level(
+                        keys.keys_dec,
+                        expected_reg,
+                        blocks_reg,
+                        inner_iterations,
+                    )
+                }
             })
             .reduce(|| true, |a, b| a && b)
     }
@@ -168,8 +303,8 @@ mod tests {
         let aes_iterations = 288;
         let verifier_parallelism = 16;
 
-        let keys = AesNiKeys::new(&ID);
-        let pot = AesNi::new();
+        let keys = AesNiKeys::new(&ID).unwrap();
+        let pot = AesNi::new().unwrap();
 
         let proof = pot.prove(&SEED, &keys, aes_iterations, verifier_parallelism);
         assert_eq!(proof.len(), verifier_parallelism * BLOCK_SIZE);
@@ -184,14 +319,26 @@ mod tests {
             aes_iterations
         ));
 
-        assert!(pot.verify_parallel(&CORRECT_PROOF_16, &SEED, &keys, aes_iterations));
+        assert!(pot.verify_x8(&CORRECT_PROOF_16, &SEED, &keys, aes_iterations));
 
-        assert!(!pot.verify_parallel(
+        assert!(!pot.verify_x8(
             &vec![42; verifier_parallelism * BLOCK_SIZE],
             &SEED,
             &keys,
             aes_iterations
         ));
+
+        #[cfg(feature = "std")]
+        {
+            assert!(pot.verify_parallel(&CORRECT_PROOF_16, &SEED, &keys, aes_iterations));
+
+            assert!(!pot.verify_parallel(
+                &vec![42; verifier_parallelism * BLOCK_SIZE],
+                &SEED,
+                &keys,
+                aes_iterations
+            ));
+        }
     }
 
     #[test]
@@ -205,14 +352,44 @@ mod tests {
         let mut seed = [0u8; 16];
         rand::thread_rng().fill(&mut seed[..]);
 
-        let keys = AesNiKeys::new(&key);
-        let pot = AesNi::new();
+        let keys = AesNiKeys::new(&key).unwrap();
+        let pot = AesNi::new().unwrap();
 
         let proof = pot.prove(&seed, &keys, aes_iterations, verifier_parallelism);
         assert_eq!(proof.len(), verifier_parallelism * BLOCK_SIZE);
 
         assert!(pot.verify(&proof, &seed, &keys, aes_iterations));
 
+        #[cfg(feature = "std")]
         assert!(pot.verify_parallel(&proof, &seed, &keys, aes_iterations));
     }
+
+    #[test]
+    fn test_x8_random() {
+        let aes_iterations = 288;
+
+        let mut key = [0u8; 16];
+        rand::thread_rng().fill(&mut key[..]);
+
+        let mut seed = [0u8; 16];
+        rand::thread_rng().fill(&mut seed[..]);
+
+        let keys = AesNiKeys::new(&key).unwrap();
+        let pot = AesNi::new().unwrap();
+
+        // Covers x8 only, x4 only and x8 with x4 remainder
+        for &verifier_parallelism in [4, 8, 12, 16].iter() {
+            let mut proof = pot.prove(&seed, &keys, aes_iterations, verifier_parallelism);
+            assert!(pot.verify_x8(&proof, &seed, &keys, aes_iterations));
+
+            let last = proof.len() - 1;
+            proof[last] ^= 1;
+            assert!(!pot.verify_x8(&proof, &seed, &keys, aes_iterations));
+        }
+    }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(AesNi::new().unwrap().self_test(), Ok(()));
+    }
 }
@@ -1,10 +1,14 @@
-/// Proof of time implementation
+//! Proof of time implementation
+
 #[cfg(target_arch = "x86_64")]
 pub mod aes_ni;
-#[cfg(test)]
-mod test_data;
+mod self_test;
+pub mod software_fix_slicing;
+// Also used by self-tests
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) mod test_data;
 #[cfg(target_arch = "x86_64")]
 pub mod vaes;
 
-const MIN_VERIFIER_PARALLELISM: usize = 4;
-const MAX_VERIFIER_PARALLELISM: usize = 16;
+pub(crate) const MIN_VERIFIER_PARALLELISM: usize = 4;
+pub(crate) const MAX_VERIFIER_PARALLELISM: usize = 16;
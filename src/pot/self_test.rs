@@ -0,0 +1,59 @@
+//! Known-answer self-test with reduced number of iterations, shared by all backends
+
+pub(super) use crate::pot::test_data::ID;
+pub(super) use crate::pot::test_data::SEED;
+use crate::utils::SelfTestFailed;
+
+pub(super) const AES_ITERATIONS: usize = 48;
+pub(super) const VERIFIER_PARALLELISM: usize = 16;
+
+/// Proof for `SEED` with `ID` and parameters above
+const PROOF: [u8; 256] = [
+    0xe3, 0x60, 0x5b, 0x70, 0x1d, 0x0e, 0xf5, 0x7e, 0x45, 0x93, 0xea, 0x7c, 0x20, 0x1b, 0x11, 0x58,
+    0xd0, 0x8b, 0xe5, 0x81, 0xaf, 0xbd, 0x93, 0xc4, 0xde, 0xb6, 0x24, 0x61, 0xc2, 0xd9, 0xf2, 0xd3,
+    0x36, 0xdd, 0xab, 0x7d, 0xf6, 0xa8, 0x00, 0x51, 0x78, 0x02, 0xb0, 0x6e, 0xbf, 0x84, 0x3f, 0xcb,
+    0x3a, 0x6a, 0x77, 0x1f, 0x37, 0x74, 0x2d, 0x3d, 0x96, 0x4e, 0x23, 0x5f, 0xb5, 0xeb, 0xa1, 0x17,
+    0x72, 0x66, 0x62, 0xb6, 0x3c, 0x60, 0x9b, 0x65, 0x7b, 0x25, 0x11, 0xeb, 0x48, 0xc5, 0x33, 0xff,
+    0x18, 0xb3, 0xdc, 0xf9, 0x19, 0x48, 0x7d, 0x61, 0xcb, 0x72, 0xb6, 0x15, 0x66, 0x52, 0x30, 0xfe,
+    0x9c, 0x43, 0x93, 0xa4, 0x3a, 0xb9, 0xef, 0xb2, 0x7c, 0x6f, 0xf3, 0xc7, 0xdd, 0x19, 0xf6, 0xa9,
+    0xbe, 0xd8, 0xd7, 0x31, 0x5e, 0xcb, 0x11, 0x75, 0x62, 0xf7, 0x20, 0x08, 0x94, 0xb7, 0xcc, 0xef,
+    0x3c, 0x46, 0xcb, 0x57, 0x98, 0x03, 0xeb, 0x6a, 0x83, 0x0d, 0x62, 0xcb, 0xbf, 0x1e, 0x2c, 0x54,
+    0xca, 0x39, 0x4b, 0x8e, 0xce, 0x00, 0xa0, 0x09, 0xf0, 0x06, 0x54, 0x97, 0x0c, 0x66, 0x05, 0x7c,
+    0x49, 0x1b, 0x07, 0x9f, 0xe5, 0x39, 0x60, 0xb1, 0xcb, 0xd9, 0xe9, 0x0f, 0xd2, 0xf3, 0xc7, 0x1e,
+    0x5d, 0xd5, 0x66, 0x04, 0x09, 0x8f, 0x84, 0x47, 0x7a, 0x21, 0x2b, 0x79, 0x4e, 0x69, 0x0c, 0x09,
+    0xaf, 0xb6, 0x88, 0x5e, 0xe4, 0x9f, 0xa4, 0x01, 0x33, 0xd8, 0x6f, 0x13, 0x7e, 0x0f, 0xd4, 0x64,
+    0xbd, 0xde, 0xe1, 0x57, 0x97, 0x4d, 0xe3, 0xbe, 0x36, 0x74, 0x2f, 0x2a, 0x01, 0x74, 0xad, 0xe6,
+    0x10, 0xd7, 0x89, 0x85, 0x33, 0xc4, 0x36, 0x9d, 0x90, 0x52, 0x85, 0xfd, 0x48, 0x47, 0x84, 0x71,
+    0x10, 0x4e, 0xd2, 0xb8, 0x18, 0x8b, 0x9f, 0xf1, 0x85, 0xfe, 0x99, 0xa5, 0x0c, 0xdd, 0x9a, 0xd6,
+];
+
+/// Checks proof created by backend and results of verification of correct and corrupted proofs
+pub(super) fn check<V>(proof: &[u8], verify: V) -> Result<(), SelfTestFailed>
+where
+    V: Fn(&[u8]) -> bool,
+{
+    let mut corrupted = PROOF;
+    corrupted[PROOF.len() - 1] ^= 1;
+
+    if proof == PROOF && verify(&PROOF) && !verify(&corrupted) {
+        Ok(())
+    } else {
+        Err(SelfTestFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!(check(&PROOF, |proof| proof == PROOF), Ok(()));
+        assert_eq!(
+            check(&PROOF[1..], |proof| proof == PROOF),
+            Err(SelfTestFailed)
+        );
+        assert_eq!(check(&PROOF, |_| true), Err(SelfTestFailed));
+        assert_eq!(check(&PROOF, |_| false), Err(SelfTestFailed));
+    }
+}
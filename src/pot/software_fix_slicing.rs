@@ -0,0 +1,214 @@
+use crate::aes_low_level::fixslice;
+use crate::aes_low_level::fixslice::State;
+use crate::aes_low_level::fixslice::LANES;
+use crate::pot::self_test;
+use crate::pot::MAX_VERIFIER_PARALLELISM;
+use crate::pot::MIN_VERIFIER_PARALLELISM;
+use crate::round_keys::RoundKeys;
+use crate::utils::SelfTestFailed;
+use crate::Block;
+use crate::BLOCK_SIZE;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use zeroize::Zeroize;
+
+pub struct SoftwareFixSlicingKeys {
+    keys_enc: [State; 11],
+    keys_dec: [State; 11],
+}
+
+impl SoftwareFixSlicingKeys {
+    /// Key expansion is constant-time as well
+    pub fn new(id: &Block) -> Self {
+        let (mut round_keys_enc, mut round_keys_dec) = fixslice::expand_keys_128(id);
+        let keys_enc = fixslice::enc_keys(&round_keys_enc);
+        let keys_dec = fixslice::dec_keys(&round_keys_dec);
+        round_keys_enc.zeroize();
+        round_keys_dec.zeroize();
+
+        Self { keys_enc, keys_dec }
+    }
+
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let keys_enc = fixslice::enc_keys(round_keys.enc());
+        let keys_dec = fixslice::dec_keys(round_keys.dec());
+
+        Self { keys_enc, keys_dec }
+    }
+}
+
+impl Drop for SoftwareFixSlicingKeys {
+    fn drop(&mut self) {
+        self.keys_enc.zeroize();
+        self.keys_dec.zeroize();
+    }
+}
+
+#[derive(Default)]
+pub struct SoftwareFixSlicing;
+
+impl SoftwareFixSlicing {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = SoftwareFixSlicingKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+        let proof = self.prove(
+            &self_test::SEED,
+            &keys,
+            self_test::AES_ITERATIONS,
+            self_test::VERIFIER_PARALLELISM,
+        );
+
+        self_test::check(&proof, |proof| {
+            self.verify(proof, &self_test::SEED, &keys, self_test::AES_ITERATIONS)
+        })
+    }
+
+    /// Arbitrary length proof-of-time purely in software (using constant-time fixsliced AES)
+    pub fn prove(
+        &self,
+        seed: &Block,
+        keys: &SoftwareFixSlicingKeys,
+        aes_iterations: usize,
+        verifier_parallelism: usize,
+    ) -> Vec<u8> {
+        assert!(
+            aes_iterations.is_multiple_of(12)
+                && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        );
+        assert!(
+            verifier_parallelism == MIN_VERIFIER_PARALLELISM
+                || verifier_parallelism == 8
+                || verifier_parallelism == 12
+                || verifier_parallelism == MAX_VERIFIER_PARALLELISM
+        );
+
+        let inner_iterations = aes_iterations / verifier_parallelism;
+
+        let mut result = Vec::<u8>::with_capacity(verifier_parallelism * BLOCK_SIZE);
+        // Only the first lane is used, state stays bitsliced between checkpoints
+        let mut blocks = [[0u8; BLOCK_SIZE]; LANES];
+        blocks[0] = *seed;
+        let mut state = fixslice::bitslice(&blocks);
+
+        for _ in 0..verifier_parallelism {
+            for _ in 0..inner_iterations {
+                fixslice::encrypt(&keys.keys_enc, &mut state);
+            }
+            let [block, ..] = fixslice::un_bitslice(&state);
+            result.extend_from_slice(&block);
+        }
+
+        result
+    }
+
+    /// Arbitrary length proof-of-time verification purely in software (using constant-time
+    /// fixsliced AES, 4 blocks at a time)
+    pub fn verify(
+        &self,
+        proof: &[u8],
+        seed: &Block,
+        keys: &SoftwareFixSlicingKeys,
+        aes_iterations: usize,
+    ) -> bool {
+        assert!(proof.len().is_multiple_of(BLOCK_SIZE));
+        assert!(
+            aes_iterations.is_multiple_of(12)
+                && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        );
+        let verifier_parallelism = proof.len() / BLOCK_SIZE;
+        assert!(
+            verifier_parallelism == MIN_VERIFIER_PARALLELISM
+                || verifier_parallelism == 8
+                || verifier_parallelism == 12
+                || verifier_parallelism == MAX_VERIFIER_PARALLELISM
+        );
+
+        let inner_iterations = aes_iterations / verifier_parallelism;
+
+        let mut previous: Block = *seed;
+
+        proof
+            .chunks_exact(BLOCK_SIZE * LANES)
+            .all(|blocks| -> bool {
+                let mut proof_blocks = [[0u8; BLOCK_SIZE]; LANES];
+                for (proof_block, block) in
+                    proof_blocks.iter_mut().zip(blocks.chunks_exact(BLOCK_SIZE))
+                {
+                    proof_block.copy_from_slice(block);
+                }
+
+                let mut expected = [previous; LANES];
+                expected[1..].copy_from_slice(&proof_blocks[..(LANES - 1)]);
+                previous = blocks[(blocks.len() - BLOCK_SIZE)..].try_into().unwrap();
+
+                let mut state = fixslice::bitslice(&proof_blocks);
+                for _ in 0..inner_iterations {
+                    fixslice::decrypt(&keys.keys_dec, &mut state);
+                }
+
+                fixslice::un_bitslice(&state) == expected
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pot::test_data::CORRECT_PROOF_12;
+    use crate::pot::test_data::CORRECT_PROOF_16;
+    use crate::pot::test_data::ID;
+    use crate::pot::test_data::SEED;
+    use rand::Rng;
+
+    #[test]
+    fn test() {
+        let aes_iterations = 288;
+
+        let keys = SoftwareFixSlicingKeys::new(&ID);
+        let pot = SoftwareFixSlicing::new();
+
+        let proof = pot.prove(&SEED, &keys, aes_iterations, 16);
+        assert_eq!(proof, CORRECT_PROOF_16.to_vec());
+
+        let proof = pot.prove(&SEED, &keys, aes_iterations, 12);
+        assert_eq!(proof, CORRECT_PROOF_12.to_vec());
+
+        assert!(pot.verify(&CORRECT_PROOF_16, &SEED, &keys, aes_iterations));
+        assert!(pot.verify(&CORRECT_PROOF_12, &SEED, &keys, aes_iterations));
+
+        assert!(!pot.verify(&vec![42; 16 * BLOCK_SIZE], &SEED, &keys, aes_iterations));
+    }
+
+    #[test]
+    fn test_random() {
+        let aes_iterations = 288;
+
+        let mut key = [0u8; 16];
+        rand::thread_rng().fill(&mut key[..]);
+
+        let mut seed = [0u8; 16];
+        rand::thread_rng().fill(&mut seed[..]);
+
+        let keys = SoftwareFixSlicingKeys::from_round_keys(&RoundKeys::new(&key));
+        let pot = SoftwareFixSlicing::new();
+
+        for &verifier_parallelism in [4, 8, 12, 16].iter() {
+            let mut proof = pot.prove(&seed, &keys, aes_iterations, verifier_parallelism);
+            assert_eq!(proof.len(), verifier_parallelism * BLOCK_SIZE);
+            assert!(pot.verify(&proof, &seed, &keys, aes_iterations));
+
+            let last = proof.len() - 1;
+            proof[last] ^= 1;
+            assert!(!pot.verify(&proof, &seed, &keys, aes_iterations));
+        }
+    }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(SoftwareFixSlicing::new().self_test(), Ok(()));
+    }
+}
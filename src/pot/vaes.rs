@@ -1,10 +1,16 @@
 use crate::aes_low_level::vaes;
 use crate::pot::aes_ni::AesNi;
 use crate::pot::aes_ni::AesNiKeys;
+use crate::pot::self_test;
 use crate::pot::MAX_VERIFIER_PARALLELISM;
 use crate::pot::MIN_VERIFIER_PARALLELISM;
+use crate::round_keys::RoundKeys;
+use crate::utils::AesImplementation;
+use crate::utils::SelfTestFailed;
+use crate::utils::UnsupportedCpu;
 use crate::Block;
 use crate::BLOCK_SIZE;
+use alloc::vec::Vec;
 
 // TODO: This should use keys expanded using AES-NI
 pub struct VAesKeys {
@@ -12,8 +18,15 @@ pub struct VAesKeys {
 }
 
 impl VAesKeys {
-    pub fn new(id: &Block) -> Self {
-        let aes_ni = AesNiKeys::new(id);
+    /// Returns an error if AES-NI is not supported by this CPU
+    pub fn new(id: &Block) -> Result<Self, UnsupportedCpu> {
+        let aes_ni = AesNiKeys::new(id)?;
+        Ok(Self { aes_ni })
+    }
+
+    /// Only loads round keys into registers, doesn't require AES-NI
+    pub fn from_round_keys(round_keys: &RoundKeys) -> Self {
+        let aes_ni = AesNiKeys::from_round_keys(round_keys);
         Self { aes_ni }
     }
 }
@@ -23,9 +36,26 @@ pub struct VAes {
 }
 
 impl VAes {
-    pub fn new() -> Self {
-        let aes_ni = AesNi::new();
-        Self { aes_ni }
+    /// Returns an error if VAES or AES-NI is not supported by this CPU
+    pub fn new() -> Result<Self, UnsupportedCpu> {
+        crate::utils::ensure_available(AesImplementation::VAes)?;
+        let aes_ni = AesNi::new()?;
+        Ok(Self { aes_ni })
+    }
+
+    /// Known-answer self-test with reduced number of iterations
+    pub fn self_test(&self) -> Result<(), SelfTestFailed> {
+        let keys = VAesKeys::from_round_keys(&RoundKeys::new(&self_test::ID));
+        let proof = self.prove(
+            &self_test::SEED,
+            &keys,
+            self_test::AES_ITERATIONS,
+            self_test::VERIFIER_PARALLELISM,
+        );
+
+        self_test::check(&proof, |proof| {
+            self.verify(proof, &self_test::SEED, &keys, self_test::AES_ITERATIONS)
+        })
     }
 
     pub fn prove(
@@ -50,8 +80,11 @@ impl VAes {
     ) -> bool {
         let pipelining_parallelism = 12;
 
-        assert!(proof.len() % BLOCK_SIZE == 0);
-        assert!(aes_iterations % 12 == 0 && aes_iterations % MAX_VERIFIER_PARALLELISM == 0);
+        assert!(proof.len().is_multiple_of(BLOCK_SIZE));
+        assert!(
+            aes_iterations.is_multiple_of(12)
+                && aes_iterations.is_multiple_of(MAX_VERIFIER_PARALLELISM)
+        );
         let verifier_parallelism = proof.len() / BLOCK_SIZE;
         assert!(
             verifier_parallelism == MIN_VERIFIER_PARALLELISM
@@ -64,49 +97,45 @@ impl VAes {
 
         let mut previous = seed.as_ref();
 
-        let iterator = proof.chunks_exact(BLOCK_SIZE * pipelining_parallelism);
+        let mut iterator = proof.chunks_exact(BLOCK_SIZE * pipelining_parallelism);
         let remainder = iterator.remainder();
-        let result = iterator
-            .map(|blocks| -> bool {
-                let expected_first_block = previous;
-                previous = &blocks[(blocks.len() - BLOCK_SIZE)..];
-
-                vaes::pot_verify_pipelined_x12_low_level(
-                    &keys.aes_ni.keys_dec,
-                    expected_first_block,
-                    blocks,
-                    inner_iterations,
-                )
-            })
-            .fold(true, |a, b| a && b);
+        let result = iterator.all(|blocks| -> bool {
+            let expected_first_block = previous;
+            previous = &blocks[(blocks.len() - BLOCK_SIZE)..];
+
+            vaes::pot_verify_pipelined_x12_low_level(
+                &keys.aes_ni.keys_dec,
+                expected_first_block,
+                blocks,
+                inner_iterations,
+            )
+        });
 
         if !result || remainder.is_empty() {
             return result;
         }
 
-        let iterator = remainder.chunks_exact(BLOCK_SIZE * 8);
+        let mut iterator = remainder.chunks_exact(BLOCK_SIZE * 8);
         let remainder = iterator.remainder();
-        let result = iterator
-            .map(|blocks| {
-                let expected_first_block = previous;
-                previous = &blocks[(blocks.len() - BLOCK_SIZE)..];
-
-                vaes::pot_verify_pipelined_x8_low_level(
-                    &keys.aes_ni.keys_dec,
-                    expected_first_block,
-                    blocks,
-                    inner_iterations,
-                )
-            })
-            .fold(true, |a, b| a && b);
+        let result = iterator.all(|blocks| {
+            let expected_first_block = previous;
+            previous = &blocks[(blocks.len() - BLOCK_SIZE)..];
+
+            vaes::pot_verify_pipelined_x8_low_level(
+                &keys.aes_ni.keys_dec,
+                expected_first_block,
+                blocks,
+                inner_iterations,
+            )
+        });
 
         if !result || remainder.is_empty() {
             return result;
         }
 
         remainder
-            .chunks_exact(MIN_VERIFIER_PARALLELISM)
-            .map(|blocks| {
+            .chunks_exact(BLOCK_SIZE * MIN_VERIFIER_PARALLELISM)
+            .all(|blocks| {
                 let expected_first_block = previous;
                 previous = &blocks[(blocks.len() - BLOCK_SIZE)..];
 
@@ -117,7 +146,6 @@ impl VAes {
                     inner_iterations,
                 )
             })
-            .fold(true, |a, b| a && b)
     }
 }
 
@@ -140,8 +168,8 @@ mod tests {
         let aes_iterations = 288;
         let verifier_parallelism = 12;
 
-        let keys = VAesKeys::new(&ID);
-        let pot = VAes::new();
+        let keys = VAesKeys::new(&ID).unwrap();
+        let pot = VAes::new().unwrap();
 
         let proof = pot.prove(&SEED, &keys, aes_iterations, verifier_parallelism);
         assert_eq!(proof.len(), verifier_parallelism * BLOCK_SIZE);
@@ -172,12 +200,17 @@ mod tests {
         let mut seed = [0u8; 16];
         rand::thread_rng().fill(&mut seed[..]);
 
-        let keys = VAesKeys::new(&key);
-        let pot = VAes::new();
+        let keys = VAesKeys::new(&key).unwrap();
+        let pot = VAes::new().unwrap();
 
         let proof = pot.prove(&seed, &keys, aes_iterations, verifier_parallelism);
         assert_eq!(proof.len(), verifier_parallelism * BLOCK_SIZE);
 
         assert!(pot.verify(&proof, &seed, &keys, aes_iterations));
     }
+
+    #[test]
+    fn test_self_test() {
+        assert_eq!(VAes::new().unwrap().self_test(), Ok(()));
+    }
 }
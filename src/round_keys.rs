@@ -0,0 +1,145 @@
+//! Portable AES-128 round keys shared by all backends
+
+use crate::aes_low_level::software;
+use crate::Block;
+use zeroize::Zeroize;
+
+/// AES-128 round keys for encryption and decryption (the latter for equivalent inverse cipher, in
+/// the same order as encryption keys), each round key is stored as bytes in the order AES uses
+/// them.
+///
+/// Keys are derived once per id with `RoundKeys::new()` and can then be converted into the form
+/// any backend needs with `from_round_keys()` constructors of backend-specific keys.
+#[derive(Clone)]
+pub struct RoundKeys {
+    enc: [Block; 11],
+    dec: [Block; 11],
+}
+
+impl RoundKeys {
+    /// Expands id into round keys, works on any platform
+    pub fn new(id: &Block) -> Self {
+        let enc = software::expand_keys_aes_128_enc(id);
+        let dec = software::expand_keys_aes_128_dec(id);
+        Self { enc, dec }
+    }
+
+    /// Round keys for encryption
+    pub fn enc(&self) -> &[Block; 11] {
+        &self.enc
+    }
+
+    /// Round keys for decryption
+    pub fn dec(&self) -> &[Block; 11] {
+        &self.dec
+    }
+
+    /// Id these round keys were derived from, which is the first round key of AES-128
+    pub fn id(&self) -> &Block {
+        &self.enc[0]
+    }
+}
+
+impl Drop for RoundKeys {
+    fn drop(&mut self) {
+        self.enc.zeroize();
+        self.dec.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::ManuallyDrop;
+    use rand::Rng;
+
+    #[test]
+    fn test() {
+        // FIPS-197 Appendix A.1
+        let key = [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ];
+        let last_round_key = [
+            0xd0, 0x14, 0xf9, 0xa8, 0xc9, 0xee, 0x25, 0x89, 0xe1, 0x3f, 0x0c, 0xc8, 0xb6, 0x63,
+            0x0c, 0xa6,
+        ];
+
+        let round_keys = RoundKeys::new(&key);
+
+        assert_eq!(round_keys.id(), &key);
+        assert_eq!(round_keys.enc()[0], key);
+        assert_eq!(round_keys.enc()[10], last_round_key);
+        // First and last decryption round keys are not transformed
+        assert_eq!(round_keys.dec()[0], key);
+        assert_eq!(round_keys.dec()[10], last_round_key);
+    }
+
+    #[test]
+    fn test_words() {
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill(&mut id[..]);
+
+        let round_keys = RoundKeys::new(&id);
+
+        let mut keys_enc = [0u32; 44];
+        aes_frast::aes_core::setkey_enc_k128(&id, &mut keys_enc);
+        let mut keys_dec = [0u32; 44];
+        aes_frast::aes_core::setkey_dec_k128(&id, &mut keys_dec);
+
+        assert_eq!(
+            software::round_keys_to_words(round_keys.enc()).to_vec(),
+            keys_enc.to_vec(),
+        );
+        assert_eq!(
+            software::round_keys_to_words(round_keys.dec()).to_vec(),
+            keys_dec.to_vec(),
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_against_aes_ni() {
+        use crate::aes_low_level::aes_ni;
+        use crate::utils;
+        use crate::utils::AesImplementation;
+
+        if !utils::aes_implementations_available().contains(&AesImplementation::AesNi) {
+            println!("AES-NI support not available, skipping test");
+            return;
+        }
+
+        for _ in 0..100 {
+            let mut id = [0u8; 16];
+            rand::thread_rng().fill(&mut id[..]);
+
+            let round_keys = RoundKeys::new(&id);
+            let (keys_enc, keys_dec) = unsafe { aes_ni::expand(&id) };
+
+            assert_eq!(
+                aes_ni::round_keys_from_expanded_keys(&keys_enc),
+                round_keys.enc
+            );
+            assert_eq!(
+                aes_ni::round_keys_from_expanded_keys(&keys_dec),
+                round_keys.dec
+            );
+
+            // Lossless in both directions
+            let keys_enc = aes_ni::expanded_keys_from_round_keys(round_keys.enc());
+            assert_eq!(
+                aes_ni::round_keys_from_expanded_keys(&keys_enc),
+                round_keys.enc
+            );
+        }
+    }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        let mut round_keys = ManuallyDrop::new(RoundKeys::new(&[1u8; 16]));
+        unsafe { ManuallyDrop::drop(&mut round_keys) };
+
+        assert_eq!(round_keys.enc, [[0u8; 16]; 11]);
+        assert_eq!(round_keys.dec, [[0u8; 16]; 11]);
+    }
+}
@@ -1,22 +1,90 @@
-use std::collections::HashSet;
+use alloc::collections::BTreeSet;
+use core::fmt;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum AesImplementation {
+    /// AES-NI instruction set
+    AesNi,
     /// AVX-512 Vector AES instruction set
     VAes,
 }
 
+/// Detects CPU features at runtime with `std` (tests always link it) and at compile time (enabled
+/// target features) without it
+#[cfg(target_arch = "x86_64")]
+macro_rules! x86_features_detected {
+    ($($feature:tt),+) => {{
+        #[cfg(any(feature = "std", test))]
+        {
+            $(is_x86_feature_detected!($feature))&&+
+        }
+        #[cfg(not(any(feature = "std", test)))]
+        {
+            cfg!(all($(target_feature = $feature),+))
+        }
+    }};
+}
+
 /// Inspect what special instruction sets are available for AES on this machine (software fallback
 /// is always available regardless)
-pub fn aes_implementations_available() -> HashSet<AesImplementation> {
-    let mut implementations = HashSet::<AesImplementation>::new();
+pub fn aes_implementations_available() -> BTreeSet<AesImplementation> {
+    [AesImplementation::AesNi, AesImplementation::VAes]
+        .iter()
+        .copied()
+        .filter(|&implementation| is_available(implementation))
+        .collect()
+}
 
+/// Doesn't allocate, so it is cheap enough to be called before every low-level call (results of
+/// runtime detection are cached by the standard library)
+fn is_available(implementation: AesImplementation) -> bool {
     #[cfg(target_arch = "x86_64")]
     {
-        if is_x86_feature_detected!("avx512vaes") {
-            implementations.insert(AesImplementation::VAes);
+        match implementation {
+            AesImplementation::AesNi => x86_features_detected!("aes", "sse2"),
+            AesImplementation::VAes => x86_features_detected!("avx512f", "vaes"),
         }
     }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let _ = implementation;
+        false
+    }
+}
 
-    return implementations;
+/// Error returned by constructors of implementations that need special instruction sets when those
+/// are not available on this machine
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnsupportedCpu(pub AesImplementation);
+
+impl fmt::Display for UnsupportedCpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not supported by this CPU", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnsupportedCpu {}
+
+/// Error returned by self-tests when implementation produces wrong results for known-answer test,
+/// which indicates faulty hardware, driver bug or miscompilation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SelfTestFailed;
+
+impl fmt::Display for SelfTestFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Known-answer self-test failed")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SelfTestFailed {}
+
+/// Returns an error if specified implementation is not available on this machine
+pub(crate) fn ensure_available(implementation: AesImplementation) -> Result<(), UnsupportedCpu> {
+    if is_available(implementation) {
+        Ok(())
+    } else {
+        Err(UnsupportedCpu(implementation))
+    }
 }